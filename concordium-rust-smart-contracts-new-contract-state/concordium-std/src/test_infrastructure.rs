@@ -69,7 +69,13 @@
 //! ```
 use crate::*;
 
-use self::trie::StateTrie;
+use self::{
+    chain::ChainLink,
+    energy::EnergyMeter,
+    trie::{StateTrie, TrieSnapshot},
+};
+#[cfg(not(feature = "std"))]
+use crate::alloc::format;
 use crate::{
     boxed::Box,
     cell::RefCell,
@@ -80,8 +86,17 @@ use crate::{
 };
 use convert::TryInto;
 
+mod chain;
+mod clock;
+mod energy;
 mod trie;
 
+pub use self::{
+    chain::{TestChain, TestChainCallResult},
+    clock::TestClock,
+    energy::{TestEnergyCosts, TestEnergyReport},
+};
+
 /// Placeholder for the context chain meta data.
 /// All the fields are optionally set and the getting an unset field will result
 /// in test failing.
@@ -91,9 +106,13 @@ mod trie;
 /// Use only in unit tests!
 ///
 /// Defaults to having all of the fields unset
+///
+/// The slot time can also be taken from a [`TestClock`] that is shared with
+/// other contexts, so that moving the clock forward moves all of them.
 #[derive(Default, Clone)]
 pub struct TestChainMeta {
     pub(crate) slot_time: Option<SlotTime>,
+    pub(crate) clock:     Option<TestClock>,
 }
 
 /// Policy type used by init and receive contexts for testing.
@@ -301,11 +320,28 @@ impl TestChainMeta {
     /// the fields will result in [`fail!`](../macro.fail.html).
     pub fn empty() -> Self { Default::default() }
 
-    /// Set the block slot time
+    /// Set the block slot time.
+    /// If a clock is set, this moves the clock to a new block with the given
+    /// slot time, which will [`fail!`](../macro.fail.html) if it is before the
+    /// current slot time of the clock.
     pub fn set_slot_time(&mut self, value: SlotTime) -> &mut Self {
-        self.slot_time = Some(value);
+        match &self.clock {
+            Some(clock) => {
+                clock.set_slot_time(value);
+            }
+            None => self.slot_time = Some(value),
+        }
         self
     }
+
+    /// Take the block slot time from the given clock from now on.
+    pub fn set_clock(&mut self, clock: TestClock) -> &mut Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Get the clock the block slot time is taken from, if one is set.
+    pub fn clock(&self) -> Option<&TestClock> { self.clock.as_ref() }
 }
 
 impl<'a, C> TestContext<'a, C> {
@@ -343,6 +379,13 @@ impl<'a, C> TestContext<'a, C> {
         self.metadata_mut().set_slot_time(value);
         self
     }
+
+    /// Take the metadata block slot time from the given clock, which can be
+    /// shared with other contexts.
+    pub fn set_clock(&mut self, clock: TestClock) -> &mut Self {
+        self.metadata_mut().set_clock(clock);
+        self
+    }
 }
 
 impl<'a> TestInitContext<'a> {
@@ -402,7 +445,12 @@ fn unwrap_ctx_field<A>(opt: Option<A>, name: &str) -> A {
 
 // Getters for testing-context
 impl HasChainMetadata for TestChainMeta {
-    fn slot_time(&self) -> SlotTime { unwrap_ctx_field(self.slot_time, "metadata.slot_time") }
+    fn slot_time(&self) -> SlotTime {
+        match &self.clock {
+            Some(clock) => clock.slot_time(),
+            None => unwrap_ctx_field(self.slot_time, "metadata.slot_time"),
+        }
+    }
 }
 
 impl HasPolicy for TestPolicy {
@@ -477,14 +525,164 @@ impl<'a> HasParameter for Cursor<&'a [u8]> {
 
 /// A logger that simply accumulates all the logged items to be inspected at the
 /// end of execution.
+///
+/// The logged items can be decoded with `events`, and checked with
+/// `assert_logged`, `assert_events` and `assert_events_unordered`. When a check
+/// fails, the expected and logged events are shown using their `Debug`
+/// implementation, or, with the `derive-serde` feature, as JSON using the
+/// schema set with `set_event_schema`.
+///
+/// # Example
+/// ```rust
+/// # use concordium_std::*;
+/// # use concordium_std::test_infrastructure::*;
+/// let mut logger = TestLogger::init();
+/// logger.log(&1u32).expect_report("Logging failed");
+/// logger.log(&2u32).expect_report("Logging failed");
+///
+/// assert_eq!(logger.events::<u32>(), vec![1, 2]);
+/// logger.assert_logged(&2u32);
+/// logger.assert_events(&[1u32, 2]);
+/// logger.assert_events_unordered(&[2u32, 1]);
+/// ```
 pub struct TestLogger {
-    pub logs: Vec<Vec<u8>>,
+    pub logs:     Vec<Vec<u8>>,
+    /// Schema used to show the events when a check fails.
+    #[cfg(feature = "derive-serde")]
+    event_schema: Option<schema::Type>,
+}
+
+impl TestLogger {
+    /// Decode all the logged events as `T`, in the order they were logged.
+    /// Will [fail] if an event cannot be decoded as `T`, including when bytes
+    /// are left over after decoding it.
+    pub fn events<T: Deserial>(&self) -> Vec<T> {
+        self.logs
+            .iter()
+            .enumerate()
+            .map(|(i, log)| match Self::decode_event(log) {
+                Some(event) => event,
+                None => fail!("Logged event {} could not be decoded: {:02x?}", i, log),
+            })
+            .collect()
+    }
+
+    /// Decode an event as `T`, which must use all of its bytes.
+    fn decode_event<T: Deserial>(event: &[u8]) -> Option<T> {
+        let mut cursor = Cursor::new(event);
+        let decoded = T::deserial(&mut cursor).ok()?;
+        if cursor.offset != event.len() {
+            return None;
+        }
+        Some(decoded)
+    }
+
+    /// Set the schema of the events, used to show them when a check fails.
+    #[cfg(feature = "derive-serde")]
+    pub fn set_event_schema(&mut self, schema: schema::Type) -> &mut Self {
+        self.event_schema = Some(schema);
+        self
+    }
+
+    /// Check that `event` was logged, in any position. Will [fail] otherwise,
+    /// showing the events that were logged.
+    pub fn assert_logged<T: Serial + Deserial + fmt::Debug>(&self, event: &T) {
+        let expected = to_bytes(event);
+        if !self.logs.contains(&expected) {
+            let mut message = format!(
+                "Event was not logged:\n  - expected: {}\nLogged events:",
+                self.show_event::<T>(&expected)
+            );
+            for (i, log) in self.logs.iter().enumerate() {
+                message.push_str(&format!("\n  {}: {}", i, self.show_event::<T>(log)));
+            }
+            fail!("{}", message);
+        }
+    }
+
+    /// Check that exactly the given events were logged, in the given order.
+    /// Will [fail] otherwise, showing the expected and logged events that
+    /// differ.
+    pub fn assert_events<T: Serial + Deserial + fmt::Debug>(&self, events: &[T]) {
+        let expected: Vec<Vec<u8>> = events.iter().map(to_bytes).collect();
+        if expected != self.logs {
+            let mut message = format!(
+                "Logged events differ from the expected events ({} expected, {} logged):",
+                expected.len(),
+                self.logs.len()
+            );
+            for i in 0..cmp::max(expected.len(), self.logs.len()) {
+                let (expected, logged) = (expected.get(i), self.logs.get(i));
+                if expected == logged {
+                    continue;
+                }
+                message.push_str(&format!(
+                    "\nevent {}:\n  - expected: {}\n  + logged:   {}",
+                    i,
+                    self.show_optional_event::<T>(expected),
+                    self.show_optional_event::<T>(logged)
+                ));
+            }
+            fail!("{}", message);
+        }
+    }
+
+    /// Check that exactly the given events were logged, in any order. Will
+    /// [fail] otherwise, showing the expected events that were not logged and
+    /// the logged events that were not expected.
+    pub fn assert_events_unordered<T: Serial + Deserial + fmt::Debug>(&self, events: &[T]) {
+        let mut unexpected: Vec<&Vec<u8>> = self.logs.iter().collect();
+        let mut missing = Vec::new();
+        for event in events.iter().map(to_bytes) {
+            match unexpected.iter().position(|log| **log == event) {
+                Some(i) => {
+                    unexpected.remove(i);
+                }
+                None => missing.push(event),
+            }
+        }
+        if !missing.is_empty() || !unexpected.is_empty() {
+            let mut message =
+                String::from("Logged events differ from the expected events, in any order:");
+            for event in missing.iter() {
+                message.push_str(&format!("\n  - expected: {}", self.show_event::<T>(event)));
+            }
+            for log in unexpected {
+                message.push_str(&format!("\n  + logged:   {}", self.show_event::<T>(log)));
+            }
+            fail!("{}", message);
+        }
+    }
+
+    fn show_optional_event<T: Deserial + fmt::Debug>(&self, event: Option<&Vec<u8>>) -> String {
+        match event {
+            Some(event) => self.show_event::<T>(event),
+            None => String::from("(none)"),
+        }
+    }
+
+    /// Show an event as JSON using the event schema if it is set, or else
+    /// decoded as `T`.
+    fn show_event<T: Deserial + fmt::Debug>(&self, event: &[u8]) -> String {
+        #[cfg(feature = "derive-serde")]
+        if let Some(json) =
+            self.event_schema.as_ref().and_then(|schema| schema.to_json_string_pretty(event).ok())
+        {
+            return json;
+        }
+        match Self::decode_event::<T>(event) {
+            Some(event) => format!("{:?}", event),
+            None => format!("{:02x?} (could not be decoded)", event),
+        }
+    }
 }
 
 impl HasLogger for TestLogger {
     fn init() -> Self {
         Self {
             logs: Vec::new(),
+            #[cfg(feature = "derive-serde")]
+            event_schema: None,
         }
     }
 
@@ -600,6 +798,7 @@ pub struct TestStateEntry {
     pub(crate) cursor:         Cursor<Rc<RefCell<TestStateEntryData>>>,
     pub(crate) key:            Vec<u8>,
     pub(crate) state_entry_id: StateEntryId,
+    pub(crate) meter:          Rc<EnergyMeter>,
 }
 
 impl TestStateEntry {
@@ -612,8 +811,19 @@ impl TestStateEntry {
             cursor: Cursor::new(data),
             key,
             state_entry_id,
+            meter: Default::default(),
         }
     }
+
+    /// Resize the data without counting it as a call to the host.
+    fn resize_data(&mut self, new_size: u32) -> Result<(), TestStateError> {
+        let new_size = new_size as usize;
+        self.cursor.data.borrow_mut().data_mut()?.resize(new_size, 0);
+        if self.cursor.offset > new_size {
+            self.cursor.offset = new_size;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -627,24 +837,32 @@ impl HasStateApi for TestStateApi {
     type IterType = trie::TestStateIter;
 
     fn create_entry(&mut self, key: &[u8]) -> Result<Self::EntryType, StateError> {
+        self.meter().host_call();
         self.trie.borrow_mut().create_entry(key)
     }
 
-    fn lookup_entry(&self, key: &[u8]) -> Option<Self::EntryType> { self.trie.borrow().lookup(key) }
+    fn lookup_entry(&self, key: &[u8]) -> Option<Self::EntryType> {
+        self.meter().host_call();
+        self.trie.borrow().lookup(key)
+    }
 
     fn delete_entry(&mut self, entry: Self::EntryType) -> Result<(), StateError> {
+        self.meter().host_call();
         self.trie.borrow_mut().delete_entry(entry)
     }
 
     fn delete_prefix(&mut self, prefix: &[u8]) -> Result<bool, StateError> {
+        self.meter().host_call();
         self.trie.borrow_mut().delete_prefix(prefix)
     }
 
     fn iterator(&self, prefix: &[u8]) -> Result<Self::IterType, StateError> {
+        self.meter().host_call();
         self.trie.borrow().iterator(prefix)
     }
 
     fn delete_iterator(&mut self, iter: Self::IterType) {
+        self.meter().host_call();
         self.trie.borrow_mut().delete_iterator(iter);
     }
 }
@@ -670,6 +888,19 @@ impl TestStateApi {
     }
 }
 
+impl TestStateApi {
+    /// Copy the data of all the entries in the state.
+    pub(crate) fn snapshot(&self) -> TrieSnapshot { self.trie.borrow().snapshot() }
+
+    /// Put back the data of the entries as it was in the snapshot.
+    pub(crate) fn restore(&self, snapshot: &TrieSnapshot) {
+        self.trie.borrow_mut().restore(snapshot)
+    }
+
+    /// The meter for the operations on the state.
+    pub(crate) fn meter(&self) -> Rc<EnergyMeter> { Rc::clone(&self.trie.borrow().meter) }
+}
+
 impl Default for TestStateApi {
     fn default() -> Self { Self::new() }
 }
@@ -694,15 +925,16 @@ impl HasStateEntry for TestStateEntry {
     /// Get the size of the data in the entry.
     /// Returns an error if the entry has been deleted with delete_prefix.
     fn size(&self) -> Result<u32, Self::Error> {
+        self.meter.host_call();
         Ok(self.cursor.data.borrow().data()?.len() as u32)
     }
 
     /// Truncate the entry.
     /// Returns an error if the entry has been deleted with delete_prefix.
     fn truncate(&mut self, new_size: u32) -> Result<(), Self::Error> {
-        let cur_size = self.size()?;
-        if cur_size > new_size {
-            self.resize(new_size)?;
+        self.meter.host_call();
+        if self.cursor.data.borrow().data()?.len() > new_size as usize {
+            self.resize_data(new_size)?;
         }
         Ok(())
     }
@@ -713,12 +945,8 @@ impl HasStateEntry for TestStateEntry {
     /// Resize the entry.
     /// Returns an error if the entry has been deleted with delete_prefix.
     fn resize(&mut self, new_size: u32) -> Result<(), Self::Error> {
-        let new_size = new_size as usize;
-        self.cursor.data.borrow_mut().data_mut()?.resize(new_size, 0);
-        if self.cursor.offset > new_size {
-            self.cursor.offset = new_size;
-        }
-        Ok(())
+        self.meter.host_call();
+        self.resize_data(new_size)
     }
 }
 
@@ -728,6 +956,7 @@ impl Read for TestStateEntry {
         if len > buf.len() {
             len = buf.len();
         }
+        self.meter.state_read(len);
         if len > 0 {
             buf[0..len].copy_from_slice(
                 &self.cursor.data.borrow().data()?[self.cursor.offset..self.cursor.offset + len],
@@ -746,11 +975,12 @@ impl Write for TestStateEntry {
     fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Err> {
         let end = self.cursor.offset + buf.len();
         if self.cursor.data.borrow().data()?.len() < end {
-            self.resize(end.try_into()?)?;
+            self.resize_data(end.try_into()?)?;
         }
         let mut cursor_data = self.cursor.data.as_ref().borrow_mut();
         let data = &mut cursor_data.data_mut()?[self.cursor.offset..];
         let to_write = cmp::min(data.len(), buf.len());
+        self.meter.state_write(to_write);
         data[..to_write].copy_from_slice(&buf[..to_write]);
         self.cursor.offset += to_write;
         Ok(to_write)
@@ -967,6 +1197,11 @@ pub struct TestHost<State> {
     state:            State,
     /// List of accounts that will cause a contract invocation to fail.
     missing_accounts: BTreeSet<AccountAddress>,
+    /// The chain the instance is executing on, if it is run by a
+    /// [`TestChain`]. Calls that are not mocked are routed to it.
+    chain:            Option<ChainLink>,
+    /// The clock shared by the contexts made with `receive_context`.
+    clock:            TestClock,
 }
 
 impl<State: Serial + DeserialWithState<TestStateApi>> HasHost<State> for TestHost<State> {
@@ -979,35 +1214,43 @@ impl<State: Serial + DeserialWithState<TestStateApi>> HasHost<State> for TestHos
     /// By default, all accounts are assumed to exist, and transfers to them
     /// will succeed (provided sufficient balance).
     /// Use `make_account_missing` to test out transfers to accounts not on
-    /// chain.
+    /// chain. When the host is run by a [`TestChain`], only the accounts on
+    /// the chain exist, and the amount is added to the balance of the
+    /// receiver.
     ///
     /// Possible errors:
     ///   - [TransferError::AmountTooLarge]: Contract has insufficient funds.
     ///   - [TransferError::MissingAccount]: Attempted transfer to an account
-    ///     set as missing with `make_account_missing`.
+    ///     set as missing with `make_account_missing`, or not on the chain.
     fn invoke_transfer(&self, receiver: &AccountAddress, amount: Amount) -> TransferResult {
-        if self.missing_accounts.contains(receiver) {
+        let missing_on_chain = match &self.chain {
+            Some(link) => !link.chain.has_account(receiver),
+            None => false,
+        };
+        self.state_builder.state_api.meter().transfer();
+        if self.missing_accounts.contains(receiver) || missing_on_chain {
             return Err(TransferError::MissingAccount);
         }
         if amount.micro_ccd > 0 {
             if *self.contract_balance.borrow() >= amount {
                 *self.contract_balance.borrow_mut() -= amount;
-                self.transfers.borrow_mut().push((*receiver, amount));
-                Ok(())
             } else {
-                Err(TransferError::AmountTooLarge)
+                return Err(TransferError::AmountTooLarge);
             }
-        } else {
-            self.transfers.borrow_mut().push((*receiver, amount));
-            Ok(())
         }
+        self.transfers.borrow_mut().push((*receiver, amount));
+        if let Some(link) = &self.chain {
+            link.chain.credit_account(receiver, amount);
+        }
+        Ok(())
     }
 
     /// Invoke a contract entrypoint.
     ///
     /// This uses the mock entrypoints set up with
-    /// `setup_mock_entrypoint`. The method will [fail] with a panic
-    /// if no responses were set for the given contract address and method.
+    /// `setup_mock_entrypoint`. If there is no mock for the given contract
+    /// address and method, the call is routed to the [`TestChain`] running the
+    /// host, if any. Otherwise the method will [fail] with a panic.
     fn invoke_contract_raw(
         &mut self,
         to: &ContractAddress,
@@ -1016,7 +1259,19 @@ impl<State: Serial + DeserialWithState<TestStateApi>> HasHost<State> for TestHos
         amount: Amount,
     ) -> CallContractResult<Self::ReturnValueType> {
         self.commit_state();
-        let handler = match self.mocking_fns.get_mut(&(*to, OwnedEntrypointName::from(method))) {
+        let meter = self.state_builder.state_api.meter();
+        meter.contract_call(parameter.0.len());
+        let key = (*to, OwnedEntrypointName::from(method));
+        if let (false, Some(link)) = (self.mocking_fns.contains_key(&key), self.chain.clone()) {
+            let (state_modified, return_value) =
+                self.invoke_chain(&link, to, parameter, method, amount)?;
+            // Like on-chain, the state is reloaded if a reentrant call changed it.
+            if state_modified {
+                self.state = Self::load_state(&self.state_builder.state_api);
+            }
+            return Ok((state_modified, return_value));
+        }
+        let handler = match self.mocking_fns.get_mut(&key) {
             Some(handler) => handler,
             None => fail!(
                 "Mocking has not been set up for invoking contract {:?} with method '{}'.",
@@ -1046,14 +1301,18 @@ impl<State: Serial + DeserialWithState<TestStateApi>> HasHost<State> for TestHos
         if state_modified {
             self.commit_state();
         }
+        if let Some(return_value) = &res {
+            meter.return_value(return_value.data.len());
+        }
         Ok((state_modified, res))
     }
 
     /// Invoke a contract entrypoint.
     ///
     /// This uses the mock entrypoints set up with
-    /// `setup_mock_entrypoint`. The method will [fail] with a panic
-    /// if no responses were set for the given contract address and method.
+    /// `setup_mock_entrypoint`. If there is no mock for the given contract
+    /// address and method, the call is routed to the [`TestChain`] running the
+    /// host, if any. Otherwise the method will [fail] with a panic.
     fn invoke_contract_raw_read_only(
         &self,
         to: &ContractAddress,
@@ -1061,7 +1320,18 @@ impl<State: Serial + DeserialWithState<TestStateApi>> HasHost<State> for TestHos
         method: EntrypointName,
         amount: Amount,
     ) -> ReadOnlyCallContractResult<Self::ReturnValueType> {
-        let handler = match self.mocking_fns.get(&(*to, OwnedEntrypointName::from(method))) {
+        let meter = self.state_builder.state_api.meter();
+        meter.contract_call(parameter.0.len());
+        let key = (*to, OwnedEntrypointName::from(method));
+        if let (false, Some(link)) = (self.mocking_fns.contains_key(&key), &self.chain) {
+            let (state_modified, return_value) =
+                self.invoke_chain(link, to, parameter, method, amount)?;
+            if state_modified {
+                fail!("State modified in a read-only contract call.");
+            }
+            return Ok(return_value);
+        }
+        let handler = match self.mocking_fns.get(&key) {
             Some(handler) => handler,
             None => fail!(
                 "Mocking has not been set up for invoking contract {:?} with method '{}'.",
@@ -1075,17 +1345,7 @@ impl<State: Serial + DeserialWithState<TestStateApi>> HasHost<State> for TestHos
         }
 
         // The callee will see state that is stored at this point.
-        let mut state = match State::deserial_with_state(
-            &self.state_builder.state_api,
-            &mut self
-                .state_builder
-                .state_api
-                .lookup_entry(&[])
-                .expect_report("Could not lookup the state root."),
-        ) {
-            Ok(state) => state,
-            Err(e) => fail!("Failed to deserialize state: {:?}", e),
-        };
+        let mut state = meter.uncounted(|| Self::load_state(&self.state_builder.state_api));
 
         // Invoke the handler.
         let (state_modified, res) =
@@ -1097,6 +1357,9 @@ impl<State: Serial + DeserialWithState<TestStateApi>> HasHost<State> for TestHos
         if amount.micro_ccd > 0 {
             *self.contract_balance.borrow_mut() -= amount;
         }
+        if let Some(return_value) = &res {
+            meter.return_value(return_value.data.len());
+        }
         Ok(res)
     }
 
@@ -1123,7 +1386,10 @@ impl<State: Serial + DeserialWithState<TestStateApi>> HasHost<State> for TestHos
 
     /// Get the contract balance.
     /// This can be set with `set_self_balance` and defaults to 0.
-    fn self_balance(&self) -> Amount { *self.contract_balance.borrow() }
+    fn self_balance(&self) -> Amount {
+        self.state_builder.state_api.meter().host_call();
+        *self.contract_balance.borrow()
+    }
 
     fn state_builder(&mut self) -> &mut StateBuilder<Self::StateApiType> { &mut self.state_builder }
 
@@ -1143,6 +1409,8 @@ impl<State: Serial + DeserialWithState<TestStateApi>> TestHost<State> {
             .create_entry(&[])
             .expect_report("TestHost::new: Could not store state root.");
         state.serial(&mut root_entry).expect_report("TestHost::new: cannot serialize state.");
+        // Setting up the state is not part of the execution being tested.
+        state_builder.state_api.meter().reset();
         Self {
             mocking_fns: BTreeMap::new(),
             transfers: RefCell::new(Vec::new()),
@@ -1150,6 +1418,8 @@ impl<State: Serial + DeserialWithState<TestStateApi>> TestHost<State> {
             state_builder,
             state,
             missing_accounts: BTreeSet::new(),
+            chain: None,
+            clock: TestClock::default(),
         }
     }
 
@@ -1235,6 +1505,204 @@ impl<State: Serial + DeserialWithState<TestStateApi>> TestHost<State> {
     pub fn make_account_missing(&mut self, account: AccountAddress) {
         self.missing_accounts.insert(account);
     }
+
+    /// Get the energy used since the host was created or since
+    /// `reset_energy` was last called, with the costs set by
+    /// `set_energy_costs`.
+    ///
+    /// The host counts calls to it, bytes read from and written to the state,
+    /// transfers, and calls to other contracts along with the bytes of their
+    /// parameters and return values. Operations on the state before the host
+    /// was created are not counted.
+    ///
+    /// Example:
+    /// ```rust
+    /// # use concordium_std::*;
+    /// # use concordium_std::test_infrastructure::*;
+    /// # fn contract_receive<S: HasStateApi>(
+    /// #    ctx: &impl HasReceiveContext,
+    /// #    host: &mut impl HasHost<u64, StateApiType = S>,
+    /// # ) -> ReceiveResult<()> {
+    /// #    *host.state_mut() += 1;
+    /// #    Ok(())
+    /// # }
+    /// let mut host = TestHost::new(0u64, TestStateBuilder::new());
+    /// let ctx = TestReceiveContext::empty();
+    /// contract_receive(&ctx, &mut host);
+    /// host.commit_state();
+    /// assert!(host.energy_used() <= 1000, "The entrypoint is over budget.");
+    /// ```
+    pub fn energy_used(&self) -> u64 { self.state_builder.state_api.meter().energy_used() }
+
+    /// Get the number of each of the operations counted for `energy_used`.
+    pub fn energy_report(&self) -> TestEnergyReport {
+        self.state_builder.state_api.meter().report()
+    }
+
+    /// Get the costs used for `energy_used`.
+    pub fn energy_costs(&self) -> TestEnergyCosts { self.state_builder.state_api.meter().costs() }
+
+    /// Set the costs used for `energy_used`. This also applies to the energy
+    /// counted before the costs were set.
+    pub fn set_energy_costs(&mut self, costs: TestEnergyCosts) {
+        self.state_builder.state_api.meter().set_costs(costs);
+    }
+
+    /// Make the host [fail] as soon as the energy used goes over `limit`,
+    /// like running out of energy on-chain. `None` removes the limit, which is
+    /// the default.
+    pub fn set_energy_limit(&mut self, limit: Option<u64>) {
+        self.state_builder.state_api.meter().set_limit(limit);
+    }
+
+    /// Get the clock of the host. It starts at slot time 0, and is shared by
+    /// all the contexts made with `receive_context`, so that a scenario can be
+    /// tested as a sequence of calls with the clock moved forward in between.
+    ///
+    /// Example:
+    /// ```rust
+    /// # use concordium_std::*;
+    /// # use concordium_std::test_infrastructure::*;
+    /// # fn contract_receive<S: HasStateApi>(
+    /// #    ctx: &impl HasReceiveContext,
+    /// #    host: &mut impl HasHost<u64, StateApiType = S>,
+    /// # ) -> ReceiveResult<()> {
+    /// #    ensure!(ctx.metadata().slot_time() >= Timestamp::from_timestamp_millis(60_000));
+    /// #    Ok(())
+    /// # }
+    /// let mut host = TestHost::new(0u64, TestStateBuilder::new());
+    /// let ctx = host.receive_context();
+    /// assert!(contract_receive(&ctx, &mut host).is_err());
+    /// host.clock().advance(Duration::from_minutes(1));
+    /// assert!(contract_receive(&ctx, &mut host).is_ok());
+    /// ```
+    pub fn clock(&self) -> &TestClock { &self.clock }
+
+    /// Set the clock used by the contexts made with `receive_context`
+    /// afterwards.
+    pub fn set_clock(&mut self, clock: TestClock) { self.clock = clock; }
+
+    /// Create a [`TestReceiveContext`] where every field is unset, except for
+    /// the metadata, which takes the slot time from the clock of the host.
+    pub fn receive_context<'a>(&self) -> TestReceiveContext<'a> {
+        let mut ctx = TestReceiveContext::empty();
+        ctx.set_clock(self.clock.clone());
+        ctx
+    }
+
+    /// Call a receive function like the chain does. The `amount` is added to
+    /// the balance of the host before the call. If the call succeeds, the
+    /// state is committed. If it rejects, the state, the balance and the
+    /// transfers are put back to how they were before the call, so the test
+    /// sees what would remain on-chain.
+    ///
+    /// The energy used by the call is counted either way. Events logged to a
+    /// [`TestLogger`] are not rolled back.
+    ///
+    /// Example:
+    /// ```rust
+    /// # use concordium_std::*;
+    /// # use concordium_std::test_infrastructure::*;
+    /// # fn contract_receive<S: HasStateApi>(
+    /// #    ctx: &impl HasReceiveContext,
+    /// #    host: &mut impl HasHost<u64, StateApiType = S>,
+    /// #    amount: Amount,
+    /// # ) -> ReceiveResult<()> {
+    /// #    *host.state_mut() += amount.micro_ccd;
+    /// #    ensure!(*host.state() <= 10);
+    /// #    Ok(())
+    /// # }
+    /// let mut host = TestHost::new(0u64, TestStateBuilder::new());
+    /// let ctx = host.receive_context();
+    /// assert!(host.invoke(contract_receive, &ctx, Amount::from_micro_ccd(5)).is_ok());
+    /// assert!(host.invoke(contract_receive, &ctx, Amount::from_micro_ccd(6)).is_err());
+    /// assert_eq!(*host.state(), 5);
+    /// assert_eq!(host.self_balance(), Amount::from_micro_ccd(5));
+    /// ```
+    pub fn invoke<C, R, E, F>(&mut self, receive: F, ctx: &C, amount: Amount) -> Result<R, E>
+    where
+        F: FnOnce(&C, &mut Self, Amount) -> Result<R, E>, {
+        let meter = self.state_builder.state_api.meter();
+        // The call starts from the stored state, as it does on-chain.
+        meter.uncounted(|| self.commit_state());
+        let state_before = self.state_builder.state_api.snapshot();
+        let balance_before = *self.contract_balance.borrow();
+        let transfers_before = self.transfers.borrow().len();
+        *self.contract_balance.borrow_mut() += amount;
+        match receive(ctx, self, amount) {
+            Ok(return_value) => {
+                self.commit_state();
+                Ok(return_value)
+            }
+            Err(error) => {
+                self.state_builder.state_api.restore(&state_before);
+                meter.uncounted(|| self.state = Self::load_state(&self.state_builder.state_api));
+                *self.contract_balance.borrow_mut() = balance_before;
+                self.transfers.borrow_mut().truncate(transfers_before);
+                Err(error)
+            }
+        }
+    }
+
+    /// Reset the counted operations, so that `energy_used` is 0.
+    pub fn reset_energy(&mut self) { self.state_builder.state_api.meter().reset(); }
+
+    /// Create a host for an instance on a [`TestChain`], with the state and
+    /// balance the instance has on the chain.
+    pub(crate) fn open_in_chain(link: ChainLink) -> Self {
+        let state_api = link.chain.state_api(link.self_address);
+        Self {
+            clock:            link.chain.clock(),
+            mocking_fns:      BTreeMap::new(),
+            transfers:        RefCell::new(Vec::new()),
+            contract_balance: RefCell::new(link.chain.balance(link.self_address)),
+            state:            Self::load_state(&state_api),
+            state_builder:    StateBuilder::open(state_api),
+            missing_accounts: BTreeSet::new(),
+            chain:            Some(link),
+        }
+    }
+
+    /// Invoke a contract on the chain running the host. Returns whether the
+    /// state of this instance was modified by the call, and the return value.
+    fn invoke_chain(
+        &self,
+        link: &ChainLink,
+        to: &ContractAddress,
+        parameter: Parameter,
+        method: EntrypointName,
+        amount: Amount,
+    ) -> CallContractResult<Cursor<Vec<u8>>> {
+        let state_before = self.state_builder.state_api.snapshot();
+        // The chain must see the current balance, as the callee may call back into
+        // this instance.
+        link.chain.set_balance(link.self_address, *self.contract_balance.borrow());
+        let result = link.chain.invoke(
+            link.invoker,
+            Address::Contract(link.self_address),
+            *to,
+            method,
+            parameter.0,
+            amount,
+        );
+        *self.contract_balance.borrow_mut() = link.chain.balance(link.self_address);
+        let return_value = result?;
+        self.state_builder.state_api.meter().return_value(return_value.data.len());
+        Ok((self.state_builder.state_api.snapshot() != state_before, Some(return_value)))
+    }
+}
+
+impl<State: DeserialWithState<TestStateApi>> TestHost<State> {
+    /// Deserialize the state stored in the root entry.
+    fn load_state(state_api: &TestStateApi) -> State {
+        match State::deserial_with_state(
+            state_api,
+            &mut state_api.lookup_entry(&[]).expect_report("Could not lookup the state root."),
+        ) {
+            Ok(state) => state,
+            Err(e) => fail!("Failed to deserialize state: {:?}", e),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1243,11 +1711,19 @@ mod test {
     use crate::{
         cell::RefCell,
         rc::Rc,
-        test_infrastructure::{TestStateBuilder, TestStateEntry},
-        Deletable, EntryRaw, HasStateApi, HasStateEntry, StateMap, StateSet,
+        test_infrastructure::{
+            MockFn, TestEnergyCosts, TestEnergyReport, TestHost, TestLogger, TestStateBuilder,
+            TestStateEntry,
+        },
+        Deletable, EntryRaw, HasHost, HasLogger, HasStateApi, HasStateEntry, StateMap, StateSet,
         INITIAL_NEXT_ITEM_PREFIX,
     };
-    use concordium_contracts_common::{to_bytes, Deserial, Read, Seek, SeekFrom, Write};
+    #[cfg(feature = "derive-serde")]
+    use concordium_contracts_common::schema;
+    use concordium_contracts_common::{
+        to_bytes, AccountAddress, Amount, ContractAddress, Deserial, EntrypointName,
+        OwnedEntrypointName, Read, Seek, SeekFrom, Write,
+    };
 
     #[test]
     // Perform a number of operations from Seek, Read, Write and HasStateApi
@@ -1752,4 +2228,194 @@ mod test {
             state.lookup_entry(&[]).expect("Lookup failed").size().expect("Getting size failed");
         assert_eq!(expected_size as u32, actual_size);
     }
+
+    #[test]
+    fn energy_counts_state_operations() {
+        let mut host = TestHost::new(0u64, TestStateBuilder::new());
+        assert_eq!(host.energy_used(), 0, "Setting up the state is not counted.");
+        host.commit_state();
+        // Looking up the root, writing it, getting its size and truncating it.
+        assert_eq!(host.energy_report(), TestEnergyReport {
+            host_calls: 4,
+            state_bytes_written: 8,
+            ..Default::default()
+        });
+        host.reset_energy();
+        let state = host
+            .state_builder
+            .state_api
+            .lookup_entry(&[])
+            .map(|mut entry| u64::deserial(&mut entry))
+            .expect("Lookup failed");
+        assert_eq!(state, Ok(0));
+        assert_eq!(host.energy_report(), TestEnergyReport {
+            host_calls: 2,
+            state_bytes_read: 8,
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn energy_counts_transfers_and_calls() {
+        let mut host = TestHost::new(0u64, TestStateBuilder::new());
+        let address = ContractAddress {
+            index:    0,
+            subindex: 0,
+        };
+        host.set_self_balance(Amount::from_ccd(10));
+        host.setup_mock_entrypoint(
+            address,
+            OwnedEntrypointName::new_unchecked("get".into()),
+            MockFn::returning_ok(5u64),
+        );
+        host.reset_energy();
+        host.invoke_transfer(&AccountAddress([0u8; 32]), Amount::from_ccd(1))
+            .expect("Transfer failed");
+        host.invoke_contract_read_only(
+            &address,
+            &1u32,
+            EntrypointName::new_unchecked("get"),
+            Amount::zero(),
+        )
+        .expect("Call failed");
+        assert_eq!(host.energy_report(), TestEnergyReport {
+            host_calls: 2,
+            bytes_serialized: 12,
+            transfers: 1,
+            contract_calls: 1,
+            ..Default::default()
+        });
+        host.set_energy_costs(TestEnergyCosts {
+            host_call:          1,
+            state_byte_read:    0,
+            state_byte_written: 0,
+            byte_serialized:    10,
+            transfer:           100,
+            contract_call:      1000,
+        });
+        assert_eq!(host.energy_used(), 2 + 120 + 100 + 1000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Out of energy")]
+    fn energy_limit_fails_when_exceeded() {
+        let mut host = TestHost::new(0u64, TestStateBuilder::new());
+        host.set_energy_limit(Some(host.energy_costs().host_call));
+        host.self_balance();
+        host.self_balance();
+    }
+
+    #[test]
+    fn energy_saturates_instead_of_overflowing() {
+        let mut host = TestHost::new(0u64, TestStateBuilder::new());
+        host.set_energy_costs(TestEnergyCosts {
+            host_call: u64::MAX,
+            ..Default::default()
+        });
+        host.self_balance();
+        host.self_balance();
+        assert_eq!(host.energy_used(), u64::MAX);
+    }
+
+    #[test]
+    fn invoke_rolls_back_rejected_calls() {
+        let mut state_builder = TestStateBuilder::new();
+        let bids = state_builder.new_map();
+        let mut host = TestHost::new(bids, state_builder);
+        let ctx = host.receive_context();
+        let bid = |_ctx: &_,
+                   host: &mut TestHost<StateMap<u8, Amount, TestStateApi>>,
+                   amount: Amount|
+         -> Result<(), ()> {
+            let mut bid = host.state_mut().entry(0).or_insert(Amount::zero());
+            *bid += amount;
+            let total = *bid;
+            drop(bid);
+            host.invoke_transfer(&AccountAddress([1u8; 32]), Amount::from_micro_ccd(1))
+                .map_err(|_| ())?;
+            if total > Amount::from_micro_ccd(10) {
+                return Err(());
+            }
+            Ok(())
+        };
+
+        assert_eq!(host.invoke(bid, &ctx, Amount::from_micro_ccd(6)), Ok(()));
+        assert_eq!(host.invoke(bid, &ctx, Amount::from_micro_ccd(6)), Err(()));
+        assert_eq!(host.state().get(&0).map(|bid| *bid), Some(Amount::from_micro_ccd(6)));
+        assert_eq!(host.self_balance(), Amount::from_micro_ccd(5));
+        assert_eq!(host.get_transfers(), [(AccountAddress([1u8; 32]), Amount::from_micro_ccd(1))]);
+    }
+
+    #[test]
+    fn invoke_rolls_back_new_entries() {
+        let mut host = TestHost::new(0u64, TestStateBuilder::new());
+        *host.state_mut() = 3;
+        let ctx = host.receive_context();
+        let result = host.invoke(
+            |_ctx: &_, host: &mut TestHost<u64>, _amount| -> Result<(), ()> {
+                *host.state_mut() = 4;
+                let _ = host.state_builder().new_box(5u64);
+                Err(())
+            },
+            &ctx,
+            Amount::zero(),
+        );
+        assert_eq!(result, Err(()));
+        // The state set before the call is kept, and the box is gone.
+        assert_eq!(*host.state(), 3);
+        assert!(host.state_builder.state_api.lookup_entry(&INITIAL_NEXT_ITEM_PREFIX).is_none());
+    }
+
+    fn logger_with(events: &[u32]) -> TestLogger {
+        let mut logger = TestLogger::init();
+        for event in events {
+            logger.log(event).expect("Logging failed");
+        }
+        logger
+    }
+
+    #[test]
+    fn logger_events_match() {
+        let logger = logger_with(&[1, 2, 2]);
+        assert_eq!(logger.events::<u32>(), vec![1, 2, 2]);
+        logger.assert_logged(&1u32);
+        logger.assert_events(&[1u32, 2, 2]);
+        logger.assert_events_unordered(&[2u32, 1, 2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Event was not logged:\n  - expected: 3\nLogged events:\n  0: 1")]
+    fn logger_assert_logged_fails() { logger_with(&[1, 2]).assert_logged(&3u32); }
+
+    #[test]
+    #[should_panic(expected = "(2 expected, 3 logged):\nevent 1:\n  - expected: 3\n  + logged:   \
+                               2\nevent 2:\n  - expected: (none)\n  + logged:   3")]
+    fn logger_assert_events_fails() { logger_with(&[1, 2, 3]).assert_events(&[1u32, 3]); }
+
+    #[test]
+    #[should_panic(expected = "in any order:\n  - expected: 4\n  + logged:   2")]
+    fn logger_assert_events_unordered_fails() {
+        logger_with(&[1, 2, 1]).assert_events_unordered(&[1u32, 1, 4]);
+    }
+
+    #[test]
+    #[should_panic(expected = "could not be decoded")]
+    fn logger_events_fail_to_decode() { logger_with(&[1]).events::<u64>(); }
+
+    #[test]
+    #[should_panic(expected = "could not be decoded")]
+    fn logger_events_fail_with_bytes_left_over() { logger_with(&[1]).events::<u16>(); }
+
+    #[cfg(feature = "derive-serde")]
+    #[test]
+    #[should_panic(expected = "  - expected: [\n  1,\n  3\n]")]
+    fn logger_shows_events_with_schema() {
+        let mut logger = TestLogger::init();
+        logger.log(&(1u8, 2u8)).expect("Logging failed");
+        logger.set_event_schema(schema::Type::Pair(
+            Box::new(schema::Type::U8),
+            Box::new(schema::Type::U8),
+        ));
+        logger.assert_events(&[(1u8, 3u8)]);
+    }
 }
@@ -0,0 +1,716 @@
+use super::{
+    energy::EnergyMeter, trie::TrieSnapshot, TestChainMeta, TestClock, TestHost, TestInitContext,
+    TestLogger, TestReceiveContext, TestStateApi, TestStateBuilder,
+};
+use crate::{
+    cell::{RefCell, RefMut},
+    collections::BTreeMap,
+    rc::Rc,
+    *,
+};
+
+/// A receive function of an instance, with the types of its state and return
+/// value erased.
+type Entrypoint = Rc<
+    dyn Fn(&Rc<ChainData>, &TestReceiveContext, Amount, &mut TestLogger) -> ReceiveResult<Vec<u8>>,
+>;
+
+/// A contract instance on a [`TestChain`].
+struct Instance {
+    owner:       AccountAddress,
+    balance:     Amount,
+    state_api:   TestStateApi,
+    entrypoints: BTreeMap<OwnedEntrypointName, Entrypoint>,
+}
+
+/// The accounts, instances and events of a [`TestChain`]. It is shared with
+/// the [`TestHost`]s of the instances that are executing, so that their calls
+/// to other contracts can be routed through the chain.
+#[derive(Default)]
+pub(crate) struct ChainData {
+    accounts:  RefCell<BTreeMap<AccountAddress, Amount>>,
+    instances: RefCell<BTreeMap<ContractAddress, Instance>>,
+    events:    RefCell<Vec<(ContractAddress, Vec<u8>)>>,
+    metadata:  RefCell<TestChainMeta>,
+    /// The energy meters of the instances with a call in progress, innermost
+    /// last. The energy used by a call is added to the meter of its caller.
+    meters:    RefCell<Vec<Rc<EnergyMeter>>>,
+}
+
+/// The balances, states and events of a chain at some point in time.
+struct ChainSnapshot {
+    accounts:   BTreeMap<AccountAddress, Amount>,
+    instances:  Vec<(ContractAddress, Amount, TrieSnapshot)>,
+    events_len: usize,
+}
+
+/// The link from a [`TestHost`] to the chain it is executing on.
+#[derive(Clone)]
+pub(crate) struct ChainLink {
+    pub(crate) chain:        Rc<ChainData>,
+    pub(crate) self_address: ContractAddress,
+    pub(crate) invoker:      AccountAddress,
+}
+
+impl ChainData {
+    pub(crate) fn has_account(&self, account: &AccountAddress) -> bool {
+        self.accounts.borrow().contains_key(account)
+    }
+
+    pub(crate) fn credit_account(&self, account: &AccountAddress, amount: Amount) {
+        if let Some(balance) = self.accounts.borrow_mut().get_mut(account) {
+            *balance += amount;
+        }
+    }
+
+    pub(crate) fn state_api(&self, address: ContractAddress) -> TestStateApi {
+        self.instance(address, |instance| instance.state_api.clone())
+    }
+
+    pub(crate) fn balance(&self, address: ContractAddress) -> Amount {
+        self.instance(address, |instance| instance.balance)
+    }
+
+    pub(crate) fn set_balance(&self, address: ContractAddress, balance: Amount) {
+        self.instance_mut(address, |instance| instance.balance = balance)
+    }
+
+    /// Get the clock of the chain, creating it at the slot time of the meta
+    /// data if none is set yet, such that time does not go backwards.
+    pub(crate) fn clock(&self) -> TestClock {
+        let mut metadata = self.metadata.borrow_mut();
+        if metadata.clock.is_none() {
+            metadata.clock = Some(match metadata.slot_time {
+                Some(slot_time) => TestClock::new(slot_time),
+                None => TestClock::default(),
+            });
+        }
+        metadata.clock.clone().unwrap_abort()
+    }
+
+    fn instance<A>(&self, address: ContractAddress, f: impl FnOnce(&Instance) -> A) -> A {
+        match self.instances.borrow().get(&address) {
+            Some(instance) => f(instance),
+            None => fail!("Contract instance {:?} does not exist.", address),
+        }
+    }
+
+    fn instance_mut<A>(&self, address: ContractAddress, f: impl FnOnce(&mut Instance) -> A) -> A {
+        match self.instances.borrow_mut().get_mut(&address) {
+            Some(instance) => f(instance),
+            None => fail!("Contract instance {:?} does not exist.", address),
+        }
+    }
+
+    fn snapshot(&self) -> ChainSnapshot {
+        ChainSnapshot {
+            accounts:   self.accounts.borrow().clone(),
+            instances:  self
+                .instances
+                .borrow()
+                .iter()
+                .map(|(address, instance)| {
+                    (*address, instance.balance, instance.state_api.snapshot())
+                })
+                .collect(),
+            events_len: self.events.borrow().len(),
+        }
+    }
+
+    fn restore(&self, snapshot: ChainSnapshot) {
+        *self.accounts.borrow_mut() = snapshot.accounts;
+        let mut instances = self.instances.borrow_mut();
+        for (address, balance, trie) in snapshot.instances {
+            if let Some(instance) = instances.get_mut(&address) {
+                instance.balance = balance;
+                instance.state_api.restore(&trie);
+            }
+        }
+        self.events.borrow_mut().truncate(snapshot.events_len);
+    }
+
+    /// Invoke an entrypoint of an instance, transferring `amount` from the
+    /// `sender` to it. If the call fails, all the balances, states and events
+    /// are put back as they were before the call.
+    pub(crate) fn invoke(
+        self: &Rc<Self>,
+        invoker: AccountAddress,
+        sender: Address,
+        to: ContractAddress,
+        entrypoint: EntrypointName,
+        parameter: &[u8],
+        amount: Amount,
+    ) -> TestChainCallResult {
+        let snapshot = self.snapshot();
+        let result = self.run(invoker, sender, to, entrypoint, parameter, amount);
+        if result.is_err() {
+            self.restore(snapshot);
+        }
+        result
+    }
+
+    fn run(
+        self: &Rc<Self>,
+        invoker: AccountAddress,
+        sender: Address,
+        to: ContractAddress,
+        entrypoint: EntrypointName,
+        parameter: &[u8],
+        amount: Amount,
+    ) -> TestChainCallResult {
+        let (owner, receive) = {
+            let instances = self.instances.borrow();
+            let instance = instances.get(&to).ok_or(CallContractError::MissingContract)?;
+            let receive = instance
+                .entrypoints
+                .get(&OwnedEntrypointName::from(entrypoint))
+                .ok_or(CallContractError::MissingEntrypoint)?;
+            (instance.owner, Rc::clone(receive))
+        };
+
+        match sender {
+            Address::Account(account) => {
+                let mut accounts = self.accounts.borrow_mut();
+                let balance =
+                    accounts.get_mut(&account).ok_or(CallContractError::MissingAccount)?;
+                if *balance < amount {
+                    return Err(CallContractError::AmountTooLarge);
+                }
+                *balance -= amount;
+            }
+            Address::Contract(address) => {
+                let mut instances = self.instances.borrow_mut();
+                let instance =
+                    instances.get_mut(&address).ok_or(CallContractError::MissingContract)?;
+                let balance = &mut instance.balance;
+                if *balance < amount {
+                    return Err(CallContractError::AmountTooLarge);
+                }
+                *balance -= amount;
+            }
+        }
+        self.instance_mut(to, |instance| instance.balance += amount);
+
+        let mut ctx = TestReceiveContext::empty();
+        ctx.set_invoker(invoker)
+            .set_self_address(to)
+            .set_sender(sender)
+            .set_owner(owner)
+            .set_named_entrypoint(OwnedEntrypointName::from(entrypoint))
+            .set_parameter(parameter);
+        *ctx.metadata_mut() = self.metadata.borrow().clone();
+
+        // Operations of a reentrant call are already counted by the meter of the
+        // instance further up, so only the energy of other instances is added
+        // to the caller.
+        let meter = self.state_api(to).meter();
+        let reentrant = self.meters.borrow().iter().any(|active| Rc::ptr_eq(active, &meter));
+        let report_before = meter.report();
+        self.meters.borrow_mut().push(Rc::clone(&meter));
+        let mut logger = TestLogger::init();
+        let result = receive(self, &ctx, amount, &mut logger);
+        self.meters.borrow_mut().pop();
+        let caller_meter = self.meters.borrow().last().cloned();
+        if let (false, Some(caller_meter)) = (reentrant, caller_meter) {
+            caller_meter.nested_call(meter.report().since(&report_before));
+        }
+
+        match result {
+            Ok(return_value) => {
+                self.events.borrow_mut().extend(logger.logs.into_iter().map(|log| (to, log)));
+                Ok(Cursor::new(return_value))
+            }
+            Err(reject) => Err(CallContractError::LogicReject {
+                reason:       reject.error_code.get(),
+                return_value: Cursor::new(reject.return_value.unwrap_or_default()),
+            }),
+        }
+    }
+}
+
+/// The result of calling a contract on a [`TestChain`]. On success it holds the
+/// return value of the entrypoint.
+pub type TestChainCallResult = Result<Cursor<Vec<u8>>, CallContractError<Cursor<Vec<u8>>>>;
+
+/// An in-memory chain for testing several contract instances together.
+///
+/// Instances are created with [`TestChain::init`] from the real init
+/// functions of the contracts, and their entrypoints are registered with
+/// [`TestChain::add_entrypoint`]. Each call runs the real receive function with
+/// a [`TestHost`] for the instance, and calls it makes to other instances with
+/// `invoke_contract` are routed to their entrypoints, also when they call back
+/// into the calling instance. Mock entrypoints set up on the host take priority
+/// over the instances on the chain.
+///
+/// The chain keeps track of the balances of the accounts and instances. When a
+/// call fails, the balances, states and events of all the instances are put
+/// back as they were before the call, as they are on-chain. A receive function
+/// that panics fails the whole test.
+///
+/// Events are recorded per instance, in the order the calls that logged them
+/// finished.
+///
+/// # Example
+/// ```rust
+/// # use concordium_std::*;
+/// # use concordium_std::test_infrastructure::*;
+/// fn counter_init(
+///     _ctx: &impl HasInitContext,
+///     _state_builder: &mut StateBuilder<impl HasStateApi>,
+/// ) -> InitResult<u64> {
+///     Ok(0)
+/// }
+///
+/// fn counter_increment(
+///     _ctx: &impl HasReceiveContext,
+///     host: &mut impl HasHost<u64>,
+/// ) -> ReceiveResult<u64> {
+///     *host.state_mut() += 1;
+///     Ok(*host.state())
+/// }
+///
+/// const ACCOUNT: AccountAddress = AccountAddress([0u8; 32]);
+///
+/// let mut chain = TestChain::new();
+/// chain.create_account(ACCOUNT, Amount::from_ccd(100));
+/// let counter = chain
+///     .init(ACCOUNT, Amount::zero(), &[], |ctx, state_builder, _, _| {
+///         counter_init(ctx, state_builder)
+///     })
+///     .expect_report("Init failed");
+/// chain.add_entrypoint::<u64, _, _, _>(counter, "increment", |ctx, host, _, _| {
+///     counter_increment(ctx, host)
+/// });
+///
+/// let mut return_value = chain
+///     .call(ACCOUNT, counter, "increment", &[], Amount::from_ccd(1))
+///     .expect_report("Call failed");
+/// assert_eq!(u64::deserial(&mut return_value), Ok(1));
+/// assert_eq!(chain.contract_state::<u64>(counter), 1);
+/// assert_eq!(chain.account_balance(ACCOUNT), Some(Amount::from_ccd(99)));
+/// assert_eq!(chain.contract_balance(counter), Some(Amount::from_ccd(1)));
+/// ```
+#[derive(Default)]
+pub struct TestChain {
+    data: Rc<ChainData>,
+}
+
+impl TestChain {
+    /// Create a chain with no accounts and no instances.
+    pub fn new() -> Self { Default::default() }
+
+    /// Create an account with the given balance, or set the balance of the
+    /// account if it exists.
+    pub fn create_account(&mut self, account: AccountAddress, balance: Amount) {
+        self.data.accounts.borrow_mut().insert(account, balance);
+    }
+
+    /// Get the balance of an account, or `None` if the account does not exist.
+    pub fn account_balance(&self, account: AccountAddress) -> Option<Amount> {
+        self.data.accounts.borrow().get(&account).copied()
+    }
+
+    /// Get the balance of an instance, or `None` if the instance does not
+    /// exist.
+    pub fn contract_balance(&self, address: ContractAddress) -> Option<Amount> {
+        self.data.instances.borrow().get(&address).map(|instance| instance.balance)
+    }
+
+    /// Get a mutable reference to the chain meta data used in the contexts
+    /// of all calls.
+    pub fn metadata_mut(&mut self) -> RefMut<'_, TestChainMeta> { self.data.metadata.borrow_mut() }
+
+    /// Set the block slot time used in the contexts of all calls. This moves
+    /// the clock of the chain to a new block, and will
+    /// [`fail!`](../macro.fail.html) if the slot time is before the current
+    /// slot time.
+    pub fn set_metadata_slot_time(&mut self, value: SlotTime) -> &mut Self {
+        self.clock().set_slot_time(value);
+        self
+    }
+
+    /// Get the clock the block slot time of all calls is taken from. It
+    /// starts at the slot time set with `metadata_mut().set_slot_time(..)`,
+    /// or at slot time 0, unless another clock is set with
+    /// `metadata_mut().set_clock(..)`.
+    pub fn clock(&self) -> TestClock { self.data.clock() }
+
+    /// Get all the events logged by successful calls, together with the
+    /// address of the instance that logged them.
+    pub fn events(&self) -> Vec<(ContractAddress, Vec<u8>)> { self.data.events.borrow().clone() }
+
+    /// Create an instance by running an init function, sending `amount` from
+    /// `owner` to the new instance. The function is given the context, a state
+    /// builder for the new instance, the amount and a logger, so most init
+    /// functions are wrapped in a closure that passes on the arguments they
+    /// take.
+    ///
+    /// Returns the address of the new instance, or the reason the init
+    /// function rejected. The entrypoints of the instance are added with
+    /// [`TestChain::add_entrypoint`].
+    ///
+    /// Fails the test if `owner` does not exist or cannot pay the amount.
+    pub fn init<State, E, F>(
+        &mut self,
+        owner: AccountAddress,
+        amount: Amount,
+        parameter: &[u8],
+        init: F,
+    ) -> Result<ContractAddress, Reject>
+    where
+        State: Serial,
+        Reject: From<E>,
+        F: FnOnce(
+            &TestInitContext,
+            &mut TestStateBuilder,
+            Amount,
+            &mut TestLogger,
+        ) -> Result<State, E>, {
+        match self.account_balance(owner) {
+            Some(balance) if balance >= amount => {}
+            Some(_) => fail!("Account {:?} cannot pay {:?} to create an instance.", owner, amount),
+            None => fail!("Account {:?} does not exist.", owner),
+        }
+
+        let state_api = TestStateApi::new();
+        let mut state_builder = StateBuilder::open(state_api.clone());
+        let mut ctx = TestInitContext::empty();
+        ctx.set_init_origin(owner).set_parameter(parameter);
+        *ctx.metadata_mut() = self.data.metadata.borrow().clone();
+        let mut logger = TestLogger::init();
+        let state = init(&ctx, &mut state_builder, amount, &mut logger).map_err(Reject::from)?;
+
+        let mut root_entry = state_builder
+            .state_api
+            .create_entry(&[])
+            .expect_report("TestChain::init: Could not store state root.");
+        state.serial(&mut root_entry).expect_report("TestChain::init: cannot serialize state.");
+
+        let address = ContractAddress {
+            index:    self.data.instances.borrow().len() as u64,
+            subindex: 0,
+        };
+        self.data.instances.borrow_mut().insert(address, Instance {
+            owner,
+            balance: amount,
+            state_api,
+            entrypoints: BTreeMap::new(),
+        });
+        if let Some(balance) = self.data.accounts.borrow_mut().get_mut(&owner) {
+            *balance -= amount;
+        }
+        self.data.events.borrow_mut().extend(logger.logs.into_iter().map(|log| (address, log)));
+        Ok(address)
+    }
+
+    /// Add an entrypoint to an instance. The function is given the context, a
+    /// [`TestHost`] for the instance, the amount and a logger, so most receive
+    /// functions are wrapped in a closure that passes on the arguments they
+    /// take. `State` must be the type of the state the instance was created
+    /// with.
+    ///
+    /// If an entrypoint with the same name was added before, it is replaced.
+    pub fn add_entrypoint<State, R, E, F>(
+        &mut self,
+        address: ContractAddress,
+        name: &str,
+        receive: F,
+    ) where
+        State: Serial + DeserialWithState<TestStateApi> + 'static,
+        R: Serial,
+        Reject: From<E>,
+        F: Fn(&TestReceiveContext, &mut TestHost<State>, Amount, &mut TestLogger) -> Result<R, E>
+            + 'static, {
+        let entrypoint: Entrypoint = Rc::new(move |chain, ctx, amount, logger| {
+            let address = ctx.self_address();
+            let mut host = TestHost::<State>::open_in_chain(ChainLink {
+                chain:        Rc::clone(chain),
+                self_address: address,
+                invoker:      ctx.invoker(),
+            });
+            let return_value = receive(ctx, &mut host, amount, logger).map_err(Reject::from)?;
+            host.commit_state();
+            chain.set_balance(address, host.self_balance());
+            Ok(to_bytes(&return_value))
+        });
+        self.data.instance_mut(address, |instance| {
+            instance.entrypoints.insert(OwnedEntrypointName::new_unchecked(name.into()), entrypoint)
+        });
+    }
+
+    /// Call an entrypoint of an instance from an account, sending `amount`
+    /// from the account to the instance. If the call fails, the balances,
+    /// states and events are left as they were before the call.
+    pub fn call(
+        &mut self,
+        invoker: AccountAddress,
+        address: ContractAddress,
+        entrypoint: &str,
+        parameter: &[u8],
+        amount: Amount,
+    ) -> TestChainCallResult {
+        self.data.invoke(
+            invoker,
+            Address::Account(invoker),
+            address,
+            EntrypointName::new_unchecked(entrypoint),
+            parameter,
+            amount,
+        )
+    }
+
+    /// Get the current state of an instance. `State` must be the type of the
+    /// state the instance was created with.
+    pub fn contract_state<State: DeserialWithState<TestStateApi>>(
+        &self,
+        address: ContractAddress,
+    ) -> State {
+        TestHost::<State>::load_state(&self.data.state_api(address))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TestChain;
+    use crate::{
+        cell::Cell,
+        rc::Rc,
+        test_infrastructure::{TestEnergyCosts, TestHost},
+        *,
+    };
+
+    const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+    const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
+
+    /// A counter that can call another instance, and rejects when it is
+    /// incremented past 2.
+    fn counter_increment(host: &mut impl HasHost<u64>) -> ReceiveResult<u64> {
+        *host.state_mut() += 1;
+        ensure!(*host.state() <= 2);
+        Ok(*host.state())
+    }
+
+    /// Call an entrypoint of another instance with the address, name and
+    /// parameter given in the parameter, then increment the counter. Returns
+    /// the value returned by the call.
+    fn counter_forward(
+        ctx: &impl HasReceiveContext,
+        host: &mut impl HasHost<u64>,
+        amount: Amount,
+    ) -> ReceiveResult<u64> {
+        let ((to, entrypoint), parameter): ((ContractAddress, OwnedEntrypointName), Vec<u8>) =
+            ctx.parameter_cursor().get()?;
+        let (_, return_value) = host
+            .invoke_contract_raw(
+                &to,
+                Parameter(&parameter),
+                entrypoint.as_entrypoint_name(),
+                amount,
+            )
+            .map_err(|_| Reject::default())?;
+        let called_value = u64::deserial(&mut return_value.unwrap_abort())?;
+        counter_increment(host)?;
+        Ok(called_value)
+    }
+
+    /// Transfer the whole balance to the account in the parameter.
+    fn counter_pay(
+        ctx: &impl HasReceiveContext,
+        host: &mut impl HasHost<u64>,
+    ) -> ReceiveResult<u64> {
+        let account: AccountAddress = ctx.parameter_cursor().get()?;
+        let balance = host.self_balance();
+        host.invoke_transfer(&account, balance).map_err(|_| Reject::default())?;
+        Ok(balance.micro_ccd)
+    }
+
+    fn setup() -> (TestChain, ContractAddress, ContractAddress) {
+        let mut chain = TestChain::new();
+        chain.create_account(ACCOUNT_0, Amount::from_ccd(100));
+        chain.create_account(ACCOUNT_1, Amount::zero());
+        let mut create = || {
+            let address = chain
+                .init(ACCOUNT_0, Amount::from_ccd(1), &[], |_, _, _, _| -> InitResult<u64> {
+                    Ok(0)
+                })
+                .expect_report("Init failed");
+            chain.add_entrypoint(address, "increment", |_, host: &mut TestHost<u64>, _, _| {
+                counter_increment(host)
+            });
+            chain.add_entrypoint(address, "forward", |ctx, host: &mut TestHost<u64>, amount, _| {
+                counter_forward(ctx, host, amount)
+            });
+            chain.add_entrypoint(address, "pay", |ctx, host: &mut TestHost<u64>, _, _| {
+                counter_pay(ctx, host)
+            });
+            address
+        };
+        let a = create();
+        let b = create();
+        (chain, a, b)
+    }
+
+    fn forward_parameter(to: ContractAddress, entrypoint: &str, parameter: &[u8]) -> Vec<u8> {
+        to_bytes(&((to, OwnedEntrypointName::new_unchecked(entrypoint.into())), parameter.to_vec()))
+    }
+
+    #[test]
+    fn test_call_other_contract() {
+        let (mut chain, a, b) = setup();
+        let parameter = forward_parameter(b, "increment", &[]);
+        let mut return_value = chain
+            .call(ACCOUNT_0, a, "forward", &parameter, Amount::from_ccd(5))
+            .expect_report("Call failed");
+        assert_eq!(u64::deserial(&mut return_value), Ok(1));
+        assert_eq!(chain.contract_state::<u64>(a), 1);
+        assert_eq!(chain.contract_state::<u64>(b), 1);
+        assert_eq!(chain.account_balance(ACCOUNT_0), Some(Amount::from_ccd(93)));
+        assert_eq!(chain.contract_balance(a), Some(Amount::from_ccd(1)));
+        assert_eq!(chain.contract_balance(b), Some(Amount::from_ccd(6)));
+    }
+
+    #[test]
+    fn test_reentrant_call() {
+        let (mut chain, a, b) = setup();
+        chain.add_entrypoint(b, "call_back", move |_, host: &mut TestHost<u64>, _, _| {
+            let (_, return_value) = host
+                .invoke_contract_raw(
+                    &a,
+                    Parameter(&[]),
+                    EntrypointName::new_unchecked("increment"),
+                    Amount::zero(),
+                )
+                .map_err(|_| Reject::default())?;
+            u64::deserial(&mut return_value.unwrap_abort()).map_err(Reject::from)
+        });
+        // `a` calls `b`, which calls back into `a`. The state `a` sees after the call
+        // includes the increment done by the reentrant call.
+        let parameter = forward_parameter(b, "call_back", &[]);
+        let mut return_value = chain
+            .call(ACCOUNT_0, a, "forward", &parameter, Amount::zero())
+            .expect_report("Call failed");
+        assert_eq!(u64::deserial(&mut return_value), Ok(1));
+        assert_eq!(chain.contract_state::<u64>(a), 2);
+        assert_eq!(chain.contract_state::<u64>(b), 0);
+    }
+
+    #[test]
+    fn test_rollback_of_failed_call() {
+        let (mut chain, a, b) = setup();
+        chain.call(ACCOUNT_0, b, "increment", &[], Amount::zero()).expect_report("Call failed");
+        chain.call(ACCOUNT_0, b, "increment", &[], Amount::zero()).expect_report("Call failed");
+
+        // `b` rejects after incrementing its state, which `a` then rejects with.
+        let parameter = forward_parameter(b, "increment", &[]);
+        let result = chain.call(ACCOUNT_0, a, "forward", &parameter, Amount::from_ccd(5));
+        assert!(matches!(result, Err(CallContractError::LogicReject { .. })));
+        assert_eq!(chain.contract_state::<u64>(a), 0);
+        assert_eq!(chain.contract_state::<u64>(b), 2);
+        assert_eq!(chain.account_balance(ACCOUNT_0), Some(Amount::from_ccd(98)));
+        assert_eq!(chain.contract_balance(a), Some(Amount::from_ccd(1)));
+        assert_eq!(chain.contract_balance(b), Some(Amount::from_ccd(1)));
+    }
+
+    #[test]
+    fn test_rollback_of_successful_nested_call() {
+        let (mut chain, a, b) = setup();
+        chain.call(ACCOUNT_0, a, "increment", &[], Amount::zero()).expect_report("Call failed");
+        chain.call(ACCOUNT_0, a, "increment", &[], Amount::zero()).expect_report("Call failed");
+
+        // The call to `b` succeeds, but `a` then rejects when incrementing.
+        let parameter = forward_parameter(b, "pay", &to_bytes(&ACCOUNT_1));
+        let result = chain.call(ACCOUNT_0, a, "forward", &parameter, Amount::from_ccd(5));
+        assert!(result.is_err());
+        assert_eq!(chain.account_balance(ACCOUNT_1), Some(Amount::zero()));
+        assert_eq!(chain.contract_balance(b), Some(Amount::from_ccd(1)));
+    }
+
+    #[test]
+    fn test_transfers() {
+        let (mut chain, a, _) = setup();
+        chain
+            .call(ACCOUNT_0, a, "pay", &to_bytes(&ACCOUNT_1), Amount::from_ccd(2))
+            .expect_report("Call failed");
+        assert_eq!(chain.account_balance(ACCOUNT_1), Some(Amount::from_ccd(3)));
+        assert_eq!(chain.contract_balance(a), Some(Amount::zero()));
+
+        let missing = AccountAddress([2u8; 32]);
+        let result = chain.call(ACCOUNT_0, a, "pay", &to_bytes(&missing), Amount::from_ccd(2));
+        assert!(result.is_err());
+        assert_eq!(chain.account_balance(ACCOUNT_0), Some(Amount::from_ccd(96)));
+        assert_eq!(chain.contract_balance(a), Some(Amount::zero()));
+    }
+
+    #[test]
+    fn test_energy_of_nested_calls() {
+        let (mut chain, a, b) = setup();
+        let callee_energy = Rc::new(Cell::new(0));
+        let callee_energy_b = Rc::clone(&callee_energy);
+        chain.add_entrypoint(b, "metered", move |_, host: &mut TestHost<u64>, _, _| {
+            let before = host.energy_used();
+            host.invoke_transfer(&ACCOUNT_1, Amount::zero()).map_err(|_| Reject::default())?;
+            callee_energy_b.set(host.energy_used() - before);
+            counter_increment(host)
+        });
+        // `a` returns the energy it used for the call to `b`.
+        chain.add_entrypoint(a, "measure", move |_, host: &mut TestHost<u64>, _, _| {
+            let before = host.energy_used();
+            host.invoke_contract_raw(
+                &b,
+                Parameter(&[]),
+                EntrypointName::new_unchecked("metered"),
+                Amount::zero(),
+            )
+            .map_err(|_| Reject::default())?;
+            Ok::<_, Reject>(host.energy_used() - before)
+        });
+
+        let mut return_value =
+            chain.call(ACCOUNT_0, a, "measure", &[], Amount::zero()).expect_report("Call failed");
+        let used = u64::deserial(&mut return_value).expect_report("Invalid return value");
+        let costs = TestEnergyCosts::default();
+        // The call itself, its return value and what `b` did are all paid by `a`.
+        let call = costs.host_call + costs.contract_call + 8 * costs.byte_serialized;
+        assert_eq!(callee_energy.get(), costs.host_call + costs.transfer);
+        assert!(used >= call + callee_energy.get(), "The energy used by b should be added to a.");
+    }
+
+    #[test]
+    fn test_slot_time_set_in_metadata() {
+        let (mut chain, a, _) = setup();
+        let slot_times = Rc::new(Cell::new(Vec::new()));
+        let slot_times_a = Rc::clone(&slot_times);
+        chain.add_entrypoint(a, "slot_time", move |ctx, _: &mut TestHost<u64>, _, _| {
+            let mut seen = slot_times_a.take();
+            seen.push(ctx.metadata().slot_time());
+            slot_times_a.set(seen);
+            Ok::<_, Reject>(())
+        });
+
+        // The clock of the chain starts at the slot time set before the first call.
+        chain.metadata_mut().set_slot_time(Timestamp::from_timestamp_millis(5000));
+        chain.call(ACCOUNT_0, a, "slot_time", &[], Amount::zero()).expect_report("Call failed");
+        chain.call(ACCOUNT_0, a, "slot_time", &[], Amount::zero()).expect_report("Call failed");
+        let slot_time = Timestamp::from_timestamp_millis(5000);
+        assert_eq!(slot_times.take(), vec![slot_time, slot_time]);
+        assert_eq!(chain.clock().slot_time(), slot_time);
+    }
+
+    #[test]
+    fn test_missing_contract_and_entrypoint() {
+        let (mut chain, a, b) = setup();
+        let missing = ContractAddress {
+            index:    2,
+            subindex: 0,
+        };
+        let result = chain.call(ACCOUNT_0, missing, "increment", &[], Amount::zero());
+        assert!(matches!(result, Err(CallContractError::MissingContract)));
+        let result = chain.call(ACCOUNT_0, a, "missing", &[], Amount::zero());
+        assert!(matches!(result, Err(CallContractError::MissingEntrypoint)));
+
+        let parameter = forward_parameter(b, "missing", &[]);
+        let result = chain.call(ACCOUNT_0, a, "forward", &parameter, Amount::zero());
+        assert!(result.is_err());
+        assert_eq!(chain.contract_state::<u64>(a), 0);
+    }
+}
@@ -0,0 +1,151 @@
+use crate::{cell::Cell, rc::Rc, *};
+
+/// A clock for a test scenario, that can be shared by several contexts, hosts
+/// and chains. Cloning the clock gives another handle to the same clock, so
+/// moving it forward is seen by every context it was set on, see
+/// [`TestChainMeta::set_clock`](super::TestChainMeta::set_clock).
+///
+/// Each time the clock is moved forward, a new block is made. The clock keeps
+/// track of the height of the current block and of the last finalized block.
+/// Time never goes backwards; trying to set an earlier time will
+/// [fail](../macro.fail.html).
+///
+/// # Example
+/// ```rust
+/// # use concordium_std::*;
+/// # use concordium_std::test_infrastructure::*;
+/// let clock = TestClock::new(Timestamp::from_timestamp_millis(0));
+/// let mut ctx = TestReceiveContext::empty();
+/// ctx.set_clock(clock.clone());
+///
+/// clock.advance(Duration::from_hours(1)).finalize();
+/// assert_eq!(ctx.metadata().slot_time(), Timestamp::from_timestamp_millis(3_600_000));
+/// assert_eq!(clock.block_height(), 1);
+/// assert_eq!(clock.finalized_block_height(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TestClock {
+    state: Rc<Cell<ClockState>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ClockState {
+    slot_time:              SlotTime,
+    block_height:           u64,
+    finalized_block_height: u64,
+}
+
+impl Default for TestClock {
+    /// Creates a clock at slot time 0.
+    fn default() -> Self { Self::new(Timestamp::from_timestamp_millis(0)) }
+}
+
+impl TestClock {
+    /// Create a clock at the given slot time. The current block is the genesis
+    /// block, with height 0, which is finalized.
+    pub fn new(slot_time: SlotTime) -> Self {
+        Self {
+            state: Rc::new(Cell::new(ClockState {
+                slot_time,
+                block_height: 0,
+                finalized_block_height: 0,
+            })),
+        }
+    }
+
+    /// Get the slot time of the current block.
+    pub fn slot_time(&self) -> SlotTime { self.state.get().slot_time }
+
+    /// Get the height of the current block.
+    pub fn block_height(&self) -> u64 { self.state.get().block_height }
+
+    /// Get the height of the last finalized block.
+    pub fn finalized_block_height(&self) -> u64 { self.state.get().finalized_block_height }
+
+    /// Check whether the block at the given height is finalized.
+    pub fn is_finalized(&self, block_height: u64) -> bool {
+        block_height <= self.finalized_block_height()
+    }
+
+    /// Move to a new block `duration` after the current one.
+    pub fn advance(&self, duration: Duration) -> &Self {
+        match self.slot_time().checked_add(duration) {
+            Some(slot_time) => self.set_slot_time(slot_time),
+            None => fail!("Advancing the clock by {:?} overflows the slot time.", duration),
+        }
+    }
+
+    /// Move to a new block with the given slot time. Will
+    /// [fail](../macro.fail.html) if the slot time is before the slot time of
+    /// the current block.
+    pub fn set_slot_time(&self, slot_time: SlotTime) -> &Self {
+        let mut state = self.state.get();
+        let current = state.slot_time;
+        if slot_time < current {
+            fail!("The clock cannot go backwards from slot time {:?} to {:?}.", current, slot_time);
+        }
+        state.slot_time = slot_time;
+        state.block_height += 1;
+        self.state.set(state);
+        self
+    }
+
+    /// Finalize all the blocks up to and including the current block.
+    pub fn finalize(&self) -> &Self {
+        let mut state = self.state.get();
+        state.finalized_block_height = state.block_height;
+        self.state.set(state);
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_infrastructure::*;
+
+    #[test]
+    fn contexts_share_the_clock() {
+        let clock = TestClock::new(Timestamp::from_timestamp_millis(1000));
+        let mut init_ctx = TestInitContext::empty();
+        init_ctx.set_clock(clock.clone());
+        let host = TestHost::new(0u8, TestStateBuilder::new());
+        let mut receive_ctx = host.receive_context();
+        receive_ctx.set_clock(clock.clone());
+
+        clock.advance(Duration::from_seconds(1));
+        assert_eq!(init_ctx.metadata().slot_time(), Timestamp::from_timestamp_millis(2000));
+        assert_eq!(receive_ctx.metadata().slot_time(), Timestamp::from_timestamp_millis(2000));
+
+        // Setting the slot time on a context moves the shared clock.
+        receive_ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(5000));
+        assert_eq!(init_ctx.metadata().slot_time(), Timestamp::from_timestamp_millis(5000));
+        assert_eq!(clock.block_height(), 2);
+    }
+
+    #[test]
+    fn host_contexts_share_the_host_clock() {
+        let host = TestHost::new(0u8, TestStateBuilder::new());
+        let ctx = host.receive_context();
+        assert_eq!(ctx.metadata().slot_time(), Timestamp::from_timestamp_millis(0));
+        host.clock().advance(Duration::from_days(1));
+        assert_eq!(ctx.metadata().slot_time(), Timestamp::from_timestamp_millis(86_400_000));
+    }
+
+    #[test]
+    fn blocks_are_finalized() {
+        let clock = TestClock::default();
+        clock.advance(Duration::from_seconds(2)).finalize();
+        clock.advance(Duration::from_seconds(2)).advance(Duration::from_seconds(2));
+        assert_eq!(clock.block_height(), 3);
+        assert_eq!(clock.finalized_block_height(), 1);
+        assert!(clock.is_finalized(1));
+        assert!(!clock.is_finalized(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "The clock cannot go backwards")]
+    fn clock_does_not_go_backwards() {
+        let clock = TestClock::new(Timestamp::from_timestamp_millis(1000));
+        clock.set_slot_time(Timestamp::from_timestamp_millis(999));
+    }
+}
@@ -0,0 +1,187 @@
+use crate::{cell::Cell, *};
+
+/// The energy charged for the operations counted by a
+/// [`TestHost`](super::TestHost) and its state.
+///
+/// The default costs are rough approximations of the costs on-chain. They are
+/// meant for comparing entrypoints and catching regressions in tests, not for
+/// predicting the exact energy a transaction uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TestEnergyCosts {
+    /// Cost of each call to the host, such as looking up a state entry,
+    /// reading from it, or getting the balance.
+    pub host_call:          u64,
+    /// Cost per byte read from the state.
+    pub state_byte_read:    u64,
+    /// Cost per byte written to the state.
+    pub state_byte_written: u64,
+    /// Cost per byte of the parameters sent to and the return values received
+    /// from other contracts.
+    pub byte_serialized:    u64,
+    /// Cost of each transfer to an account.
+    pub transfer:           u64,
+    /// Cost of each call to another contract.
+    pub contract_call:      u64,
+}
+
+impl Default for TestEnergyCosts {
+    fn default() -> Self {
+        Self {
+            host_call:          100,
+            state_byte_read:    1,
+            state_byte_written: 10,
+            byte_serialized:    1,
+            transfer:           500,
+            contract_call:      1000,
+        }
+    }
+}
+
+/// The operations counted by a [`TestHost`](super::TestHost) and its state
+/// since it was created or since the meter was last reset.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TestEnergyReport {
+    /// Number of calls to the host.
+    pub host_calls:          u64,
+    /// Number of bytes read from the state.
+    pub state_bytes_read:    u64,
+    /// Number of bytes written to the state.
+    pub state_bytes_written: u64,
+    /// Number of bytes of parameters sent to and return values received from
+    /// other contracts.
+    pub bytes_serialized:    u64,
+    /// Number of transfers to accounts.
+    pub transfers:           u64,
+    /// Number of calls to other contracts.
+    pub contract_calls:      u64,
+}
+
+impl TestEnergyReport {
+    /// The energy used for the counted operations with the given costs. It
+    /// saturates at `u64::MAX` instead of overflowing.
+    pub fn energy(&self, costs: &TestEnergyCosts) -> u64 {
+        [
+            (self.host_calls, costs.host_call),
+            (self.state_bytes_read, costs.state_byte_read),
+            (self.state_bytes_written, costs.state_byte_written),
+            (self.bytes_serialized, costs.byte_serialized),
+            (self.transfers, costs.transfer),
+            (self.contract_calls, costs.contract_call),
+        ]
+        .iter()
+        .fold(0u64, |energy, (count, cost)| energy.saturating_add(count.saturating_mul(*cost)))
+    }
+
+    /// The operations counted since `earlier`, a report of the same meter.
+    pub(crate) fn since(&self, earlier: &TestEnergyReport) -> TestEnergyReport {
+        TestEnergyReport {
+            host_calls:          self.host_calls.saturating_sub(earlier.host_calls),
+            state_bytes_read:    self.state_bytes_read.saturating_sub(earlier.state_bytes_read),
+            state_bytes_written: self
+                .state_bytes_written
+                .saturating_sub(earlier.state_bytes_written),
+            bytes_serialized:    self.bytes_serialized.saturating_sub(earlier.bytes_serialized),
+            transfers:           self.transfers.saturating_sub(earlier.transfers),
+            contract_calls:      self.contract_calls.saturating_sub(earlier.contract_calls),
+        }
+    }
+}
+
+/// Counts the operations of a host and its state, and fails the test if they
+/// use more energy than the limit.
+#[derive(Debug, Default)]
+pub(crate) struct EnergyMeter {
+    costs:  Cell<TestEnergyCosts>,
+    report: Cell<TestEnergyReport>,
+    limit:  Cell<Option<u64>>,
+}
+
+impl EnergyMeter {
+    pub(crate) fn costs(&self) -> TestEnergyCosts { self.costs.get() }
+
+    pub(crate) fn set_costs(&self, costs: TestEnergyCosts) { self.costs.set(costs) }
+
+    pub(crate) fn report(&self) -> TestEnergyReport { self.report.get() }
+
+    pub(crate) fn energy_used(&self) -> u64 { self.report().energy(&self.costs()) }
+
+    pub(crate) fn set_limit(&self, limit: Option<u64>) { self.limit.set(limit) }
+
+    pub(crate) fn reset(&self) { self.report.set(TestEnergyReport::default()) }
+
+    /// Count a call to the host.
+    pub(crate) fn host_call(&self) { self.charge(|report| report.host_calls += 1) }
+
+    /// Count a call to the host that reads `len` bytes from the state.
+    pub(crate) fn state_read(&self, len: usize) {
+        self.charge(|report| {
+            report.host_calls += 1;
+            report.state_bytes_read += len as u64;
+        })
+    }
+
+    /// Count a call to the host that writes `len` bytes to the state.
+    pub(crate) fn state_write(&self, len: usize) {
+        self.charge(|report| {
+            report.host_calls += 1;
+            report.state_bytes_written += len as u64;
+        })
+    }
+
+    /// Count a transfer to an account.
+    pub(crate) fn transfer(&self) {
+        self.charge(|report| {
+            report.host_calls += 1;
+            report.transfers += 1;
+        })
+    }
+
+    /// Count a call to another contract with a parameter of `len` bytes.
+    pub(crate) fn contract_call(&self, len: usize) {
+        self.charge(|report| {
+            report.host_calls += 1;
+            report.contract_calls += 1;
+            report.bytes_serialized += len as u64;
+        })
+    }
+
+    /// Count a return value of `len` bytes received from another contract.
+    pub(crate) fn return_value(&self, len: usize) {
+        self.charge(|report| report.bytes_serialized += len as u64)
+    }
+
+    /// Count the operations of a call to another instance, which the caller
+    /// pays for.
+    pub(crate) fn nested_call(&self, used: TestEnergyReport) {
+        self.charge(|report| {
+            report.host_calls = report.host_calls.saturating_add(used.host_calls);
+            report.state_bytes_read = report.state_bytes_read.saturating_add(used.state_bytes_read);
+            report.state_bytes_written =
+                report.state_bytes_written.saturating_add(used.state_bytes_written);
+            report.bytes_serialized = report.bytes_serialized.saturating_add(used.bytes_serialized);
+            report.transfers = report.transfers.saturating_add(used.transfers);
+            report.contract_calls = report.contract_calls.saturating_add(used.contract_calls);
+        })
+    }
+
+    /// Run `f` without counting the operations it does. Used for the work
+    /// the test infrastructure does that is not part of the execution.
+    pub(crate) fn uncounted<A>(&self, f: impl FnOnce() -> A) -> A {
+        let report = self.report.get();
+        let result = f();
+        self.report.set(report);
+        result
+    }
+
+    fn charge(&self, f: impl FnOnce(&mut TestEnergyReport)) {
+        let mut report = self.report.get();
+        f(&mut report);
+        self.report.set(report);
+        if let Some(limit) = self.limit.get() {
+            let used = report.energy(&self.costs.get());
+            if used > limit {
+                fail!("Out of energy: used {} energy with a limit of {}.", used, limit);
+            }
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use super::{TestStateEntry, TestStateEntryData};
+use super::{energy::EnergyMeter, TestStateEntry, TestStateEntryData};
 use crate::{
     cell::{Cell, RefCell},
     collections::{btree_map, BTreeMap, HashMap as Map, VecDeque},
@@ -12,10 +12,12 @@ pub(crate) type Index = usize;
 
 #[derive(Debug)]
 pub(crate) struct StateTrie {
-    nodes:           Node,
-    next_entry_id:   Cell<StateEntryId>,
-    entry_map:       RefCell<Map<StateEntryId, Vec<Index>>>,
-    iterator_counts: RefCell<BTreeMap<Vec<Index>, u32>>,
+    nodes:            Node,
+    next_entry_id:    Cell<StateEntryId>,
+    entry_map:        RefCell<Map<StateEntryId, Vec<Index>>>,
+    iterator_counts:  RefCell<BTreeMap<Vec<Index>, u32>>,
+    /// Meter for the operations on the state and its entries.
+    pub(crate) meter: Rc<EnergyMeter>,
 }
 
 impl Default for StateTrie {
@@ -49,6 +51,7 @@ impl StateTrie {
             next_entry_id:   Cell::new(0),
             entry_map:       RefCell::new(Map::default()),
             iterator_counts: Default::default(),
+            meter:           Default::default(),
         }
     }
 
@@ -67,7 +70,9 @@ impl StateTrie {
         self.entry_map.borrow_mut().insert(state_entry_id, indexes);
         self.next_entry_id.set(state_entry_id + 1);
 
-        TestStateEntry::open(data, key, state_entry_id)
+        let mut entry = TestStateEntry::open(data, key, state_entry_id);
+        entry.meter = Rc::clone(&self.meter);
+        entry
     }
 
     pub(crate) fn delete_prefix(&mut self, prefix: &[u8]) -> Result<bool, StateError> {
@@ -179,6 +184,44 @@ impl StateTrie {
     }
 }
 
+/// The data of all the entries of a [`StateTrie`], by key.
+pub(crate) type TrieSnapshot = BTreeMap<Vec<u8>, Vec<u8>>;
+
+impl StateTrie {
+    /// Copy the data of all the entries, so that it can be put back with
+    /// `restore`.
+    pub(crate) fn snapshot(&self) -> TrieSnapshot {
+        let mut snapshot = BTreeMap::new();
+        self.nodes.collect_data(&mut Vec::new(), &mut snapshot);
+        snapshot
+    }
+
+    /// Put back the data of the entries as it was when the snapshot was taken.
+    /// Entries that exist in both keep their identity, so that entries
+    /// given out before the restore see the restored data. Entries that did
+    /// not exist when the snapshot was taken are deleted.
+    pub(crate) fn restore(&mut self, snapshot: &TrieSnapshot) {
+        let mut current = BTreeMap::new();
+        self.nodes.collect_data(&mut Vec::new(), &mut current);
+        for key in current.keys().filter(|key| !snapshot.contains_key(*key)) {
+            let indexes = to_indexes(key);
+            if let Some(data) = self.nodes.lookup(&indexes) {
+                *data.borrow_mut() = TestStateEntryData::EntryDeleted;
+            }
+            // The entry exists, so deleting it cannot fail.
+            let _ = self.nodes.delete_data(&indexes);
+        }
+        for (key, data) in snapshot {
+            let indexes = to_indexes(key);
+            let entry_data = match self.nodes.lookup(&indexes) {
+                Some(entry_data) => entry_data,
+                None => self.nodes.create(&indexes),
+            };
+            *entry_data.borrow_mut() = TestStateEntryData::new_from(data.clone());
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct TestStateIter {
     // Only used when deleting the iterator.
@@ -229,7 +272,11 @@ impl TestStateIter {
 impl Iterator for TestStateIter {
     type Item = TestStateEntry;
 
-    fn next(&mut self) -> Option<Self::Item> { self.queue.pop_front() }
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.queue.pop_front()?;
+        entry.meter.host_call();
+        Some(entry)
+    }
 }
 
 #[derive(Debug)]
@@ -325,6 +372,23 @@ impl Node {
         }
     }
 
+    /// Add the data of this node and all the nodes below it to `out`, keyed by
+    /// their full key. `indexes` is the path to this node.
+    fn collect_data(&self, indexes: &mut Vec<Index>, out: &mut BTreeMap<Vec<u8>, Vec<u8>>) {
+        if let Some(data) = &self.data {
+            if let Ok(data) = data.borrow().data() {
+                out.insert(from_indexes(indexes), data.to_vec());
+            }
+        }
+        for (idx, child) in self.children.iter().enumerate() {
+            if let Some(child) = child {
+                indexes.push(idx);
+                child.collect_data(indexes, out);
+                indexes.pop();
+            }
+        }
+    }
+
     // A node is considered empty when it has no data and no children.
     fn is_empty(&self) -> bool { self.data.is_none() && self.children.iter().all(|x| x.is_none()) }
 }
@@ -12,7 +12,11 @@ use rand::thread_rng;
 use rs_merkle::algorithms::Sha256 as merkle_sha256;
 use rs_merkle::*;
 use sha2::{Digest, Sha256};
-use util::{hash_to_scalar, OneInTwoZKP, SchnorrProof};
+use util::{
+    encode_vote_option, hash_to_challenge, merkle_leaf, ChallengeContext, ChaumPedersenProof,
+    OneInKZKP, OneInTwoZKP, SchnorrProof, CHAUM_PEDERSEN_DOMAIN, ONE_IN_K_DOMAIN,
+    ONE_IN_TWO_DOMAIN, SCHNORR_DOMAIN,
+};
 
 /// Create a voting key (pk, sk) pair of g^x and x
 pub fn create_votingkey_pair() -> (Scalar, ProjectivePoint) {
@@ -22,16 +26,23 @@ pub fn create_votingkey_pair() -> (Scalar, ProjectivePoint) {
     (x, g_x)
 }
 
-/// Create a discrete log Schnorr ZKP (g^w, r = w - xz)
-pub fn create_schnorr_zkp(g_x: ProjectivePoint, x: Scalar) -> SchnorrProof {
+/// Create a discrete log Schnorr ZKP (g^w, r = w - xz), bound to the given challenge context
+pub fn create_schnorr_zkp(
+    g_x: ProjectivePoint,
+    x: Scalar,
+    context: &ChallengeContext,
+) -> SchnorrProof {
     let rng = thread_rng();
 
     let w = Scalar::random(rng);
     let g_w = ProjectivePoint::GENERATOR * w;
 
-    // Create hash z = H(g, g^w, g^x)
-    let value_to_hash = ProjectivePoint::GENERATOR + g_w + g_x;
-    let z = hash_to_scalar(value_to_hash.to_bytes().to_vec());
+    // Create hash z = H(domain, context, g, g^w, g^x)
+    let z = hash_to_challenge(
+        SCHNORR_DOMAIN,
+        context,
+        &[ProjectivePoint::GENERATOR, g_w, g_x],
+    );
 
     let r = w - x * z;
 
@@ -43,6 +54,7 @@ pub fn create_one_in_two_zkp_yes(
     g_x: ProjectivePoint,
     g_y: ProjectivePoint,
     x: Scalar,
+    context: &ChallengeContext,
 ) -> OneInTwoZKP {
     let rng = thread_rng();
 
@@ -58,9 +70,8 @@ pub fn create_one_in_two_zkp_yes(
     let a2 = ProjectivePoint::GENERATOR * w.clone();
     let b2 = g_y * w.clone();
 
-    // c = H(g^x, y, a1, b1, a2, b2)
-    let value_to_hash = g_x.clone() + y.clone() + a1.clone() + b1.clone() + a2.clone() + b2.clone();
-    let c = hash_to_scalar(value_to_hash.to_bytes().to_vec());
+    // c = H(domain, context, g^x, y, a1, b1, a2, b2)
+    let c = hash_to_challenge(ONE_IN_TWO_DOMAIN, context, &[g_x, y, a1, b1, a2, b2]);
 
     let d2: Scalar = c - d1.clone();
     let r2 = w - (x * d2.clone());
@@ -73,6 +84,7 @@ pub fn create_one_in_two_zkp_no(
     g_x: ProjectivePoint,
     g_y: ProjectivePoint,
     x: Scalar,
+    context: &ChallengeContext,
 ) -> OneInTwoZKP {
     let rng = thread_rng();
 
@@ -88,9 +100,8 @@ pub fn create_one_in_two_zkp_no(
     let a2 = (ProjectivePoint::GENERATOR * r2.clone()) + (g_x.clone() * d2.clone());
     let b2 = (g_y.clone() * r2.clone()) + ((y.clone() - ProjectivePoint::GENERATOR) * d2.clone());
 
-    // c = H(g^x, y, a1, b1, a2, b2)
-    let value_to_hash = g_x.clone() + y.clone() + a1.clone() + b1.clone() + a2.clone() + b2.clone();
-    let c = hash_to_scalar(value_to_hash.to_bytes().to_vec());
+    // c = H(domain, context, g^x, y, a1, b1, a2, b2)
+    let c = hash_to_challenge(ONE_IN_TWO_DOMAIN, context, &[g_x, y, a1, b1, a2, b2]);
 
     let d1 = c - d2.clone();
     let r1 = w - (x * d1.clone());
@@ -98,6 +109,64 @@ pub fn create_one_in_two_zkp_no(
     OneInTwoZKP::new(r1, r2, d1, d2, g_x, y, a1, b1, a2, b2)
 }
 
+/// Create one-in-k ZKP for a vote with weight w on one of the k options, encoded as g^(w*base^vote_option)
+pub fn create_one_in_k_zkp(
+    g_x: ProjectivePoint,
+    g_y: ProjectivePoint,
+    x: Scalar,
+    vote_option: u32,
+    number_of_options: u32,
+    base: u64,
+    weight: u64,
+    context: &ChallengeContext,
+) -> OneInKZKP {
+    let rng = thread_rng();
+
+    let y = (g_y.clone() * x.clone())
+        + (ProjectivePoint::GENERATOR * encode_vote_option(vote_option, base, weight));
+
+    let mut r = Vec::new();
+    let mut d = Vec::new();
+    let mut a = Vec::new();
+    let mut b = Vec::new();
+
+    // Simulate the proof for every option except the one voted for, which is committed to with w
+    let w = Scalar::random(rng.clone());
+    for i in 0..number_of_options {
+        if i == vote_option {
+            r.push(Scalar::ZERO);
+            d.push(Scalar::ZERO);
+            a.push(ProjectivePoint::GENERATOR * w.clone());
+            b.push(g_y.clone() * w.clone());
+        } else {
+            let r_i = Scalar::random(rng.clone());
+            let d_i = Scalar::random(rng.clone());
+            let g_v = ProjectivePoint::GENERATOR * encode_vote_option(i, base, weight);
+
+            r.push(r_i.clone());
+            d.push(d_i.clone());
+            a.push((ProjectivePoint::GENERATOR * r_i.clone()) + (g_x.clone() * d_i.clone()));
+            b.push((g_y.clone() * r_i) + ((y.clone() - g_v) * d_i));
+        }
+    }
+
+    // c = H(domain, context, g^x, y, a1, b1, ..., ak, bk)
+    let mut points_to_hash = vec![g_x, y];
+    for i in 0..number_of_options as usize {
+        points_to_hash.push(a[i]);
+        points_to_hash.push(b[i]);
+    }
+    let c = hash_to_challenge(ONE_IN_K_DOMAIN, context, &points_to_hash);
+
+    // Fix d and r of the option voted for, such that the d's sum to c
+    let option = vote_option as usize;
+    let d_sum = d.iter().fold(Scalar::ZERO, |sum, d_i| sum + d_i);
+    d[option] = c - d_sum;
+    r[option] = w - (x * d[option].clone());
+
+    OneInKZKP::new(r, d, a, b)
+}
+
 /// Compute a voter's reconstructed key (g^y) from their voting key (g^x) and all other voting keys in a given vote
 /// Note: It's important that the list of keys is in the same order for all voters
 pub fn compute_reconstructed_key(
@@ -146,30 +215,100 @@ pub fn commit_to_vote(x: &Scalar, g_y: &ProjectivePoint, g_v: ProjectivePoint) -
     Sha256::digest(&g_xy_g_v.to_bytes().to_vec()).to_vec()
 }
 
-/// Create a merkle tree for storing its root in the contract via the voteconfig
-pub fn create_merkle_tree(leaf_values: &Vec<AccountAddress>) -> MerkleTree<merkle_sha256> {
+/// Compute a voter's recovery base (ĝ) from the voting keys of the voters who dropped out
+/// Note: The list of keys must be in the same order as used for the reconstructed keys, i.e. the order of the voters in the contract
+pub fn compute_recovery_base(
+    keys: &Vec<ProjectivePoint>,
+    missing_keys: &Vec<ProjectivePoint>,
+    g_x: ProjectivePoint,
+) -> ProjectivePoint {
+    //Get our key's position in the list of voting keys
+    let position = keys.iter().position(|k| *k == g_x.clone()).unwrap();
+
+    let mut recovery_base = ProjectivePoint::IDENTITY;
+    for (j, key) in keys.iter().enumerate() {
+        if !missing_keys.contains(key) {
+            continue;
+        }
+
+        // add missing keys before your position and subtract missing keys after your position
+        if j < position {
+            recovery_base += key.clone();
+        } else if j > position {
+            recovery_base -= key.clone();
+        }
+    }
+    recovery_base
+}
+
+/// Create a Chaum-Pedersen ZKP (g^w, h^w, r = w - xz) that g^x and h^x share the exponent x
+pub fn create_chaum_pedersen_zkp(
+    g_x: ProjectivePoint,
+    h: ProjectivePoint,
+    x: Scalar,
+    context: &ChallengeContext,
+) -> ChaumPedersenProof {
+    let rng = thread_rng();
+
+    let w = Scalar::random(rng);
+    let g_w = ProjectivePoint::GENERATOR * w;
+    let h_w = h * w;
+    let h_x = h * x;
+
+    // Create hash z = H(domain, context, g, h, g^x, h^x, g^w, h^w)
+    let z = hash_to_challenge(
+        CHAUM_PEDERSEN_DOMAIN,
+        context,
+        &[ProjectivePoint::GENERATOR, h, g_x, h_x, g_w, h_w],
+    );
+
+    let r = w - x * z;
+
+    ChaumPedersenProof::new(g_w, h_w, r)
+}
+
+/// Create the recovery key (ĝ^x) and its Chaum-Pedersen ZKP, which a voter sends in the recovery phase
+/// to cancel out the voting keys of the voters who dropped out
+pub fn create_recovery_key(
+    keys: &Vec<ProjectivePoint>,
+    missing_keys: &Vec<ProjectivePoint>,
+    g_x: ProjectivePoint,
+    x: Scalar,
+    context: &ChallengeContext,
+) -> (ProjectivePoint, ChaumPedersenProof) {
+    let recovery_base = compute_recovery_base(keys, missing_keys, g_x);
+
+    (
+        recovery_base * x,
+        create_chaum_pedersen_zkp(g_x, recovery_base, x, context),
+    )
+}
+
+/// Create a merkle tree of the voters' accounts and weights for storing its root in the contract via the voteconfig
+pub fn create_merkle_tree(leaf_values: &Vec<(AccountAddress, u64)>) -> MerkleTree<merkle_sha256> {
     let mut leaves: Vec<[u8; 32]> = Vec::new();
     leaves.extend(
         leaf_values
             .iter()
-            .map(|x| merkle_sha256::hash(&to_bytes(x))),
+            .map(|(account, weight)| merkle_leaf(account, *weight)),
     );
 
-    let merkle_tree = MerkleTree::<merkle_sha256>::from_leaves(&leaves);
+    let merkle_tree = MerkleTree::<merkle_sha256>::par_from_leaves(&leaves);
 
     merkle_tree
 }
 
-/// Create a merkle proof-of-membership via your AccountAddress and the tree itself
+/// Create a merkle proof-of-membership via your AccountAddress, your weight and the tree itself
 pub fn create_merkle_proof(
     account: AccountAddress,
+    weight: u64,
     merkle_tree: &MerkleTree<merkle_sha256>,
 ) -> util::MerkleProof {
     let leaves = merkle_tree.leaves().unwrap();
     let index_to_prove = leaves
         .iter()
-        .position(|&l| l == merkle_sha256::hash(&to_bytes(&account)))
-        .ok_or("Can't get index to prove. AccountAddress not in MerkleTree")
+        .position(|&l| l == merkle_leaf(&account, weight))
+        .ok_or("Can't get index to prove. AccountAddress and weight not in MerkleTree")
         .unwrap();
 
     let leaf_to_prove = leaves
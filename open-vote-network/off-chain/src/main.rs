@@ -1,49 +1,435 @@
-//! Rust binary entry point for locally creating binary files of voter messages, for the purpose of testing a full election on-chain.
+//! Rust binary entry point for a voter to locally create binary files of their messages to the *voting* contract.
 //!
-//! In order to make an actual vote, and not just run this test, this program needs to be modified in 3 ways:
+//! Each voter runs the commands below on their own machine, such that their secret x never leaves it:
 //!
-//! 1. Needs to take an argument for the vote.
-//! 2. Every voter needs a way to get eachothers reconstructed keys (g_y).
-//!    This could be done by modifying the program such that it only creates one message at a time (through some argument)
-//!    and adding a getter (view function) to the smart contract to retrieve reconstructed keys.
-//! 3. A way of publishing the merkle tree to all voters.
+//! 1. `keygen --keystore <file>` creates a voting key pair and saves the secret x in a local keystore file.
+//! 2. `register --keystore <file> --voters <file> --account <address> --contract <index> --out <file>` creates the register message,
+//!    where the voters file is the published list of authorized accounts and their weights (one `<address> [weight]` per line,
+//!    the weight defaulting to 1) that the merkle tree is built from. The weight is saved in the keystore.
+//! 3. `commit --keystore <file> --config <file> --voting-keys <file> (--yes | --no | --option <i>) --out <file>`
+//!    creates the commit message, where the voting keys file is the JSON returned by the contract's `view_voting_keys`
+//!    and the config file is the JSON of the vote config.
+//! 4. `vote --keystore <file> --config <file> --voting-keys <file> --account <address> --contract <index> (--yes | --no | --option <i>) --out <file>`
+//!    creates the vote message for the same option as committed to.
+//! 5. `recover --keystore <file> --voting-keys <file> --statuses <file> --account <address> --contract <index> --out <file>`
+//!    creates the recovery message if some voters dropped out before voting, where the statuses file is the JSON returned
+//!    by the contract's `view_voters`.
+//!
+//! The ZKPs are bound to the account sending the message and the index of the *voting* contract instance,
+//! so the register, vote and recovery messages are only accepted from that account by that instance.
+//!
+//! The `simulate --contract <index>` command creates the messages of all 40 test voters at once, for the purpose of testing a full election on-chain.
 //!
 //! Ideally, a simple decentralized app would provide an interface to the above, such that voter's wouldn't need to download and run this code and call the contract directly themselves.
 
 use base58check::*;
 use concordium_std::*;
 use group::GroupEncoding;
-use k256::{ProjectivePoint, Scalar};
+use k256::elliptic_curve::{PublicKey, SecretKey};
+use k256::{ProjectivePoint, Scalar, Secp256k1};
 use rs_merkle::algorithms::Sha256 as merkle_sha256;
 use rs_merkle::*;
 use serde_json::json;
 use std::fs;
-use std::fs::File;
-use std::io::{Error, Write};
+use std::fs::{File, OpenOptions};
+use std::io::{Error, ErrorKind, Write};
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::Path;
 use std::str::FromStr;
+use util::ChallengeContext;
 use voting::*;
 
 pub mod lib;
 
-/// Entry point taking an argument of the number of voter's to create messages for (cargo run)
+const USAGE: &str =
+    "Usage: off-chain <keygen | register | commit | vote | recover | simulate> [options]";
+
+/// Entry point taking the command to run and its options (cargo run -- <command> [options])
 fn main() -> Result<(), Error> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.first().map(|command| command.as_str()) {
+        Some("keygen") => run_keygen(&args[1..]),
+        Some("register") => run_register(&args[1..]),
+        Some("commit") => run_commit(&args[1..]),
+        Some("vote") => run_vote(&args[1..]),
+        Some("recover") => run_recover(&args[1..]),
+        Some("simulate") => run_simulation(&args[1..]),
+        _ => Err(invalid_input(USAGE)),
+    }
+}
+
+/// Creates a voting key pair (x, g^x) and saves x in a new keystore file
+fn run_keygen(args: &[String]) -> std::io::Result<()> {
+    let keystore = get_arg(args, "--keystore")?;
+
+    // Never overwrite an existing secret
+    if Path::new(keystore).exists() {
+        return Err(invalid_input("Keystore already exists"));
+    }
+
+    let (x, _) = lib::create_votingkey_pair();
+
+    write_keystore(keystore, &json!({ "x": x.to_bytes().to_vec() }), true)
+}
+
+/// Creates the register message of the voter with the voting key from the keystore.
+/// The weight of the voter is saved in the keystore, as it is needed for the commit and vote messages
+fn run_register(args: &[String]) -> std::io::Result<()> {
+    let keystore = get_arg(args, "--keystore")?;
+    let (x, g_x) = read_keystore(keystore)?;
+    let voters = read_voter_accounts(get_arg(args, "--voters")?)?;
+    let account = get_account(args)?;
+    let contract = get_contract(args)?;
+
+    let weight = match voters.iter().find(|(voter, _)| *voter == account) {
+        Some((_, weight)) => *weight,
+        None => return Err(invalid_input("Account is not in the list of voters")),
+    };
+
+    let merkle_tree = lib::create_merkle_tree(&voters);
+
+    let context = ChallengeContext::new(contract, account, &types::VotingPhase::Registration);
+    let register_msg = RegisterMessage {
+        voting_key: g_x.to_bytes().to_vec(),
+        voting_key_zkp: lib::create_schnorr_zkp(g_x, x, &context),
+        merkle_proof: lib::create_merkle_proof(account, weight, &merkle_tree),
+        weight,
+    };
+
+    fs::write(get_arg(args, "--out")?, to_bytes(&register_msg))?;
+
+    update_keystore(keystore, "weight", json!(weight))
+}
+
+/// Creates the commit message of the voter, committing to the chosen option.
+/// The option is saved in the keystore, such that the vote can be checked against it
+fn run_commit(args: &[String]) -> std::io::Result<()> {
+    let keystore = get_arg(args, "--keystore")?;
+    let (x, g_x) = read_keystore(keystore)?;
+    let (voting_options, base) = read_vote_config(get_arg(args, "--config")?)?;
+    let vote_option = get_vote_option(args, &voting_options)?;
+    let g_y = get_reconstructed_key(get_arg(args, "--voting-keys")?, g_x)?;
+    let weight = read_keystore_weight(keystore)?;
+
+    let g_v = ProjectivePoint::GENERATOR * util::encode_vote_option(vote_option, base, weight);
+
+    let commit_msg = CommitMessage {
+        reconstructed_key: g_y.to_bytes().to_vec(),
+        commitment: lib::commit_to_vote(&x, &g_y, g_v),
+    };
+
+    fs::write(get_arg(args, "--out")?, to_bytes(&commit_msg))?;
+
+    update_keystore(keystore, "vote_option", json!(vote_option))
+}
+
+/// Creates the vote message of the voter, along with the one-in-k ZKP for the chosen option
+fn run_vote(args: &[String]) -> std::io::Result<()> {
+    let keystore = get_arg(args, "--keystore")?;
+    let (x, g_x) = read_keystore(keystore)?;
+    let (voting_options, base) = read_vote_config(get_arg(args, "--config")?)?;
+    let vote_option = get_vote_option(args, &voting_options)?;
+    let g_y = get_reconstructed_key(get_arg(args, "--voting-keys")?, g_x)?;
+    let account = get_account(args)?;
+    let contract = get_contract(args)?;
+    let weight = read_keystore_weight(keystore)?;
+
+    // The vote must match the commitment, otherwise the contract rejects it
+    let keystore_json: serde_json::Value = serde_json::from_str(&fs::read_to_string(keystore)?)?;
+    if let Some(committed_option) = keystore_json["vote_option"].as_u64() {
+        if committed_option != vote_option as u64 {
+            return Err(invalid_input(
+                "Option does not match the option committed to",
+            ));
+        }
+    }
+
+    let g_v = ProjectivePoint::GENERATOR * util::encode_vote_option(vote_option, base, weight);
+
+    let context = ChallengeContext::new(contract, account, &types::VotingPhase::Vote);
+    let vote_msg = VoteMessage {
+        vote: ((g_y * x) + g_v).to_bytes().to_vec(),
+        vote_zkp: lib::create_one_in_k_zkp(
+            g_x,
+            g_y,
+            x,
+            vote_option,
+            voting_options.len() as u32,
+            base,
+            weight,
+            &context,
+        ),
+    };
+
+    fs::write(get_arg(args, "--out")?, to_bytes(&vote_msg))
+}
+
+/// Creates the recovery message of the voter, cancelling out the voting keys of the voters who dropped out before voting
+fn run_recover(args: &[String]) -> std::io::Result<()> {
+    let (x, g_x) = read_keystore(get_arg(args, "--keystore")?)?;
+    let voting_keys = read_voting_keys(get_arg(args, "--voting-keys")?)?;
+    let dropped_out = read_dropped_out_voters(get_arg(args, "--statuses")?)?;
+    let account = get_account(args)?;
+    let contract = get_contract(args)?;
+
+    let keys: Vec<ProjectivePoint> = voting_keys.iter().map(|(_, key)| *key).collect();
+    let missing_keys: Vec<ProjectivePoint> = voting_keys
+        .iter()
+        .filter(|(voter, _)| dropped_out.contains(voter))
+        .map(|(_, key)| *key)
+        .collect();
+
+    if !keys.contains(&g_x) {
+        return Err(invalid_input(
+            "Voting key of the keystore is not registered",
+        ));
+    }
+    if missing_keys.is_empty() {
+        return Err(invalid_input(
+            "No voters dropped out, so there is nothing to recover",
+        ));
+    }
+
+    let context = ChallengeContext::new(contract, account, &types::VotingPhase::Recovery);
+    let (recovery_key, recovery_zkp) =
+        lib::create_recovery_key(&keys, &missing_keys, g_x, x, &context);
+    let recovery_msg = RecoveryMessage {
+        recovery_key: recovery_key.to_bytes().to_vec(),
+        recovery_zkp,
+    };
+
+    fs::write(get_arg(args, "--out")?, to_bytes(&recovery_msg))
+}
+
+/// Creates the messages of all test voters, voting "Yes", for testing a full election on-chain
+fn run_simulation(args: &[String]) -> std::io::Result<()> {
+    let contract = get_contract(args)?;
     let (merkle_tree, voter_accounts) = make_voteconfig_json()?;
+    // Encode the votes with the same base as the contract does, from the vote config it is set up with
+    let (_, base) = read_vote_config("../voting/parameters/voteconfig.json")?;
 
     let (list_of_scalar, list_of_voting_keys) =
-        make_register_msg(merkle_tree, voter_accounts)?;
+        make_register_msg(merkle_tree, voter_accounts.clone(), contract)?;
 
     let list_of_reconstructed_keys =
-        make_commit_msg(list_of_scalar.clone(), list_of_voting_keys.clone())?;
+        make_commit_msg(list_of_scalar.clone(), list_of_voting_keys.clone(), base)?;
 
     make_vote_msg(
         list_of_scalar,
         list_of_voting_keys,
         list_of_reconstructed_keys,
+        voter_accounts,
+        contract,
+        base,
     )?;
 
     Ok(())
 }
 
+fn invalid_input(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidInput, message)
+}
+
+/// Get the value following a flag, e.g. the file of `--keystore <file>`
+fn get_arg<'a>(args: &'a [String], flag: &str) -> std::io::Result<&'a str> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(|value| value.as_str())
+        .ok_or_else(|| invalid_input(&format!("Missing {} <value>\n{}", flag, USAGE)))
+}
+
+/// Get the account address of `--account <address>`
+fn get_account(args: &[String]) -> std::io::Result<AccountAddress> {
+    AccountAddress2::from_str(get_arg(args, "--account")?)
+        .map(|account| account.0)
+        .map_err(|_| invalid_input("Invalid account address"))
+}
+
+/// Get the address of the contract instance from `--contract <index>`
+fn get_contract(args: &[String]) -> std::io::Result<ContractAddress> {
+    let index = get_arg(args, "--contract")?
+        .parse::<u64>()
+        .map_err(|_| invalid_input("Contract index must be a number"))?;
+
+    Ok(ContractAddress { index, subindex: 0 })
+}
+
+/// Get the chosen option from `--yes`, `--no` (matched with the names of the options) or `--option <i>`
+fn get_vote_option(args: &[String], voting_options: &Vec<String>) -> std::io::Result<u32> {
+    let find_option = |name: &str| {
+        voting_options
+            .iter()
+            .position(|option| option.eq_ignore_ascii_case(name))
+            .map(|i| i as u32)
+            .ok_or_else(|| invalid_input(&format!("The vote has no \"{}\" option", name)))
+    };
+
+    let vote_option = if args.iter().any(|arg| arg == "--yes") {
+        find_option("yes")?
+    } else if args.iter().any(|arg| arg == "--no") {
+        find_option("no")?
+    } else {
+        get_arg(args, "--option")?
+            .parse::<u32>()
+            .map_err(|_| invalid_input("Option must be a number"))?
+    };
+
+    if vote_option as usize >= voting_options.len() {
+        return Err(invalid_input("Option is not one of the voting options"));
+    }
+    Ok(vote_option)
+}
+
+/// Read the secret x from the keystore file and compute the voting key g^x
+fn read_keystore(path: &str) -> std::io::Result<(Scalar, ProjectivePoint)> {
+    let keystore: serde_json::Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+    let bytes: Vec<u8> = serde_json::from_value(keystore["x"].clone())?;
+
+    let x: Scalar = *SecretKey::<Secp256k1>::from_be_bytes(&bytes)
+        .map_err(|_| invalid_input("Keystore does not contain a valid secret"))?
+        .to_nonzero_scalar();
+
+    Ok((x, ProjectivePoint::GENERATOR * x))
+}
+
+/// Read the weight of the voter saved in the keystore when registering
+fn read_keystore_weight(path: &str) -> std::io::Result<u64> {
+    let keystore: serde_json::Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+
+    keystore["weight"]
+        .as_u64()
+        .ok_or_else(|| invalid_input("Keystore is missing the weight, register first"))
+}
+
+/// Set a field of the keystore, keeping the other fields (e.g. the secret x)
+fn update_keystore(path: &str, field: &str, value: serde_json::Value) -> std::io::Result<()> {
+    let mut keystore: serde_json::Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+    keystore[field] = value;
+
+    write_keystore(path, &keystore, false)
+}
+
+/// Write the keystore, such that only the owner can read and write it, as it contains the secret x.
+/// A new keystore fails if the file already exists, so a secret is never overwritten
+fn write_keystore(path: &str, keystore: &serde_json::Value, new: bool) -> std::io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).truncate(true).create_new(new);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(path)?;
+    // The mode only applies to new files, so also restrict keystores created before
+    #[cfg(unix)]
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+
+    file.write_all(serde_json::to_string_pretty(keystore)?.as_bytes())
+}
+
+/// Read the list of authorized voter accounts and their weights, one `<address> [weight]` per line
+fn read_voter_accounts(path: &str) -> std::io::Result<Vec<(AccountAddress, u64)>> {
+    fs::read_to_string(path)?
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let mut fields = line.split_whitespace();
+            let account = fields.next().unwrap_or_default();
+
+            let account = AccountAddress2::from_str(account)
+                .map(|account| account.0)
+                .map_err(|_| invalid_input(&format!("Invalid account address: {}", line)))?;
+            let weight = match fields.next() {
+                Some(weight) => weight
+                    .parse::<u64>()
+                    .map_err(|_| invalid_input(&format!("Invalid weight: {}", line)))?,
+                None => 1,
+            };
+            Ok((account, weight))
+        })
+        .collect()
+}
+
+/// Read the voting options and the base they are encoded with (max total weight + 1) from the vote config JSON
+fn read_vote_config(path: &str) -> std::io::Result<(Vec<String>, u64)> {
+    let config: serde_json::Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+    let voting_options: Vec<String> = serde_json::from_value(config["voting_options"].clone())?;
+    let max_total_weight = config["max_total_weight"]
+        .as_u64()
+        .ok_or_else(|| invalid_input("Vote config is missing max_total_weight"))?;
+
+    let base = max_total_weight
+        .checked_add(1)
+        .ok_or_else(|| invalid_input("Vote config max_total_weight is too large"))?;
+
+    Ok((voting_options, base))
+}
+
+/// Read the voting keys from the JSON returned by the contract's `view_voting_keys`, along with the (JSON) account of
+/// each voter, in the order of the voters
+fn read_voting_keys(path: &str) -> std::io::Result<Vec<(serde_json::Value, ProjectivePoint)>> {
+    let view: serde_json::Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+    let entries = view["voting_keys"]
+        .as_array()
+        .ok_or_else(|| invalid_input("Voting keys file is missing voting_keys"))?;
+
+    let mut keys = Vec::new();
+    for entry in entries {
+        let bytes: Vec<u8> = serde_json::from_value(entry[1].clone())?;
+        let key = PublicKey::<Secp256k1>::from_sec1_bytes(&bytes)
+            .map_err(|_| invalid_input("Voting keys file contains an invalid voting key"))?;
+        keys.push((entry[0].clone(), key.to_projective()));
+    }
+    Ok(keys)
+}
+
+/// Read the (JSON) accounts of the voters who dropped out before voting, i.e. whose status is still registered or
+/// committed, from the JSON returned by the contract's `view_voters`
+fn read_dropped_out_voters(path: &str) -> std::io::Result<Vec<serde_json::Value>> {
+    let view: serde_json::Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+    let entries = view["voters"]
+        .as_array()
+        .ok_or_else(|| invalid_input("Statuses file is missing voters"))?;
+
+    // An enum without fields is either a string or an object with the variant as its only key
+    let status = |entry: &serde_json::Value| match &entry[1] {
+        serde_json::Value::String(status) => Some(status.clone()),
+        serde_json::Value::Object(status) => status.keys().next().cloned(),
+        _ => None,
+    };
+
+    Ok(entries
+        .iter()
+        .filter(|entry| {
+            matches!(
+                status(entry).as_deref(),
+                Some("Registered") | Some("Committed")
+            )
+        })
+        .map(|entry| entry[0].clone())
+        .collect())
+}
+
+/// Compute the reconstructed key g^y from the JSON returned by the contract's `view_voting_keys`,
+/// which lists the voting keys in the order of the voters
+fn get_reconstructed_key(path: &str, g_x: ProjectivePoint) -> std::io::Result<ProjectivePoint> {
+    let keys: Vec<ProjectivePoint> = read_voting_keys(path)?
+        .into_iter()
+        .map(|(_, key)| key)
+        .collect();
+
+    if !keys.contains(&g_x) {
+        return Err(invalid_input(
+            "Voting key of the keystore is not registered",
+        ));
+    }
+    Ok(lib::compute_reconstructed_key(&keys, g_x))
+}
+
+/// The options voters can choose between in the test election
+const VOTING_OPTIONS: [&str; 2] = ["Yes", "No"];
+
 pub struct AccountAddress2(AccountAddress);
 
 impl FromStr for AccountAddress2 {
@@ -186,7 +572,10 @@ pub fn make_voteconfig_json() -> std::io::Result<(MerkleTree<merkle_sha256>, Vec
             .0,
     ];
 
-    let merkle_tree = lib::create_merkle_tree(&voter_accounts);
+    // All test voters have a weight of 1
+    let voters: Vec<(AccountAddress, u64)> =
+        voter_accounts.iter().map(|account| (*account, 1)).collect();
+    let merkle_tree = lib::create_merkle_tree(&voters);
     let root = merkle_tree
         .root_hex()
         .ok_or("Couldn't get the merkle root")
@@ -196,11 +585,15 @@ pub fn make_voteconfig_json() -> std::io::Result<(MerkleTree<merkle_sha256>, Vec
     let json = json!({
         "merkle_root": root,
         "merkle_leaf_count": merkle_tree.leaves_len(),
+        "max_total_weight": merkle_tree.leaves_len(),
         "voting_question": "Vote for x",
+        "voting_options": VOTING_OPTIONS,
         "deposit": "1000000",
+        "beneficiary": "4SxRVot39zszDDGe1jqprRHbF3D13EJ4MA7i2BMK88kfqG74TB",
         "registration_timeout": "2022-05-26T23:05:01Z",
         "commit_timeout": "2022-05-26T23:06:01Z",
-        "vote_timeout": "2022-05-26T23:07:01Z"
+        "vote_timeout": "2022-05-26T23:07:01Z",
+        "recovery_timeout": "2022-05-26T23:08:01Z"
     });
 
     std::fs::write(
@@ -215,13 +608,16 @@ pub fn make_voteconfig_json() -> std::io::Result<(MerkleTree<merkle_sha256>, Vec
 pub fn make_register_msg(
     merkle_tree: MerkleTree<merkle_sha256>,
     accounts: Vec<AccountAddress>,
+    contract: ContractAddress,
 ) -> std::io::Result<(Vec<Scalar>, Vec<ProjectivePoint>)> {
     let mut list_of_scalar: Vec<Scalar> = Vec::new();
     let mut list_of_voting_keys: Vec<ProjectivePoint> = Vec::new();
 
     for i in 0..40 as usize {
         let (x, g_x) = lib::create_votingkey_pair();
-        let schnorr = lib::create_schnorr_zkp(g_x, x);
+        let context =
+            ChallengeContext::new(contract, accounts[i], &types::VotingPhase::Registration);
+        let schnorr = lib::create_schnorr_zkp(g_x, x, &context);
 
         fs::create_dir_all("../voting/parameters/register_msgs")?;
 
@@ -231,7 +627,8 @@ pub fn make_register_msg(
         let register_msg = RegisterMessage {
             voting_key: g_x.to_bytes().to_vec(),
             voting_key_zkp: schnorr,
-            merkle_proof: lib::create_merkle_proof(accounts[i], &merkle_tree),
+            merkle_proof: lib::create_merkle_proof(accounts[i], 1, &merkle_tree),
+            weight: 1,
         };
 
         list_of_scalar.push(x);
@@ -246,6 +643,7 @@ pub fn make_register_msg(
 pub fn make_commit_msg(
     list_of_scalar: Vec<Scalar>,
     list_of_voting_keys: Vec<ProjectivePoint>,
+    base: u64,
 ) -> std::io::Result<Vec<ProjectivePoint>> {
     let mut list_of_reconstructed_keys: Vec<ProjectivePoint> = Vec::new();
 
@@ -253,8 +651,8 @@ pub fn make_commit_msg(
         let g_y =
             off_chain::compute_reconstructed_key(&list_of_voting_keys, list_of_voting_keys[i]);
 
-        // Currently hardcoded such that all voters will commit to voting "yes"
-        let g_v = ProjectivePoint::GENERATOR;
+        // Currently hardcoded such that all voters will commit to voting for the first option ("Yes")
+        let g_v = ProjectivePoint::GENERATOR * util::encode_vote_option(0, base, 1);
 
         let commitment = off_chain::commit_to_vote(&list_of_scalar[i], &g_y, g_v);
 
@@ -276,20 +674,29 @@ pub fn make_commit_msg(
     Ok(list_of_reconstructed_keys)
 }
 
-/// Generates vote and its one-in-two ZKP to create vote messages as binaries
+/// Generates vote and its one-in-k ZKP to create vote messages as binaries
 pub fn make_vote_msg(
     list_of_scalar: Vec<Scalar>,
     list_of_voting_keys: Vec<ProjectivePoint>,
     list_of_reconstructed_keys: Vec<ProjectivePoint>,
+    accounts: Vec<AccountAddress>,
+    contract: ContractAddress,
+    base: u64,
 ) -> std::io::Result<()> {
     for i in 0..list_of_voting_keys.clone().len() {
-        // Hardcoded such that all voters vote "yes"
-        let vote = (list_of_reconstructed_keys[i] * list_of_scalar[i]) + ProjectivePoint::GENERATOR;
+        // Hardcoded such that all voters vote for the first option ("Yes")
+        let vote = (list_of_reconstructed_keys[i] * list_of_scalar[i])
+            + (ProjectivePoint::GENERATOR * util::encode_vote_option(0, base, 1));
 
-        let vote_zkp = off_chain::create_one_in_two_zkp_yes(
+        let vote_zkp = off_chain::create_one_in_k_zkp(
             list_of_voting_keys[i],
             list_of_reconstructed_keys[i],
             list_of_scalar[i],
+            0,
+            VOTING_OPTIONS.len() as u32,
+            base,
+            1,
+            &ChallengeContext::new(contract, accounts[i], &types::VotingPhase::Vote),
         );
 
         let vote_msg = VoteMessage {
@@ -0,0 +1,65 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use rs_merkle::{algorithms::Sha256, Hasher, MerkleTree};
+
+const LEAVES_COUNTS: [usize; 3] = [1 << 10, 1 << 14, 100_000];
+
+fn leaves(count: usize) -> Vec<[u8; 32]> {
+    (0..count).map(|i| Sha256::hash(&i.to_le_bytes())).collect()
+}
+
+fn from_leaves(c: &mut Criterion) {
+    let mut group = c.benchmark_group("from_leaves");
+    group.sample_size(10);
+
+    for count in LEAVES_COUNTS.iter() {
+        let leaves = leaves(*count);
+        group.bench_with_input(
+            BenchmarkId::new("sequential", count),
+            &leaves,
+            |b, leaves| b.iter(|| MerkleTree::<Sha256>::from_leaves(black_box(leaves))),
+        );
+        group.bench_with_input(BenchmarkId::new("parallel", count), &leaves, |b, leaves| {
+            b.iter(|| MerkleTree::<Sha256>::par_from_leaves(black_box(leaves)))
+        });
+    }
+
+    group.finish();
+}
+
+fn proofs(c: &mut Criterion) {
+    let mut group = c.benchmark_group("proofs");
+    group.sample_size(10);
+
+    for count in LEAVES_COUNTS.iter() {
+        let tree = MerkleTree::<Sha256>::from_leaves(&leaves(*count));
+        let indices: Vec<usize> = (0..*count).collect();
+
+        group.bench_with_input(
+            BenchmarkId::new("one_by_one", count),
+            &indices,
+            |b, indices| {
+                b.iter(|| {
+                    indices
+                        .iter()
+                        .map(|i| tree.proof(&[*i]))
+                        .collect::<Vec<_>>()
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("sequential", count),
+            &indices,
+            |b, indices| b.iter(|| tree.proofs(black_box(indices))),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("parallel", count),
+            &indices,
+            |b, indices| b.iter(|| tree.par_proofs(black_box(indices))),
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, from_leaves, proofs);
+criterion_main!(benches);
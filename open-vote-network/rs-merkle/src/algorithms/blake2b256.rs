@@ -0,0 +1,45 @@
+use crate::{prelude::*, Hasher};
+use blake2::{
+    digest::{consts::U32, FixedOutput},
+    Blake2b, Digest,
+};
+
+/// BLAKE2b implementation of the [`Hasher`] trait, with a 32 byte output and no key, like the
+/// BLAKE2b hashes of the Concordium node tooling.
+///
+/// Needs the `blake2b` feature.
+///
+/// # Examples
+///
+/// ```
+/// # use rs_merkle::{MerkleTree, algorithms::Blake2b256, Hasher};
+/// let leaves: Vec<[u8; 32]> = ["a", "b", "c"]
+///     .iter()
+///     .map(|x| Blake2b256::hash(x.as_bytes()))
+///     .collect();
+///
+/// let tree = MerkleTree::<Blake2b256>::from_leaves(&leaves);
+/// assert_eq!(
+///     tree.root_hex(),
+///     Some("350bf288b7179755b0d6f6e91f8e6fa5b2ac2d8bcf6d0b3882b81a2d3171bc8c".to_string())
+/// );
+/// ```
+///
+/// [`Hasher`]: crate::Hasher
+#[derive(Clone)]
+pub struct Blake2b256Algorithm {}
+
+impl Hasher for Blake2b256Algorithm {
+    type Hash = [u8; 32];
+
+    fn hash(data: &[u8]) -> [u8; 32] {
+        let mut hasher = Blake2b::<U32>::new();
+
+        hasher.update(data);
+        <[u8; 32]>::from(hasher.finalize_fixed())
+    }
+
+    fn identifier() -> String {
+        String::from("blake2b-256")
+    }
+}
@@ -0,0 +1,43 @@
+use crate::{prelude::*, Hasher};
+use sha3::{digest::FixedOutput, Digest, Keccak256};
+
+/// Keccak-256 implementation of the [`Hasher`] trait, the hash used by Ethereum. It is not the
+/// same as the standardized SHA3-256, see [`Sha3_256`] for that one.
+///
+/// Needs the `keccak` feature.
+///
+/// # Examples
+///
+/// ```
+/// # use rs_merkle::{MerkleTree, algorithms::Keccak256, Hasher};
+/// let leaves: Vec<[u8; 32]> = ["a", "b", "c"]
+///     .iter()
+///     .map(|x| Keccak256::hash(x.as_bytes()))
+///     .collect();
+///
+/// let tree = MerkleTree::<Keccak256>::from_leaves(&leaves);
+/// assert_eq!(
+///     tree.root_hex(),
+///     Some("aff1208e69c9e8be9b584b07ebac4e48a1ee9d15ce3afe20b77a4d29e4175aa3".to_string())
+/// );
+/// ```
+///
+/// [`Hasher`]: crate::Hasher
+/// [`Sha3_256`]: crate::algorithms::Sha3_256
+#[derive(Clone)]
+pub struct Keccak256Algorithm {}
+
+impl Hasher for Keccak256Algorithm {
+    type Hash = [u8; 32];
+
+    fn hash(data: &[u8]) -> [u8; 32] {
+        let mut hasher = Keccak256::new();
+
+        hasher.update(data);
+        <[u8; 32]>::from(hasher.finalize_fixed())
+    }
+
+    fn identifier() -> String {
+        String::from("keccak256")
+    }
+}
@@ -1,6 +1,21 @@
 //! This module contains built-in implementations of the [`Hasher`]
 //!
 //! [`Hasher`]: crate::Hasher
+#[cfg(feature = "blake2b")]
+mod blake2b256;
+#[cfg(feature = "keccak")]
+mod keccak256;
+mod rfc6962;
 mod sha256;
+#[cfg(feature = "sha3")]
+mod sha3_256;
 
+#[cfg(feature = "blake2b")]
+pub use blake2b256::Blake2b256Algorithm as Blake2b256;
+#[cfg(feature = "keccak")]
+pub use keccak256::Keccak256Algorithm as Keccak256;
+pub use rfc6962::Rfc6962;
+pub use rfc6962::{LEAF_PREFIX, NODE_PREFIX};
 pub use sha256::Sha256Algorithm as Sha256;
+#[cfg(feature = "sha3")]
+pub use sha3_256::Sha3Algorithm as Sha3_256;
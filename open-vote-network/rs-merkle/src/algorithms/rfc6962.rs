@@ -0,0 +1,69 @@
+use crate::{prelude::*, Hasher};
+use core::marker::PhantomData;
+
+/// Prefix of the data of a leaf before it is hashed
+pub const LEAF_PREFIX: u8 = 0x00;
+/// Prefix of the concatenated children of an internal node before they are hashed
+pub const NODE_PREFIX: u8 = 0x01;
+
+/// RFC 6962 style implementation of the [`Hasher`] trait on top of another hasher, which
+/// hashes leaves as `H(0x00 || data)` and internal nodes as `H(0x01 || left || right)`.
+///
+/// Since leaves and internal nodes can't be confused, an internal node can't be passed off as a
+/// leaf to forge a second preimage of the root, as long as the leaves are hashed with
+/// [`Hasher::hash`] of this hasher. A left node without a sibling is passed up unchanged, which
+/// gives the same tree as the one defined in [RFC 6962](https://www.rfc-editor.org/rfc/rfc6962#section-2.1),
+/// so the roots and proofs are compatible with Certificate Transparency logs.
+///
+/// # Examples
+///
+/// ```
+/// # use rs_merkle::{MerkleTree, algorithms::{Rfc6962, Sha256}, Hasher};
+/// let leaves: Vec<[u8; 32]> = ["a", "b", "c"]
+///     .iter()
+///     .map(|x| Rfc6962::<Sha256>::hash(x.as_bytes()))
+///     .collect();
+///
+/// let merkle_tree = MerkleTree::<Rfc6962<Sha256>>::from_leaves(&leaves);
+/// let proof = merkle_tree.proof(&[1]);
+///
+/// assert!(proof.verify(merkle_tree.root().unwrap(), &[1], &leaves[1..2], leaves.len()));
+/// ```
+///
+/// [`Hasher`]: crate::Hasher
+#[derive(Clone)]
+pub struct Rfc6962<T: Hasher> {
+    hasher: PhantomData<T>,
+}
+
+impl<T: Hasher> Hasher for Rfc6962<T> {
+    type Hash = T::Hash;
+
+    /// Hashes the data of a leaf as `H(0x00 || data)`
+    fn hash(data: &[u8]) -> T::Hash {
+        let mut prefixed = vec![LEAF_PREFIX];
+        prefixed.extend_from_slice(data);
+        T::hash(&prefixed)
+    }
+
+    /// Hashes an internal node as `H(0x01 || left || right)`
+    fn concat_and_hash(left: &T::Hash, right: Option<&T::Hash>) -> T::Hash {
+        match right {
+            Some(right_node) => {
+                let mut concatenated = vec![NODE_PREFIX];
+                concatenated.append(&mut (*left).into());
+                concatenated.append(&mut (*right_node).into());
+                T::hash(&concatenated)
+            }
+            None => *left,
+        }
+    }
+
+    fn hash_size() -> usize {
+        T::hash_size()
+    }
+
+    fn identifier() -> String {
+        format!("rfc6962-{}", T::identifier())
+    }
+}
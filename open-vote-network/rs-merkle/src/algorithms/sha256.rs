@@ -40,4 +40,8 @@ impl Hasher for Sha256Algorithm {
         hasher.update(data);
         <[u8; 32]>::from(hasher.finalize_fixed())
     }
+
+    fn identifier() -> String {
+        String::from("sha256")
+    }
 }
@@ -0,0 +1,41 @@
+use crate::{prelude::*, Hasher};
+use sha3::{digest::FixedOutput, Digest, Sha3_256};
+
+/// SHA3-256 implementation of the [`Hasher`] trait, as standardized in FIPS 202.
+///
+/// Needs the `sha3` feature.
+///
+/// # Examples
+///
+/// ```
+/// # use rs_merkle::{MerkleTree, algorithms::Sha3_256, Hasher};
+/// let leaves: Vec<[u8; 32]> = ["a", "b", "c"]
+///     .iter()
+///     .map(|x| Sha3_256::hash(x.as_bytes()))
+///     .collect();
+///
+/// let tree = MerkleTree::<Sha3_256>::from_leaves(&leaves);
+/// assert_eq!(
+///     tree.root_hex(),
+///     Some("b940dc53d707e4d9dfe9300664c6bbc4ab0c9f045d74441bfeda030cedbdbcba".to_string())
+/// );
+/// ```
+///
+/// [`Hasher`]: crate::Hasher
+#[derive(Clone)]
+pub struct Sha3Algorithm {}
+
+impl Hasher for Sha3Algorithm {
+    type Hash = [u8; 32];
+
+    fn hash(data: &[u8]) -> [u8; 32] {
+        let mut hasher = Sha3_256::new();
+
+        hasher.update(data);
+        <[u8; 32]>::from(hasher.finalize_fixed())
+    }
+
+    fn identifier() -> String {
+        String::from("sha3-256")
+    }
+}
@@ -0,0 +1,49 @@
+//! Implementations of the serialization traits of `concordium_std` for proofs, such that typed
+//! proofs can be part of contract parameters and be described by a contract schema. Enabled by
+//! the `concordium` feature.
+use crate::prelude::*;
+use crate::{Hasher, MerkleProof};
+use concordium_contracts_common::{
+    schema::{Fields, SchemaType, SizeLength, Type},
+    Deserial, Get, ParseError, ParseResult, Read, Serial, Write,
+};
+
+/// Serialized by writing an `u32` representing the number of proof hashes, followed by the
+/// hashes, from left to right, bottom to top, each taking [`Hasher::hash_size`] bytes.
+impl<T: Hasher> Serial for MerkleProof<T> {
+    fn serial<W: Write>(&self, out: &mut W) -> Result<(), W::Err> {
+        let len = self.proof_hashes().len() as u32;
+        len.serial(out)?;
+        for hash in self.proof_hashes() {
+            let bytes: Vec<u8> = (*hash).into();
+            out.write_all(&bytes)?;
+        }
+        Ok(())
+    }
+}
+
+/// Deserialized by reading an `u32` representing the number of proof hashes, then reading that
+/// many hashes of [`Hasher::hash_size`] bytes.
+impl<T: Hasher> Deserial for MerkleProof<T> {
+    fn deserial<R: Read>(source: &mut R) -> ParseResult<Self> {
+        let len: u32 = source.get()?;
+        let mut proof_hashes = Vec::new();
+        for _ in 0..len {
+            let mut bytes = vec![0u8; T::hash_size()];
+            source.read_exact(&mut bytes)?;
+            proof_hashes.push(T::Hash::try_from(bytes).map_err(|_| ParseError::default())?);
+        }
+        Ok(MerkleProof::new(proof_hashes))
+    }
+}
+
+/// Described as a struct with the list of proof hashes, where each hash is an array of bytes.
+impl<T: Hasher> SchemaType for MerkleProof<T> {
+    fn get_type() -> Type {
+        let hash_type = Type::Array(T::hash_size() as u32, Box::new(Type::U8));
+        Type::Struct(Fields::Named(vec![(
+            "proof_hashes".to_string(),
+            Type::List(SizeLength::U32, Box::new(hash_type)),
+        )]))
+    }
+}
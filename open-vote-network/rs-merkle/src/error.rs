@@ -21,6 +21,26 @@ pub enum ErrorKind {
     HashConversionError,
     NotEnoughHashesToCalculateRoot,
     LeavesIndicesCountMismatch,
+    /// There is no leaf at the index given to [`MerkleTree::update`] or [`MerkleTree::remove`].
+    ///
+    /// [`MerkleTree::update`]: crate::MerkleTree::update
+    /// [`MerkleTree::remove`]: crate::MerkleTree::remove
+    LeafIndexOutOfBounds,
+    /// The number of siblings in a [`SparseMerkleProof`] doesn't match its bitmap.
+    ///
+    /// [`SparseMerkleProof`]: crate::SparseMerkleProof
+    SiblingsCountMismatch,
+    /// A proof has more hashes than are needed to calculate the root.
+    UnusedProofHashes,
+    /// The sizes given for a consistency proof don't describe a non-empty tree and a tree at
+    /// least as big.
+    InvalidTreeSizes,
+    /// A tree file was written with a different hasher than the one it is opened with.
+    HasherMismatch,
+    /// A tree file is not in a format this version can read, or it is truncated.
+    UnsupportedFileFormat,
+    /// Reading or writing a tree file failed.
+    Io,
 }
 
 /// The error type for tree traversals/parsing errors of the [`MerkleProof`] and [`PartialTree`].
@@ -81,6 +101,73 @@ impl Error {
         )
     }
 
+    pub fn leaf_index_out_of_bounds(index: usize, leaves_len: usize) -> Self {
+        Self::new(
+            ErrorKind::LeafIndexOutOfBounds,
+            format!(
+                "leaf index {} is out of bounds for a tree of {} leaves",
+                index, leaves_len
+            ),
+        )
+    }
+
+    pub fn wrong_sparse_proof_size(proof_len: usize, expected_len: usize) -> Self {
+        Self::new(
+            ErrorKind::SerializedProofSizeIsIncorrect,
+            format!(
+                "sparse proof of size {} bytes doesn't match the expected size of {} bytes",
+                proof_len, expected_len,
+            ),
+        )
+    }
+
+    pub fn siblings_count_mismatch(bitmap_count: usize, siblings_len: usize) -> Self {
+        Self::new(
+            ErrorKind::SiblingsCountMismatch,
+            format!(
+                "bitmap marks {} siblings, but the proof has {}",
+                bitmap_count, siblings_len
+            ),
+        )
+    }
+
+    pub fn unused_proof_hashes(unused_count: usize) -> Self {
+        Self::new(
+            ErrorKind::UnusedProofHashes,
+            format!(
+                "proof has {} hashes that are not needed to calculate the root",
+                unused_count
+            ),
+        )
+    }
+
+    pub fn invalid_tree_sizes(old_len: usize, new_len: usize) -> Self {
+        Self::new(
+            ErrorKind::InvalidTreeSizes,
+            format!(
+                "can not prove consistency from {} leaves to {} leaves",
+                old_len, new_len
+            ),
+        )
+    }
+
+    pub fn hasher_mismatch(expected: &str, found: &str) -> Self {
+        Self::new(
+            ErrorKind::HasherMismatch,
+            format!(
+                "tree file was written with the {} hasher, but is opened with {}",
+                found, expected
+            ),
+        )
+    }
+
+    pub fn unsupported_file_format(reason: &str) -> Self {
+        Self::new(
+            ErrorKind::UnsupportedFileFormat,
+            format!("unsupported tree file: {}", reason),
+        )
+    }
+
     pub fn kind(&self) -> ErrorKind {
         self.kind
     }
@@ -93,6 +180,13 @@ impl Error {
 #[cfg(feature = "std")]
 impl std::error::Error for Error {}
 
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Self::new(ErrorKind::Io, error.to_string())
+    }
+}
+
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}", self.message)
@@ -1,6 +1,6 @@
 use crate::prelude::*;
 use core::convert::TryFrom;
-use core::mem;
+use core::{any, mem};
 
 /// Hasher is a trait used to provide a hashing algorithm for the library.
 ///
@@ -54,8 +54,12 @@ pub trait Hasher: Clone {
     /// if the left node doesn't have a sibling it is concatenated to itself and
     /// then hashed instead of just being propagated to the next level.
     ///
+    /// The default implementation hashes leaves and internal nodes the same way. To keep them
+    /// apart with distinct prefixes, wrap the hasher in [`Rfc6962`].
+    ///
     /// [`MerkleTree`]: crate::MerkleTree
     /// [`PartialTree`]: crate::PartialTree
+    /// [`Rfc6962`]: crate::algorithms::Rfc6962
     fn concat_and_hash(left: &Self::Hash, right: Option<&Self::Hash>) -> Self::Hash {
         let mut concatenated: Vec<u8> = (*left).into();
 
@@ -75,4 +79,12 @@ pub trait Hasher: Clone {
     fn hash_size() -> usize {
         mem::size_of::<Self::Hash>()
     }
+
+    /// Returns the name of the hash algorithm. It is written to the files of `MerkleTreeFile`,
+    /// so that they can't be opened with a different hasher.
+    /// The default implementation returns the name of the type, which isn't guaranteed
+    /// to stay the same between compiler versions, so it is better to override it.
+    fn identifier() -> String {
+        String::from(any::type_name::<Self>())
+    }
 }
@@ -7,6 +7,10 @@
 //! The library has two main structs. The first one is [`MerkleTree`],
 //! which builds the tree that can be used to verify data integrity and produce a Merkle proof. The
 //! second is [`MerkleProof`], which can be used to verify the inclusion of an item in a set.
+//! [`SparseMerkleTree`] and [`SparseMerkleProof`] are keyed by a 256-bit path instead, and can
+//! also prove that a key is absent. [`MerkleMountainRange`] is an append-only accumulator with
+//! inclusion and consistency proofs, verified with [`MerkleMountainRangeProof`]. With the `disk`
+//! feature, `MerkleTreeFile` streams a tree to a file and makes proofs from it without loading it.
 //!
 //! The library is highly customizable. Hashing algorithm and the way how the tree is built
 //! can be configured through the [`Hasher`] trait.
@@ -136,17 +140,31 @@ extern crate std;
 pub use error::Error;
 pub use error::ErrorKind;
 pub use hasher::Hasher;
+pub use merkle_mountain_range::MerkleMountainRange;
+pub use merkle_mountain_range_proof::MerkleMountainRangeProof;
 pub use merkle_proof::MerkleProof;
 pub use merkle_tree::MerkleTree;
+#[cfg(feature = "disk")]
+pub use merkle_tree_file::{MerkleTreeFile, TREE_FILE_VERSION};
 pub use partial_tree::PartialTree;
 pub use proof_serializers::MerkleProofSerializer;
+pub use sparse_merkle_proof::SparseMerkleProof;
+pub use sparse_merkle_tree::{SparseKey, SparseMerkleTree, SPARSE_TREE_DEPTH};
 
+#[cfg(feature = "concordium")]
+mod concordium;
 mod error;
 mod hasher;
+mod merkle_mountain_range;
+mod merkle_mountain_range_proof;
 mod merkle_proof;
 mod merkle_tree;
+#[cfg(feature = "disk")]
+mod merkle_tree_file;
 mod partial_tree;
 mod prelude;
+mod sparse_merkle_proof;
+mod sparse_merkle_tree;
 #[doc(hidden)]
 pub mod utils;
 
@@ -0,0 +1,238 @@
+use crate::prelude::*;
+use crate::{utils, Error, Hasher, MerkleMountainRangeProof};
+
+/// [`MerkleMountainRange`] is an append-only accumulator. It commits to a growing log of items,
+/// like bids or votes, and can prove that an item is in the log, and that the log at a later size
+/// extends the log at an earlier size.
+///
+/// The leaves are split into perfect binary trees, one for each bit set in the number of leaves,
+/// from the biggest to the smallest. The roots of those trees are the peaks, and the root of the
+/// range bags the peaks from right to left: `H(peak_0, H(peak_1, ... H(peak_n-1, peak_n)))`. This
+/// gives the same root as a [`MerkleTree`] with the same leaves, but pushing a leaf only hashes
+/// the nodes that become complete, and nodes never change once they exist.
+///
+/// ## Examples
+///
+/// ```
+/// # use rs_merkle::{MerkleMountainRange, MerkleMountainRangeProof, algorithms::Sha256, Hasher};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut mmr = MerkleMountainRange::<Sha256>::new();
+/// let leaves: Vec<[u8; 32]> = ["a", "b", "c", "d", "e"]
+///     .iter()
+///     .map(|x| Sha256::hash(x.as_bytes()))
+///     .collect();
+///
+/// mmr.push(leaves[0]).push(leaves[1]).push(leaves[2]);
+/// let old_root = mmr.root().ok_or("couldn't get the root")?;
+/// mmr.push(leaves[3]).push(leaves[4]);
+/// let new_root = mmr.root().ok_or("couldn't get the root")?;
+///
+/// let proof_bytes = mmr.proof(1)?.to_bytes();
+/// let proof = MerkleMountainRangeProof::<Sha256>::from_bytes(&proof_bytes)?;
+/// assert!(proof.verify(new_root, 1, leaves[1], 5));
+///
+/// let consistency_proof = mmr.consistency_proof(3)?;
+/// assert!(consistency_proof.verify_consistency(old_root, new_root, 3, 5));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`MerkleTree`]: crate::MerkleTree
+#[derive(Clone)]
+pub struct MerkleMountainRange<T: Hasher> {
+    // Complete nodes by height: `layers[h][i]` is the root of the leaves `i * 2^h..(i + 1) * 2^h`
+    layers: Vec<Vec<T::Hash>>,
+}
+
+impl<T: Hasher> Default for MerkleMountainRange<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Hasher> MerkleMountainRange<T> {
+    /// Creates a new, empty Merkle mountain range.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use rs_merkle::{MerkleMountainRange, algorithms::Sha256};
+    ///
+    /// let mmr = MerkleMountainRange::<Sha256>::new();
+    /// assert_eq!(mmr.root(), None);
+    /// ```
+    pub fn new() -> Self {
+        Self { layers: Vec::new() }
+    }
+
+    /// Pushes all leaves in order into a new Merkle mountain range
+    pub fn from_leaves(leaves: &[T::Hash]) -> Self {
+        let mut mmr = Self::new();
+
+        for leaf in leaves {
+            mmr.push(*leaf);
+        }
+
+        mmr
+    }
+
+    /// Appends a leaf, hashing the nodes that it completes.
+    pub fn push(&mut self, leaf: T::Hash) -> &mut Self {
+        let mut node = leaf;
+        let mut height = 0;
+
+        loop {
+            if self.layers.len() == height {
+                self.layers.push(Vec::new());
+            }
+            let layer = &mut self.layers[height];
+            layer.push(node);
+
+            // Only a node on the right completes its parent
+            if utils::indices::is_left_index(layer.len() - 1) {
+                break;
+            }
+            node = T::concat_and_hash(&layer[layer.len() - 2], Some(&node));
+            height += 1;
+        }
+
+        self
+    }
+
+    /// Returns the number of leaves.
+    pub fn leaves_len(&self) -> usize {
+        self.layers.first().map_or(0, |leaves| leaves.len())
+    }
+
+    /// Returns the leaves.
+    pub fn leaves(&self) -> Option<Vec<T::Hash>> {
+        Some(self.layers.first()?.to_vec())
+    }
+
+    /// Returns the peaks, from left to right.
+    pub fn peaks(&self) -> Vec<T::Hash> {
+        self.peaks_at(self.leaves_len())
+    }
+
+    /// Returns the root, which is the bag of the peaks, or `None` if there are no leaves.
+    pub fn root(&self) -> Option<T::Hash> {
+        self.root_at(self.leaves_len())
+    }
+
+    /// Similar to [`MerkleMountainRange::root`], but returns a hex encoded string instead of
+    /// [`Hasher::Hash`].
+    pub fn root_hex(&self) -> Option<String> {
+        let root = self.root()?;
+        Some(utils::collections::to_hex_string(&root))
+    }
+
+    /// Returns the root the range had when it had `leaves_len` leaves, or `None` if there were no
+    /// leaves or there are fewer leaves than that.
+    pub fn root_at(&self, leaves_len: usize) -> Option<T::Hash> {
+        if leaves_len > self.leaves_len() {
+            return None;
+        }
+
+        bag_peaks::<T>(&self.peaks_at(leaves_len))
+    }
+
+    /// Returns the proof that the leaf at `leaf_index` is in the range. The proof has the
+    /// siblings from the leaf up to its peak, followed by the bag of the peaks on the right, if
+    /// there are any, and then the peaks on the left, from the nearest to the farthest.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if there is no leaf at `leaf_index`.
+    pub fn proof(&self, leaf_index: usize) -> Result<MerkleMountainRangeProof<T>, Error> {
+        let leaves_len = self.leaves_len();
+        if leaf_index >= leaves_len {
+            return Err(Error::leaf_index_out_of_bounds(leaf_index, leaves_len));
+        }
+
+        let peaks = utils::indices::mountain_peaks(leaves_len);
+        let peak_index = peak_index(&peaks, leaf_index);
+        let (_, peak_height) = peaks[peak_index];
+        let peak_hashes = self.peaks_at(leaves_len);
+        let mut proof_hashes = Vec::new();
+
+        for height in 0..peak_height {
+            let sibling_index = utils::indices::get_sibling_index(leaf_index >> height);
+            proof_hashes.push(self.layers[height][sibling_index]);
+        }
+        if let Some(bag) = bag_peaks::<T>(&peak_hashes[peak_index + 1..]) {
+            proof_hashes.push(bag);
+        }
+        proof_hashes.extend(peak_hashes[..peak_index].iter().rev());
+
+        Ok(MerkleMountainRangeProof::new(proof_hashes))
+    }
+
+    /// Returns the proof that the range with all the current leaves extends the range it was when
+    /// it had `old_len` leaves. The proof has the old peaks, followed by the nodes that are needed
+    /// to build the new peaks from the old ones, from left to right.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if `old_len` is 0 or greater than the number of leaves.
+    pub fn consistency_proof(&self, old_len: usize) -> Result<MerkleMountainRangeProof<T>, Error> {
+        let new_len = self.leaves_len();
+        if old_len == 0 || old_len > new_len {
+            return Err(Error::invalid_tree_sizes(old_len, new_len));
+        }
+
+        let mut proof_hashes = self.peaks_at(old_len);
+        for (start, height) in consistency_nodes(old_len, new_len) {
+            proof_hashes.push(self.layers[height][start >> height]);
+        }
+
+        Ok(MerkleMountainRangeProof::new(proof_hashes))
+    }
+
+    fn peaks_at(&self, leaves_len: usize) -> Vec<T::Hash> {
+        utils::indices::mountain_peaks(leaves_len)
+            .iter()
+            .map(|(start, height)| self.layers[*height][start >> height])
+            .collect()
+    }
+}
+
+/// Bags the peaks from right to left, or returns `None` if there are no peaks
+pub(crate) fn bag_peaks<T: Hasher>(peaks: &[T::Hash]) -> Option<T::Hash> {
+    let (last, rest) = peaks.split_last()?;
+
+    Some(
+        rest.iter()
+            .rev()
+            .fold(*last, |bag, peak| T::concat_and_hash(peak, Some(&bag))),
+    )
+}
+
+/// Returns the index of the peak over the leaf at `leaf_index`
+pub(crate) fn peak_index(peaks: &[(usize, usize)], leaf_index: usize) -> usize {
+    peaks.partition_point(|(start, _)| *start <= leaf_index) - 1
+}
+
+/// Returns the nodes, as `(index of the first leaf, height)`, that are needed besides the peaks of
+/// a range with `old_len` leaves to build the peaks of the range with `new_len` leaves. They are
+/// ordered from left to right, the way they are used when walking down each new peak.
+pub(crate) fn consistency_nodes(old_len: usize, new_len: usize) -> Vec<(usize, usize)> {
+    fn walk(start: usize, height: usize, old_len: usize, nodes: &mut Vec<(usize, usize)>) {
+        if start + (1 << height) <= old_len {
+            // An old peak
+            return;
+        }
+        if start >= old_len {
+            nodes.push((start, height));
+            return;
+        }
+        walk(start, height - 1, old_len, nodes);
+        walk(start + (1 << (height - 1)), height - 1, old_len, nodes);
+    }
+
+    let mut nodes = Vec::new();
+    for (start, height) in utils::indices::mountain_peaks(new_len) {
+        walk(start, height, old_len, &mut nodes);
+    }
+
+    nodes
+}
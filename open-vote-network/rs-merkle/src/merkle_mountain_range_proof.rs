@@ -0,0 +1,237 @@
+use crate::prelude::*;
+use crate::{
+    error::Error,
+    merkle_mountain_range::{bag_peaks, peak_index},
+    proof_serializers::{DirectHashesOrder, MerkleProofSerializer},
+    utils, Hasher, MerkleProof,
+};
+
+/// [`MerkleMountainRangeProof`] is used to verify inclusion and consistency proofs made by a
+/// [`MerkleMountainRange`].
+///
+/// The proof is a list of hashes, just like a [`MerkleProof`], so it is serialized with the same
+/// [`MerkleProofSerializer`]s, and the verifier only needs the sizes of the ranges besides the
+/// hashes.
+///
+/// ## Examples
+///
+/// ```
+/// # use rs_merkle::{
+/// #     MerkleMountainRange, MerkleMountainRangeProof, algorithms::Sha256, Hasher,
+/// #     proof_serializers::ReverseHashesOrder,
+/// # };
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let leaves: Vec<[u8; 32]> = ["a", "b", "c"]
+///     .iter()
+///     .map(|x| Sha256::hash(x.as_bytes()))
+///     .collect();
+/// let mmr = MerkleMountainRange::<Sha256>::from_leaves(&leaves);
+/// let root = mmr.root().ok_or("couldn't get the root")?;
+///
+/// let proof_bytes = mmr.proof(2)?.serialize::<ReverseHashesOrder>();
+/// let proof = MerkleMountainRangeProof::<Sha256>::deserialize::<ReverseHashesOrder>(&proof_bytes)?;
+///
+/// assert!(proof.verify(root, 2, leaves[2], leaves.len()));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`MerkleMountainRange`]: crate::MerkleMountainRange
+pub struct MerkleMountainRangeProof<T: Hasher> {
+    proof: MerkleProof<T>,
+}
+
+impl<T: Hasher> MerkleMountainRangeProof<T> {
+    pub fn new(proof_hashes: Vec<T::Hash>) -> Self {
+        MerkleMountainRangeProof {
+            proof: MerkleProof::new(proof_hashes),
+        }
+    }
+
+    /// Creates a proof from a slice of bytes, direct hashes order.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Self::deserialize::<DirectHashesOrder>(bytes)
+    }
+
+    /// Creates a proof from a slice of bytes serialized with the given serializer.
+    pub fn deserialize<S: MerkleProofSerializer>(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(MerkleMountainRangeProof {
+            proof: S::deserialize(bytes)?,
+        })
+    }
+
+    /// Serializes the proof hashes to a flat vector of bytes, direct hashes order.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.serialize::<DirectHashesOrder>()
+    }
+
+    /// Serializes the proof hashes to a flat vector of bytes using the given serializer.
+    pub fn serialize<S: MerkleProofSerializer>(&self) -> Vec<u8> {
+        S::serialize(&self.proof)
+    }
+
+    /// Returns all hashes from the proof.
+    pub fn proof_hashes(&self) -> &[T::Hash] {
+        self.proof.proof_hashes()
+    }
+
+    /// Verifies that `leaf_hash` is the leaf at `leaf_index` of the range with `leaves_len`
+    /// leaves and the given root.
+    pub fn verify(
+        &self,
+        root: T::Hash,
+        leaf_index: usize,
+        leaf_hash: T::Hash,
+        leaves_len: usize,
+    ) -> bool {
+        match self.root(leaf_index, leaf_hash, leaves_len) {
+            Ok(extracted_root) => extracted_root == root,
+            Err(_) => false,
+        }
+    }
+
+    /// Calculates the root of the range from an inclusion proof. Used inside the
+    /// [`MerkleMountainRangeProof::verify`] method, but can be used on its own.
+    pub fn root(
+        &self,
+        leaf_index: usize,
+        leaf_hash: T::Hash,
+        leaves_len: usize,
+    ) -> Result<T::Hash, Error> {
+        if leaf_index >= leaves_len {
+            return Err(Error::leaf_index_out_of_bounds(leaf_index, leaves_len));
+        }
+
+        let peaks = utils::indices::mountain_peaks(leaves_len);
+        let peak_index = peak_index(&peaks, leaf_index);
+        let (_, peak_height) = peaks[peak_index];
+        let mut proof_hashes = self.proof_hashes().iter();
+        let mut next_hash = || {
+            proof_hashes
+                .next()
+                .ok_or_else(Error::not_enough_hashes_to_calculate_root)
+        };
+
+        let mut node = leaf_hash;
+        for height in 0..peak_height {
+            let sibling = next_hash()?;
+            node = match utils::indices::is_left_index(leaf_index >> height) {
+                true => T::concat_and_hash(&node, Some(sibling)),
+                false => T::concat_and_hash(sibling, Some(&node)),
+            };
+        }
+        if peak_index + 1 < peaks.len() {
+            node = T::concat_and_hash(&node, Some(next_hash()?));
+        }
+        for _ in 0..peak_index {
+            node = T::concat_and_hash(next_hash()?, Some(&node));
+        }
+
+        self.check_all_hashes_used(proof_hashes.len())?;
+        Ok(node)
+    }
+
+    /// Verifies that the range with `new_len` leaves and root `new_root` extends the range with
+    /// `old_len` leaves and root `old_root`.
+    pub fn verify_consistency(
+        &self,
+        old_root: T::Hash,
+        new_root: T::Hash,
+        old_len: usize,
+        new_len: usize,
+    ) -> bool {
+        match self.consistency_roots(old_len, new_len) {
+            Ok((extracted_old_root, extracted_new_root)) => {
+                extracted_old_root == old_root && extracted_new_root == new_root
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Calculates the old and the new root of the ranges from a consistency proof. Used inside
+    /// the [`MerkleMountainRangeProof::verify_consistency`] method, but can be used on its own.
+    pub fn consistency_roots(
+        &self,
+        old_len: usize,
+        new_len: usize,
+    ) -> Result<(T::Hash, T::Hash), Error> {
+        if old_len == 0 || old_len > new_len {
+            return Err(Error::invalid_tree_sizes(old_len, new_len));
+        }
+
+        let old_peaks = utils::indices::mountain_peaks(old_len);
+        if self.proof_hashes().len() < old_peaks.len() {
+            return Err(Error::not_enough_hashes_to_calculate_root());
+        }
+        let (old_peak_hashes, new_hashes) = self.proof_hashes().split_at(old_peaks.len());
+        let mut new_hashes = new_hashes.iter();
+
+        let mut new_peak_hashes = Vec::new();
+        for (start, height) in utils::indices::mountain_peaks(new_len) {
+            new_peak_hashes.push(build_node::<T>(
+                start,
+                height,
+                &old_peaks,
+                old_peak_hashes,
+                &mut new_hashes,
+            )?);
+        }
+        self.check_all_hashes_used(new_hashes.len())?;
+
+        match (
+            bag_peaks::<T>(old_peak_hashes),
+            bag_peaks::<T>(&new_peak_hashes),
+        ) {
+            (Some(old_root), Some(new_root)) => Ok((old_root, new_root)),
+            _ => Err(Error::not_enough_hashes_to_calculate_root()),
+        }
+    }
+
+    fn check_all_hashes_used(&self, unused_count: usize) -> Result<(), Error> {
+        match unused_count {
+            0 => Ok(()),
+            _ => Err(Error::unused_proof_hashes(unused_count)),
+        }
+    }
+}
+
+/// Builds the node over the leaves `start..start + 2^height` of the new range from the old peaks
+/// and the new nodes of a consistency proof, which come in the order of `consistency_nodes`
+fn build_node<'a, T: Hasher>(
+    start: usize,
+    height: usize,
+    old_peaks: &[(usize, usize)],
+    old_peak_hashes: &[T::Hash],
+    new_hashes: &mut impl Iterator<Item = &'a T::Hash>,
+) -> Result<T::Hash, Error>
+where
+    T::Hash: 'a,
+{
+    let old_len = old_peaks
+        .last()
+        .map_or(0, |(start, height)| start + (1 << height));
+
+    if start + (1 << height) <= old_len {
+        let index = old_peaks
+            .iter()
+            .position(|peak| *peak == (start, height))
+            .ok_or_else(Error::not_enough_hashes_to_calculate_root)?;
+        return Ok(old_peak_hashes[index]);
+    }
+    if start >= old_len {
+        return new_hashes
+            .next()
+            .copied()
+            .ok_or_else(Error::not_enough_hashes_to_calculate_root);
+    }
+
+    let left = build_node::<T>(start, height - 1, old_peaks, old_peak_hashes, new_hashes)?;
+    let right = build_node::<T>(
+        start + (1 << (height - 1)),
+        height - 1,
+        old_peaks,
+        old_peak_hashes,
+        new_hashes,
+    )?;
+    Ok(T::concat_and_hash(&left, Some(&right)))
+}
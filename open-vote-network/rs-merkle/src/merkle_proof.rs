@@ -249,6 +249,93 @@ impl<T: Hasher> MerkleProof<T> {
         }
     }
 
+    /// Uses a proof made by [`MerkleTree::consistency_proof`] to verify that the tree with
+    /// `new_len` leaves and root `new_root` extends the tree with `old_len` leaves and root
+    /// `old_root`, following the verification algorithm of
+    /// [RFC 9162](https://www.rfc-editor.org/rfc/rfc9162#section-2.1.4.2).
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use rs_merkle::{MerkleTree, MerkleProof, algorithms::Sha256, Hasher, Error, utils};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let leaves: Vec<[u8; 32]> = ["a", "b", "c", "d", "e"]
+    ///     .iter()
+    ///     .map(|x| Sha256::hash(x.as_bytes()))
+    ///     .collect();
+    ///
+    /// let old_tree = MerkleTree::<Sha256>::from_leaves(&leaves[..2]);
+    /// let new_tree = MerkleTree::<Sha256>::from_leaves(&leaves);
+    /// let old_root = old_tree.root().ok_or("couldn't get the merkle root")?;
+    /// let new_root = new_tree.root().ok_or("couldn't get the merkle root")?;
+    ///
+    /// let proof_bytes = new_tree.consistency_proof(2, 5)?.to_bytes();
+    /// let proof = MerkleProof::<Sha256>::from_bytes(&proof_bytes)?;
+    ///
+    /// assert!(proof.verify_consistency(old_root, new_root, 2, 5));
+    /// assert!(!proof.verify_consistency(new_root, old_root, 2, 5));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`MerkleTree::consistency_proof`]: crate::MerkleTree::consistency_proof
+    pub fn verify_consistency(
+        &self,
+        old_root: T::Hash,
+        new_root: T::Hash,
+        old_len: usize,
+        new_len: usize,
+    ) -> bool {
+        if old_len == 0 || old_len > new_len {
+            return false;
+        }
+        if old_len == new_len {
+            return self.proof_hashes.is_empty() && old_root == new_root;
+        }
+
+        // If the old tree is a perfect tree, its root is a node of the new tree that is left out
+        // of the proof
+        let mut path = Vec::with_capacity(self.proof_hashes.len() + 1);
+        if old_len.is_power_of_two() {
+            path.push(old_root);
+        }
+        path.extend(self.proof_hashes.iter().cloned());
+        let (first_hash, path) = match path.split_first() {
+            Some(split) => split,
+            None => return false,
+        };
+
+        let mut old_index = old_len - 1;
+        let mut new_index = new_len - 1;
+        while old_index & 1 == 1 {
+            old_index >>= 1;
+            new_index >>= 1;
+        }
+
+        let mut old_hash = *first_hash;
+        let mut new_hash = *first_hash;
+        for hash in path {
+            if new_index == 0 {
+                return false;
+            }
+
+            if old_index & 1 == 1 || old_index == new_index {
+                old_hash = T::concat_and_hash(hash, Some(&old_hash));
+                new_hash = T::concat_and_hash(hash, Some(&new_hash));
+                while old_index & 1 == 0 && old_index != 0 {
+                    old_index >>= 1;
+                    new_index >>= 1;
+                }
+            } else {
+                new_hash = T::concat_and_hash(&new_hash, Some(hash));
+            }
+            old_index >>= 1;
+            new_index >>= 1;
+        }
+
+        new_index == 0 && old_hash == old_root && new_hash == new_root
+    }
+
     /// Calculates the root and serializes it into a hex string.
     ///
     /// ## Examples
@@ -1,5 +1,8 @@
 use crate::prelude::*;
-use crate::{partial_tree::PartialTree, utils, utils::indices, Hasher, MerkleProof};
+use crate::{partial_tree::PartialTree, utils, utils::indices, Error, Hasher, MerkleProof};
+use alloc::collections::BTreeMap;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 /// [`MerkleTree`] is a Merkle Tree that is well suited for both basic and advanced usage.
 ///
@@ -12,8 +15,64 @@ use crate::{partial_tree::PartialTree, utils, utils::indices, Hasher, MerkleProo
 #[derive(Clone)]
 pub struct MerkleTree<T: Hasher> {
     current_working_tree: PartialTree<T>,
-    history: Vec<PartialTree<T>>,
+    history: Vec<Commit<T>>,
     uncommitted_leaves: Vec<T::Hash>,
+    uncommitted_updates: BTreeMap<usize, T::Hash>,
+    uncommitted_removals: usize,
+}
+
+#[cfg(feature = "parallel")]
+impl<T: Hasher> MerkleTree<T>
+where
+    T::Hash: Send + Sync,
+{
+    /// Same as [`MerkleTree::from_leaves`], but hashes each layer of the tree on all threads of
+    /// the `rayon` thread pool. The resulting tree, including its history, is the same.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use rs_merkle::{MerkleTree, algorithms::Sha256, Hasher};
+    /// let leaves: Vec<[u8; 32]> = ["a", "b", "c"]
+    ///     .iter()
+    ///     .map(|x| Sha256::hash(x.as_bytes()))
+    ///     .collect();
+    ///
+    /// let merkle_tree = MerkleTree::<Sha256>::par_from_leaves(&leaves);
+    /// assert_eq!(merkle_tree.root(), MerkleTree::<Sha256>::from_leaves(&leaves).root());
+    /// ```
+    pub fn par_from_leaves(leaves: &[T::Hash]) -> Self {
+        let mut tree = Self::new();
+
+        if !leaves.is_empty() {
+            tree.commit_diff(PartialTree::par_from_leaves(leaves), leaves.len());
+        }
+
+        tree
+    }
+
+    /// Same as [`MerkleTree::proofs`], but splits the leaf indices between all threads of the
+    /// `rayon` thread pool.
+    pub fn par_proofs(&self, leaf_indices: &[usize]) -> Vec<MerkleProof<T>> {
+        leaf_indices
+            .par_chunks(PAR_PROOFS_CHUNK_SIZE)
+            .flat_map_iter(|chunk| self.single_leaf_proof_hashes(chunk))
+            .map(MerkleProof::new)
+            .collect()
+    }
+}
+
+/// Number of leaf indices each task of [`MerkleTree::par_proofs`] collects proofs for
+#[cfg(feature = "parallel")]
+const PAR_PROOFS_CHUNK_SIZE: usize = 1024;
+
+/// A commit saved to the history of the tree. It keeps the diff that reverts the commit, such
+/// that rolling back costs about the size of the diff rather than the size of the history
+#[derive(Clone)]
+struct Commit<T: Hasher> {
+    reverse_diff: PartialTree<T>,
+    leaves_len: usize,
+    root: Option<T::Hash>,
 }
 
 impl<T: Hasher> Default for MerkleTree<T> {
@@ -39,6 +98,8 @@ impl<T: Hasher> MerkleTree<T> {
             current_working_tree: PartialTree::new(),
             history: Vec::new(),
             uncommitted_leaves: Vec::new(),
+            uncommitted_updates: BTreeMap::new(),
+            uncommitted_removals: 0,
         }
     }
 
@@ -199,6 +260,127 @@ impl<T: Hasher> MerkleTree<T> {
         MerkleProof::<T>::new(self.helper_nodes(leaf_indices))
     }
 
+    /// Returns a separate proof for each of the given leaf indices, in the same order. It gives
+    /// the same proofs as calling [`MerkleTree::proof`] with each index on its own, but collects
+    /// them in one pass over the tree.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use rs_merkle::{MerkleTree, algorithms::Sha256, Hasher};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let leaves: Vec<[u8; 32]> = ["a", "b", "c", "d", "e", "f"]
+    ///     .iter()
+    ///     .map(|x| Sha256::hash(x.as_bytes()))
+    ///     .collect();
+    ///
+    /// let merkle_tree = MerkleTree::<Sha256>::from_leaves(&leaves);
+    /// let merkle_root = merkle_tree.root().ok_or("couldn't get the merkle root")?;
+    /// let proofs = merkle_tree.proofs(&[0, 4]);
+    ///
+    /// assert!(proofs[0].verify(merkle_root, &[0], &leaves[0..1], leaves.len()));
+    /// assert!(proofs[1].verify(merkle_root, &[4], &leaves[4..5], leaves.len()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn proofs(&self, leaf_indices: &[usize]) -> Vec<MerkleProof<T>> {
+        self.single_leaf_proof_hashes(leaf_indices)
+            .into_iter()
+            .map(MerkleProof::new)
+            .collect()
+    }
+
+    /// Returns the proof that the tree of the first `new_len` leaves extends the tree of the
+    /// first `old_len` leaves, like the consistency proofs of
+    /// [RFC 6962](https://www.rfc-editor.org/rfc/rfc6962#section-2.1.2). It can be checked with
+    /// [`MerkleProof::verify_consistency`], for example to show that an update of the tree only
+    /// appended leaves.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use rs_merkle::{MerkleTree, MerkleProof, algorithms::Sha256, Hasher, Error, utils};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let leaves: Vec<[u8; 32]> = ["a", "b", "c", "d", "e", "f"]
+    ///     .iter()
+    ///     .map(|x| Sha256::hash(x.as_bytes()))
+    ///     .collect();
+    ///
+    /// let mut merkle_tree = MerkleTree::<Sha256>::from_leaves(&leaves[..3]);
+    /// let old_root = merkle_tree.root().ok_or("couldn't get the merkle root")?;
+    ///
+    /// merkle_tree.append(leaves[3..].to_vec().as_mut()).commit();
+    /// let new_root = merkle_tree.root().ok_or("couldn't get the merkle root")?;
+    ///
+    /// let proof = merkle_tree.consistency_proof(3, 6)?;
+    /// assert!(proof.verify_consistency(old_root, new_root, 3, 6));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// Fails if `old_len` is 0, or if `old_len <= new_len <= leaves_len` doesn't hold.
+    pub fn consistency_proof(
+        &self,
+        old_len: usize,
+        new_len: usize,
+    ) -> Result<MerkleProof<T>, Error> {
+        if old_len == 0 || old_len > new_len || new_len > self.leaves_len() {
+            return Err(Error::invalid_tree_sizes(old_len, new_len));
+        }
+
+        let mut proof_hashes = Vec::new();
+        self.consistency_subproof(old_len, 0, new_len, true, &mut proof_hashes);
+
+        Ok(MerkleProof::new(proof_hashes))
+    }
+
+    /// SUBPROOF from RFC 6962 for the subtree over the leaves `start..end`, where the old tree is
+    /// made of its first `old_len` leaves. `is_old_tree` tells if the subtree root is the old root,
+    /// which the verifier already knows.
+    fn consistency_subproof(
+        &self,
+        old_len: usize,
+        start: usize,
+        end: usize,
+        is_old_tree: bool,
+        proof_hashes: &mut Vec<T::Hash>,
+    ) {
+        if old_len == end - start {
+            if !is_old_tree {
+                proof_hashes.push(self.subtree_root(start, end));
+            }
+            return;
+        }
+
+        let split = start + indices::subtree_split(end - start);
+        if start + old_len <= split {
+            self.consistency_subproof(old_len, start, split, is_old_tree, proof_hashes);
+            proof_hashes.push(self.subtree_root(split, end));
+        } else {
+            self.consistency_subproof(old_len - (split - start), split, end, false, proof_hashes);
+            proof_hashes.push(self.subtree_root(start, split));
+        }
+    }
+
+    /// Returns the root of the tree over the leaves `start..end`. `start` must be a multiple of the
+    /// biggest power of two that is not greater than `end - start`, like in the subtrees of RFC
+    /// 6962, so that the perfect part of the subtree is a node of this tree
+    fn subtree_root(&self, start: usize, end: usize) -> T::Hash {
+        let width = end - start;
+        if width.is_power_of_two() {
+            let height = width.trailing_zeros() as usize;
+            return self.layer_tuples()[height][start >> height].1;
+        }
+
+        let split = start + indices::subtree_split(width);
+        T::concat_and_hash(
+            &self.subtree_root(start, split),
+            Some(&self.subtree_root(split, end)),
+        )
+    }
+
     /// Inserts a new leaf. Please note it won't modify the root just yet; For the changes
     /// to be applied to the root, [`MerkleTree::commit`] method should be called first. To get the
     /// root of the new tree without applying the changes, you can use
@@ -280,8 +462,108 @@ impl<T: Hasher> MerkleTree<T> {
         self
     }
 
-    /// Commits the changes made by [`MerkleTree::insert`] and [`MerkleTree::append`]
-    /// and modifies the root.
+    /// Replaces the leaf at the given index. Behaves similarly to [`MerkleTree::insert`], so the
+    /// change is applied to the root on [`MerkleTree::commit`], where only the path from the leaf
+    /// to the root is recomputed. The index refers to the tree with the uncommitted changes applied.
+    ///
+    /// Returns an error if there is no leaf at the index.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use rs_merkle::{MerkleTree, MerkleProof, algorithms::Sha256, Hasher, Error, utils};
+    /// # use std::convert::TryFrom;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let leaves = [
+    ///     Sha256::hash("a".as_bytes()),
+    ///     Sha256::hash("b".as_bytes()),
+    ///     Sha256::hash("c".as_bytes()),
+    /// ];
+    /// let mut merkle_tree = MerkleTree::<Sha256>::from_leaves(&leaves);
+    ///
+    /// merkle_tree.update(1, Sha256::hash("d".as_bytes()))?.commit();
+    ///
+    /// let expected_tree = MerkleTree::<Sha256>::from_leaves(&[
+    ///     Sha256::hash("a".as_bytes()),
+    ///     Sha256::hash("d".as_bytes()),
+    ///     Sha256::hash("c".as_bytes()),
+    /// ]);
+    /// assert_eq!(merkle_tree.root(), expected_tree.root());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn update(&mut self, index: usize, leaf: T::Hash) -> Result<&mut Self, Error> {
+        let leaves_len = self.uncommitted_leaves_len();
+        if index >= leaves_len {
+            return Err(Error::leaf_index_out_of_bounds(index, leaves_len));
+        }
+
+        let committed_leaves_count = self.leaves_len() - self.uncommitted_removals;
+        if index >= committed_leaves_count {
+            self.uncommitted_leaves[index - committed_leaves_count] = leaf;
+        } else {
+            self.uncommitted_updates.insert(index, leaf);
+        }
+
+        Ok(self)
+    }
+
+    /// Removes the leaf at the given index by moving the last leaf into its place, similarly to
+    /// [`std::vec::Vec::swap_remove`], so only the paths of those two leaves are recomputed on
+    /// [`MerkleTree::commit`]. The index refers to the tree with the uncommitted changes applied.
+    ///
+    /// Returns an error if there is no leaf at the index.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use rs_merkle::{MerkleTree, MerkleProof, algorithms::Sha256, Hasher, Error, utils};
+    /// # use std::convert::TryFrom;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let leaves = [
+    ///     Sha256::hash("a".as_bytes()),
+    ///     Sha256::hash("b".as_bytes()),
+    ///     Sha256::hash("c".as_bytes()),
+    /// ];
+    /// let mut merkle_tree = MerkleTree::<Sha256>::from_leaves(&leaves);
+    ///
+    /// merkle_tree.remove(0)?.commit();
+    ///
+    /// let expected_tree = MerkleTree::<Sha256>::from_leaves(&[
+    ///     Sha256::hash("c".as_bytes()),
+    ///     Sha256::hash("b".as_bytes()),
+    /// ]);
+    /// assert_eq!(merkle_tree.root(), expected_tree.root());
+    /// assert_eq!(merkle_tree.leaves_len(), 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn remove(&mut self, index: usize) -> Result<&mut Self, Error> {
+        let leaves_len = self.uncommitted_leaves_len();
+        if index >= leaves_len {
+            return Err(Error::leaf_index_out_of_bounds(index, leaves_len));
+        }
+        let last_index = leaves_len - 1;
+        let last_leaf = self
+            .uncommitted_leaf(last_index)
+            .ok_or_else(|| Error::leaf_index_out_of_bounds(last_index, leaves_len))?;
+
+        // Drop the last leaf, which is either an uncommitted or a committed one
+        if self.uncommitted_leaves.pop().is_none() {
+            self.uncommitted_removals += 1;
+            self.uncommitted_updates.remove(&last_index);
+        }
+
+        // And move it into the place of the removed leaf
+        if index != last_index {
+            self.update(index, last_leaf)?;
+        }
+
+        Ok(self)
+    }
+
+    /// Commits the changes made by [`MerkleTree::insert`], [`MerkleTree::append`],
+    /// [`MerkleTree::update`] and [`MerkleTree::remove`] and modifies the root.
     /// Commits are saved to the history, so the tree can be rolled back to any previous commit
     /// using [`MerkleTree::rollback`]
     ///
@@ -312,9 +594,8 @@ impl<T: Hasher> MerkleTree<T> {
     /// ```
     pub fn commit(&mut self) {
         if let Some(diff) = self.uncommitted_diff() {
-            self.history.push(diff.clone());
-            self.current_working_tree.merge_unverified(diff);
-            self.uncommitted_leaves.clear();
+            let leaves_len = self.uncommitted_leaves_len();
+            self.commit_diff(diff, leaves_len);
         }
     }
 
@@ -351,17 +632,74 @@ impl<T: Hasher> MerkleTree<T> {
     /// ```
     pub fn rollback(&mut self) {
         // Remove the most recent commit
-        self.history.pop();
-        // Clear working tree
-        self.current_working_tree.clear();
-        // Applying all the commits up to the removed one. This is not an
-        // efficient way of doing things, but the diff subtraction is not implemented yet on
-        // PartialMerkleTree
-        for commit in &self.history {
-            self.current_working_tree.merge_unverified(commit.clone());
+        if let Some(commit) = self.history.pop() {
+            let previous_leaves_len = self.history.last().map_or(0, |commit| commit.leaves_len);
+
+            // Revert the commit, dropping the nodes it added to the tree
+            self.current_working_tree
+                .merge_unverified(commit.reverse_diff);
+            self.current_working_tree.truncate(previous_leaves_len);
+
+            // Uncommitted updates and removals refer to leaves of the reverted tree
+            self.uncommitted_updates.clear();
+            self.uncommitted_removals = 0;
+        }
+    }
+
+    /// Rolls back to the state right after the commit with the given index, i.e. the state
+    /// with the root at that index of [`MerkleTree::commit_roots`]. Removes all later commits
+    /// from the history. Does nothing if there is no commit with the given index.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use rs_merkle::{MerkleTree, MerkleProof, algorithms::Sha256, Hasher, Error, utils};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut merkle_tree = MerkleTree::<Sha256>::new();
+    ///
+    /// merkle_tree.insert(Sha256::hash("a".as_bytes())).commit();
+    /// merkle_tree.insert(Sha256::hash("b".as_bytes())).commit();
+    /// merkle_tree.insert(Sha256::hash("c".as_bytes())).commit();
+    ///
+    /// // Rollback to the state after the first commit
+    /// merkle_tree.rollback_to(0);
+    /// assert_eq!(
+    ///     merkle_tree.root_hex(),
+    ///     Some("ca978112ca1bbdcafac231b39a23dc4da786eff8147c4e72b9807785afee48bb".to_string())
+    /// );
+    /// assert_eq!(merkle_tree.commit_roots().len(), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn rollback_to(&mut self, commit_index: usize) {
+        while self.history.len() > commit_index + 1 {
+            self.rollback();
         }
     }
 
+    /// Returns the root after each commit in the history, starting with the first commit.
+    /// The root is `None` for commits that removed all leaves from the tree.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use rs_merkle::{MerkleTree, MerkleProof, algorithms::Sha256, Hasher, Error, utils};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut merkle_tree = MerkleTree::<Sha256>::new();
+    ///
+    /// merkle_tree.insert(Sha256::hash("a".as_bytes())).commit();
+    /// let first_root = merkle_tree.root();
+    /// merkle_tree.insert(Sha256::hash("b".as_bytes())).commit();
+    /// let second_root = merkle_tree.root();
+    ///
+    /// assert_eq!(merkle_tree.commit_roots(), vec![first_root, second_root]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn commit_roots(&self) -> Vec<Option<T::Hash>> {
+        self.history.iter().map(|commit| commit.root).collect()
+    }
+
     /// Calculates the root of the uncommitted changes as if they were committed.
     /// Will return the same hash as [`MerkleTree::root`] after [`MerkleTree::commit`]
     ///
@@ -409,8 +747,8 @@ impl<T: Hasher> MerkleTree<T> {
         Some(utils::collections::to_hex_string(&root))
     }
 
-    /// Clears all uncommitted changes made by [`MerkleTree::insert`] and [`MerkleTree::append`]
-    /// operations without applying them to the tree.
+    /// Clears all uncommitted changes made by [`MerkleTree::insert`], [`MerkleTree::append`],
+    /// [`MerkleTree::update`] and [`MerkleTree::remove`] operations without applying them to the tree.
     ///
     /// ## Examples
     ///
@@ -440,7 +778,9 @@ impl<T: Hasher> MerkleTree<T> {
     /// # }
     /// ```
     pub fn abort_uncommitted(&mut self) {
-        self.uncommitted_leaves.clear()
+        self.uncommitted_leaves.clear();
+        self.uncommitted_updates.clear();
+        self.uncommitted_removals = 0;
     }
 
     /// Returns the tree depth. A tree depth is how many layers there is between the
@@ -531,33 +871,111 @@ impl<T: Hasher> MerkleTree<T> {
         self.current_working_tree.layers()
     }
 
+    /// Returns the number of leaves the tree would have if the uncommitted changes were committed
+    fn uncommitted_leaves_len(&self) -> usize {
+        self.leaves_len() - self.uncommitted_removals + self.uncommitted_leaves.len()
+    }
+
+    /// Returns the leaf at the given index as if the uncommitted changes were committed
+    fn uncommitted_leaf(&self, index: usize) -> Option<T::Hash> {
+        let committed_leaves_count = self.leaves_len() - self.uncommitted_removals;
+        if index >= committed_leaves_count {
+            return self
+                .uncommitted_leaves
+                .get(index - committed_leaves_count)
+                .cloned();
+        }
+
+        match self.uncommitted_updates.get(&index) {
+            Some(leaf) => Some(*leaf),
+            None => Some(self.leaves_tuples()?.get(index)?.1),
+        }
+    }
+
+    /// Applies a diff to the working tree and saves it to the history as a new commit
+    fn commit_diff(&mut self, diff: PartialTree<T>, leaves_len: usize) {
+        self.history.push(Commit {
+            reverse_diff: self.current_working_tree.reverse_diff(&diff, leaves_len),
+            leaves_len,
+            root: diff.root().cloned(),
+        });
+        self.current_working_tree.merge_unverified(diff);
+        self.current_working_tree.truncate(leaves_len);
+        self.abort_uncommitted();
+    }
+
+    /// Returns the hashes of a single leaf proof for each of the given indices, walking the
+    /// layers of the tree once
+    fn single_leaf_proof_hashes(&self, leaf_indices: &[usize]) -> Vec<Vec<T::Hash>> {
+        let mut proof_hashes = vec![Vec::new(); leaf_indices.len()];
+
+        for (height, layer) in self.layer_tuples().iter().enumerate() {
+            for (hashes, leaf_index) in proof_hashes.iter_mut().zip(leaf_indices) {
+                let sibling_index = indices::get_sibling_index(leaf_index >> height);
+                if let Some((_, sibling)) = layer.get(sibling_index) {
+                    hashes.push(*sibling);
+                }
+            }
+        }
+
+        proof_hashes
+    }
+
     /// Creates a diff from a changes that weren't committed to the main tree yet. Can be used
     /// to get uncommitted root or can be merged with the main tree
     fn uncommitted_diff(&self) -> Option<PartialTree<T>> {
-        if self.uncommitted_leaves.is_empty() {
+        if self.uncommitted_leaves.is_empty()
+            && self.uncommitted_updates.is_empty()
+            && self.uncommitted_removals == 0
+        {
             return None;
         }
 
-        let committed_leaves_count = self.leaves_len();
+        // Figuring what tree height would be if we've committed the changes
+        let leaves_in_new_tree = self.uncommitted_leaves_len();
+        if leaves_in_new_tree == 0 {
+            return Some(PartialTree::new());
+        }
+        let uncommitted_tree_depth = utils::indices::tree_depth(leaves_in_new_tree);
+        let committed_leaves_count = self.leaves_len() - self.uncommitted_removals;
 
-        let shadow_indices: Vec<usize> = self
-            .uncommitted_leaves
-            .iter()
-            .enumerate()
-            .map(|(index, _)| committed_leaves_count + index)
-            .collect();
         // Tuples (index, hash) needed to construct a partial tree, since partial tree can't
-        // maintain indices otherwise
-        let mut shadow_node_tuples: Vec<(usize, T::Hash)> = shadow_indices
+        // maintain indices otherwise. These are the updated and the appended leaves
+        let mut shadow_node_tuples: Vec<(usize, T::Hash)> = self
+            .uncommitted_updates
             .iter()
-            .cloned()
-            .zip(self.uncommitted_leaves.iter().cloned())
+            .map(|(index, leaf)| (*index, *leaf))
+            .chain(
+                self.uncommitted_leaves
+                    .iter()
+                    .cloned()
+                    .enumerate()
+                    .map(|(index, leaf)| (committed_leaves_count + index, leaf)),
+            )
+            .collect();
+        // If leaves were removed, the path of the new last leaf changes as well, since its
+        // ancestors may have lost their right sibling
+        let last_index = leaves_in_new_tree - 1;
+        if self.uncommitted_removals > 0
+            && !shadow_node_tuples.iter().any(|(i, _)| *i == last_index)
+        {
+            shadow_node_tuples.push((last_index, self.uncommitted_leaf(last_index)?));
+            shadow_node_tuples.sort_by(|(a, _), (b, _)| a.cmp(b));
+        }
+        let shadow_indices: Vec<usize> = shadow_node_tuples.iter().map(|(i, _)| *i).collect();
+
+        // Helper nodes of the committed tree, leaving out the nodes of removed leaves
+        let mut partial_tree_tuples: Vec<Vec<(usize, T::Hash)>> = self
+            .helper_node_tuples(&shadow_indices)
+            .into_iter()
+            .zip(utils::indices::layer_widths(leaves_in_new_tree))
+            .map(|(layer, width)| {
+                layer
+                    .into_iter()
+                    .filter(|(index, _)| *index < width)
+                    .collect()
+            })
             .collect();
-        let mut partial_tree_tuples = self.helper_node_tuples(&shadow_indices);
-
-        // Figuring what tree height would be if we've committed the changes
-        let leaves_in_new_tree = self.leaves_len() + self.uncommitted_leaves.len();
-        let uncommitted_tree_depth = utils::indices::tree_depth(leaves_in_new_tree);
 
         match partial_tree_tuples.first_mut() {
             Some(first_layer) => {
@@ -0,0 +1,300 @@
+use crate::prelude::*;
+use crate::{utils, Error, Hasher, MerkleProof};
+use core::marker::PhantomData;
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Bytes a tree file starts with
+const MAGIC: &[u8; 4] = b"RSMT";
+
+/// Version of the tree file format written by [`MerkleTreeFile::create`]
+pub const TREE_FILE_VERSION: u16 = 1;
+
+/// [`MerkleTreeFile`] is a read-only Merkle tree stored in a file. It is meant for trees that are
+/// too big to keep every layer in memory: [`MerkleTreeFile::create`] hashes the leaves as they
+/// come from an iterator, and [`MerkleTreeFile::open`] maps the file into memory, so making a
+/// proof only reads the nodes it needs.
+///
+/// The tree has the same nodes, and so the same root and proofs, as a [`MerkleTree`] with the
+/// same leaves.
+///
+/// ## File format
+///
+/// All numbers are little endian:
+///
+/// - `"RSMT"`
+/// - format version, `u16`
+/// - length of the hasher identifier, `u16`, followed by the identifier, see
+///   [`Hasher::identifier`]
+/// - hash size, `u32`
+/// - number of leaves, `u64`
+/// - the layers, from the leaves to the root, each a flat list of hashes
+///
+/// ## Examples
+///
+/// ```
+/// # use rs_merkle::{MerkleTree, MerkleTreeFile, algorithms::Sha256, Hasher};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let path = std::env::temp_dir().join("rs_merkle_doc_example.tree");
+/// let leaves = ["a", "b", "c", "d", "e", "f"]
+///     .iter()
+///     .map(|x| Sha256::hash(x.as_bytes()));
+///
+/// MerkleTreeFile::<Sha256>::create(&path, leaves.clone())?;
+///
+/// let tree = MerkleTreeFile::<Sha256>::open(&path)?;
+/// let leaves: Vec<[u8; 32]> = leaves.collect();
+/// assert_eq!(tree.root(), MerkleTree::<Sha256>::from_leaves(&leaves).root());
+///
+/// let proof = tree.proof(&[3, 4])?;
+/// assert!(proof.verify(tree.root().ok_or("no root")?, &[3, 4], &leaves[3..5], leaves.len()));
+/// # std::fs::remove_file(&path)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`MerkleTree`]: crate::MerkleTree
+pub struct MerkleTreeFile<T: Hasher> {
+    mmap: Mmap,
+    // Offset of the first hash of each layer in the file, from the leaves to the root
+    layer_offsets: Vec<usize>,
+    layer_widths: Vec<usize>,
+    hasher: PhantomData<T>,
+}
+
+impl<T: Hasher> MerkleTreeFile<T> {
+    /// Writes the tree of the given leaves to a new file at `path`, replacing the file if it
+    /// exists, and opens it. Only the leaves are streamed to the file first; each layer above is
+    /// then hashed while reading the layer below back from the file, so memory use doesn't grow
+    /// with the number of leaves.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the file can't be written.
+    pub fn create<P, I>(path: P, leaves: I) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+        I: IntoIterator<Item = T::Hash>,
+    {
+        let path = path.as_ref();
+        let identifier = T::identifier();
+        let identifier_len = u16::try_from(identifier.len())
+            .map_err(|_| Error::unsupported_file_format("hasher identifier is too long"))?;
+        let hash_size = T::hash_size();
+        let header_len = header_len(identifier.len());
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&TREE_FILE_VERSION.to_le_bytes())?;
+        writer.write_all(&identifier_len.to_le_bytes())?;
+        writer.write_all(identifier.as_bytes())?;
+        writer.write_all(&(hash_size as u32).to_le_bytes())?;
+        // The number of leaves is only known once they are all written
+        writer.write_all(&0u64.to_le_bytes())?;
+
+        let mut leaves_len = 0;
+        for leaf in leaves {
+            let leaf: Vec<u8> = leaf.into();
+            writer.write_all(&leaf)?;
+            leaves_len += 1;
+        }
+        writer.seek(SeekFrom::Start((header_len - 8) as u64))?;
+        writer.write_all(&(leaves_len as u64).to_le_bytes())?;
+        writer.seek(SeekFrom::End(0))?;
+
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut layer_offset = header_len;
+        let mut left = vec![0u8; hash_size];
+        let mut right = vec![0u8; hash_size];
+
+        // Every layer but the root has a parent layer
+        let layer_widths = utils::indices::layer_widths(leaves_len);
+        for width in layer_widths
+            .iter()
+            .take(layer_widths.len().saturating_sub(1))
+        {
+            writer.flush()?;
+            reader.seek(SeekFrom::Start(layer_offset as u64))?;
+
+            for left_index in (0..*width).step_by(2) {
+                reader.read_exact(&mut left)?;
+                let left_node = to_hash::<T>(&left)?;
+                let parent = match left_index + 1 < *width {
+                    true => {
+                        reader.read_exact(&mut right)?;
+                        T::concat_and_hash(&left_node, Some(&to_hash::<T>(&right)?))
+                    }
+                    false => T::concat_and_hash(&left_node, None),
+                };
+                let parent: Vec<u8> = parent.into();
+                writer.write_all(&parent)?;
+            }
+
+            layer_offset += width * hash_size;
+        }
+        writer.flush()?;
+        drop(writer);
+
+        Self::open(path)
+    }
+
+    /// Opens a tree file written by [`MerkleTreeFile::create`] by mapping it into memory.
+    ///
+    /// The file must not be changed while it is open, as the tree reads it directly.
+    ///
+    /// ## Errors
+    ///
+    /// Fails with [`ErrorKind::HasherMismatch`] if the file was written with another hasher, with
+    /// [`ErrorKind::UnsupportedFileFormat`] if it isn't a tree file of a known version or is
+    /// truncated, and with [`ErrorKind::Io`] if it can't be read.
+    ///
+    /// [`ErrorKind::HasherMismatch`]: crate::ErrorKind::HasherMismatch
+    /// [`ErrorKind::UnsupportedFileFormat`]: crate::ErrorKind::UnsupportedFileFormat
+    /// [`ErrorKind::Io`]: crate::ErrorKind::Io
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::open(path)?;
+        // Safety: the file is only read, and must not be changed while it is mapped
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        let mut header = &mmap[..];
+        if take(&mut header, MAGIC.len())? != MAGIC {
+            return Err(Error::unsupported_file_format("not a tree file"));
+        }
+        let version = u16::from_le_bytes(take_array(&mut header)?);
+        if version != TREE_FILE_VERSION {
+            return Err(Error::unsupported_file_format(&format!(
+                "format version {} is not supported",
+                version
+            )));
+        }
+        let identifier_len = u16::from_le_bytes(take_array(&mut header)?) as usize;
+        let identifier = String::from_utf8_lossy(take(&mut header, identifier_len)?);
+        let hash_size = u32::from_le_bytes(take_array(&mut header)?) as usize;
+        if identifier != T::identifier() || hash_size != T::hash_size() {
+            return Err(Error::hasher_mismatch(&T::identifier(), &identifier));
+        }
+        let leaves_len = u64::from_le_bytes(take_array(&mut header)?) as usize;
+        // The leaves must fit in the file, which also keeps the offsets below from overflowing
+        if leaves_len.saturating_mul(hash_size) > header.len() {
+            return Err(Error::unsupported_file_format(
+                "file size doesn't match the number of leaves",
+            ));
+        }
+
+        let layer_widths = utils::indices::layer_widths(leaves_len);
+        let mut layer_offsets = Vec::with_capacity(layer_widths.len());
+        let mut layer_offset = header_len(identifier_len);
+        for width in &layer_widths {
+            layer_offsets.push(layer_offset);
+            layer_offset += width * hash_size;
+        }
+        if layer_offset != mmap.len() {
+            return Err(Error::unsupported_file_format(
+                "file size doesn't match the number of leaves",
+            ));
+        }
+
+        Ok(Self {
+            mmap,
+            layer_offsets,
+            layer_widths,
+            hasher: PhantomData,
+        })
+    }
+
+    /// Returns the number of leaves in the tree.
+    pub fn leaves_len(&self) -> usize {
+        self.layer_widths.first().copied().unwrap_or(0)
+    }
+
+    /// Returns the leaf at `index`, or `None` if there is no such leaf.
+    pub fn leaf(&self, index: usize) -> Option<T::Hash> {
+        self.node(0, index)
+    }
+
+    /// Returns the tree root, or `None` if the tree has no leaves.
+    pub fn root(&self) -> Option<T::Hash> {
+        self.node(self.layer_widths.len().checked_sub(1)?, 0)
+    }
+
+    /// Similar to [`MerkleTreeFile::root`], but returns a hex encoded string instead of
+    /// [`Hasher::Hash`].
+    pub fn root_hex(&self) -> Option<String> {
+        let root = self.root()?;
+        Some(utils::collections::to_hex_string(&root))
+    }
+
+    /// Returns the Merkle proof of the leaves at the given indices, the same proof that
+    /// [`MerkleTree::proof`] makes.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if there is no leaf at one of the indices.
+    ///
+    /// [`MerkleTree::proof`]: crate::MerkleTree::proof
+    pub fn proof(&self, leaf_indices: &[usize]) -> Result<MerkleProof<T>, Error> {
+        let leaves_len = self.leaves_len();
+        if let Some(index) = leaf_indices.iter().find(|index| **index >= leaves_len) {
+            return Err(Error::leaf_index_out_of_bounds(*index, leaves_len));
+        }
+
+        let mut current_layer_indices = leaf_indices.to_vec();
+        let mut proof_hashes = Vec::new();
+
+        for layer_index in 0..self.layer_widths.len() {
+            let siblings = utils::indices::sibling_indices(&current_layer_indices);
+            let helper_indices = utils::collections::difference(&siblings, &current_layer_indices);
+
+            for index in helper_indices {
+                if index < self.layer_widths[layer_index] {
+                    let hash = self
+                        .node(layer_index, index)
+                        .ok_or_else(Error::vec_to_hash_conversion_error)?;
+                    proof_hashes.push(hash);
+                }
+            }
+
+            current_layer_indices = utils::indices::parent_indices(&current_layer_indices);
+        }
+
+        Ok(MerkleProof::new(proof_hashes))
+    }
+
+    fn node(&self, layer_index: usize, index: usize) -> Option<T::Hash> {
+        if index >= *self.layer_widths.get(layer_index)? {
+            return None;
+        }
+
+        let hash_size = T::hash_size();
+        let start = self.layer_offsets[layer_index] + index * hash_size;
+        to_hash::<T>(&self.mmap[start..start + hash_size]).ok()
+    }
+}
+
+/// Size of the header of a tree file, which ends with the number of leaves
+fn header_len(identifier_len: usize) -> usize {
+    MAGIC.len() + 2 + 2 + identifier_len + 4 + 8
+}
+
+fn to_hash<T: Hasher>(bytes: &[u8]) -> Result<T::Hash, Error> {
+    T::Hash::try_from(bytes.to_vec()).map_err(|_| Error::vec_to_hash_conversion_error())
+}
+
+/// Splits `len` bytes off the start of the header
+fn take<'a>(header: &mut &'a [u8], len: usize) -> Result<&'a [u8], Error> {
+    if header.len() < len {
+        return Err(Error::unsupported_file_format("header is truncated"));
+    }
+
+    let (bytes, rest) = header.split_at(len);
+    *header = rest;
+    Ok(bytes)
+}
+
+fn take_array<const N: usize>(header: &mut &[u8]) -> Result<[u8; N], Error> {
+    let mut array = [0u8; N];
+    array.copy_from_slice(take(header, N)?);
+    Ok(array)
+}
@@ -1,5 +1,7 @@
 use crate::prelude::*;
 use crate::{error::Error, utils, Hasher};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 type PartialTreeLayer<H> = Vec<(usize, H)>;
 
@@ -38,6 +40,31 @@ impl<T: Hasher> PartialTree<T> {
         Self::build(vec![leaf_tuples], utils::indices::tree_depth(leaves.len()))
     }
 
+    /// Same as [`PartialTree::from_leaves`], but hashes each layer on all threads of the `rayon`
+    /// thread pool.
+    #[cfg(feature = "parallel")]
+    pub fn par_from_leaves(leaves: &[T::Hash]) -> Self
+    where
+        T::Hash: Send + Sync,
+    {
+        let mut layers: Vec<PartialTreeLayer<T::Hash>> =
+            vec![leaves.iter().cloned().enumerate().collect()];
+
+        for _ in 0..utils::indices::tree_depth(leaves.len()) {
+            let parents = layers[layers.len() - 1]
+                .par_chunks(2)
+                .enumerate()
+                .map(|(index, nodes)| {
+                    let right_node = nodes.get(1).map(|(_, hash)| hash);
+                    (index, T::concat_and_hash(&nodes[0].1, right_node))
+                })
+                .collect();
+            layers.push(parents);
+        }
+
+        Self { layers }
+    }
+
     pub fn build(partial_layers: Vec<Vec<(usize, T::Hash)>>, depth: usize) -> Result<Self, Error> {
         let layers = Self::build_tree(partial_layers, depth)?;
         Ok(Self { layers })
@@ -118,45 +145,68 @@ impl<T: Hasher> PartialTree<T> {
     /// `MerkleTree`, since both partial trees are essentially constructed in place and there's
     /// no need to verify integrity of the result.
     pub fn merge_unverified(&mut self, other: Self) {
-        // Figure out new tree depth after merge
-        let depth_difference = other.layers().len() - self.layers().len();
-        let combined_tree_size = if depth_difference > 0 {
-            other.layers().len()
-        } else {
-            self.layers().len()
-        };
-
-        for layer_index in 0..combined_tree_size {
-            let mut combined_layer: Vec<(usize, T::Hash)> = Vec::new();
-
-            if let Some(self_layer) = self.layers().get(layer_index) {
-                let mut filtered_layer: Vec<(usize, T::Hash)> = self_layer
-                    .iter()
-                    .filter(|(node_index, _)| !other.contains(layer_index, *node_index))
-                    .cloned()
-                    .collect();
-
-                combined_layer.append(&mut filtered_layer);
+        for (layer_index, other_layer) in other.layers.into_iter().enumerate() {
+            // Figure out new tree depth after merge
+            if layer_index == self.layers.len() {
+                self.layers.push(Vec::new());
             }
 
-            if let Some(other_layer) = other.layers().get(layer_index) {
-                let mut cloned_other_layer = other_layer.clone();
-                combined_layer.append(&mut cloned_other_layer);
+            // Layers are sorted by node index, so each node can be found with a binary search
+            let layer = &mut self.layers[layer_index];
+            for (node_index, hash) in other_layer {
+                match layer.binary_search_by_key(&node_index, |(index, _)| *index) {
+                    Ok(position) => layer[position].1 = hash,
+                    Err(position) => layer.insert(position, (node_index, hash)),
+                }
             }
-
-            combined_layer.sort_by(|(a, _), (b, _)| a.cmp(b));
-            self.upsert_layer(layer_index, combined_layer);
         }
     }
 
-    /// Replace layer at a given index with a new layer. Used during tree merge
-    fn upsert_layer(&mut self, layer_index: usize, mut new_layer: Vec<(usize, T::Hash)>) {
-        match self.layers.get_mut(layer_index) {
-            Some(layer) => {
-                layer.clear();
-                layer.append(new_layer.as_mut())
-            }
-            None => self.layers.push(new_layer),
+    /// Returns the diff that reverts merging `other` into this tree and truncating the result to
+    /// `leaves_count` leaves, i.e. the nodes of this tree that would be replaced or removed.
+    /// The change is reverted by merging the diff with [`PartialTree::merge_unverified`] and then
+    /// truncating the tree back to its previous number of leaves with [`PartialTree::truncate`].
+    pub fn reverse_diff(&self, other: &Self, leaves_count: usize) -> Self {
+        let layer_widths = utils::indices::layer_widths(leaves_count);
+
+        let layers = self
+            .layers()
+            .iter()
+            .enumerate()
+            .map(|(layer_index, layer)| {
+                // Nodes that would be removed by the truncation
+                let width = layer_widths.get(layer_index).cloned().unwrap_or(0);
+                let first_removed = layer.partition_point(|(index, _)| *index < width);
+                let mut reverse_layer = layer[first_removed..].to_vec();
+
+                // Nodes that would be replaced by the merge
+                if let Some(other_layer) = other.layers().get(layer_index) {
+                    for (node_index, _) in other_layer.iter().filter(|(index, _)| *index < width) {
+                        if let Ok(position) =
+                            layer.binary_search_by_key(node_index, |(index, _)| *index)
+                        {
+                            reverse_layer.push(layer[position]);
+                        }
+                    }
+                }
+
+                reverse_layer.sort_by(|(a, _), (b, _)| a.cmp(b));
+                reverse_layer
+            })
+            .collect();
+
+        Self { layers }
+    }
+
+    /// Removes the nodes that are not part of a tree with the given number of leaves. Used after
+    /// a merge when leaves were removed from the tree, since merging can't remove nodes
+    pub fn truncate(&mut self, leaves_count: usize) {
+        let layer_widths = utils::indices::layer_widths(leaves_count);
+        self.layers.truncate(layer_widths.len());
+
+        for (layer, width) in self.layers.iter_mut().zip(layer_widths) {
+            let first_removed = layer.partition_point(|(index, _)| *index < width);
+            layer.truncate(first_removed);
         }
     }
 
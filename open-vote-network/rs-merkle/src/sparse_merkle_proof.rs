@@ -0,0 +1,170 @@
+use crate::prelude::*;
+use crate::{
+    error::Error,
+    sparse_merkle_tree::{empty_hashes, key_bit, leaf_hash, SparseKey, SPARSE_TREE_DEPTH},
+    utils, Hasher,
+};
+
+/// [`SparseMerkleProof`] is used to verify membership and non-membership proofs made by a
+/// [`SparseMerkleTree`].
+///
+/// The proof is compact: a 256-bit bitmap tells which siblings along the path of the key are not
+/// empty subtrees, and only those siblings are included, from the bottom to the top. Verifying
+/// only needs `alloc`, so it can be done inside a smart contract.
+///
+/// ## Examples
+///
+/// ```
+/// # use rs_merkle::{SparseMerkleTree, SparseMerkleProof, algorithms::Sha256, Hasher};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let key = Sha256::hash("alice".as_bytes());
+/// let value = Sha256::hash("revoked".as_bytes());
+/// let tree = SparseMerkleTree::<Sha256>::from_leaves(&[(key, value)]);
+///
+/// let proof_bytes = tree.proof(&key).to_bytes();
+/// let proof = SparseMerkleProof::<Sha256>::from_bytes(&proof_bytes)?;
+///
+/// assert!(proof.verify_membership(tree.root(), &key, &value));
+/// assert!(!proof.verify_non_membership(tree.root(), &key));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`SparseMerkleTree`]: crate::SparseMerkleTree
+pub struct SparseMerkleProof<T: Hasher> {
+    bitmap: [u8; 32],
+    siblings: Vec<T::Hash>,
+}
+
+impl<T: Hasher> SparseMerkleProof<T> {
+    pub fn new(bitmap: [u8; 32], siblings: Vec<T::Hash>) -> Self {
+        SparseMerkleProof { bitmap, siblings }
+    }
+
+    /// Parses a proof serialized with [`SparseMerkleProof::to_bytes`].
+    ///
+    /// ## Errors
+    ///
+    /// In case of a parsing error result will contain [`Error`]
+    ///
+    /// [`Error`]: crate::Error
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let hash_size = T::hash_size();
+        let mut bitmap = [0u8; 32];
+        let bitmap_bytes = bytes
+            .get(..bitmap.len())
+            .ok_or_else(|| Error::wrong_sparse_proof_size(bytes.len(), bitmap.len()))?;
+        bitmap.copy_from_slice(bitmap_bytes);
+
+        let siblings_count = count_siblings(&bitmap);
+        let expected_len = bitmap.len() + siblings_count * hash_size;
+        if bytes.len() != expected_len {
+            return Err(Error::wrong_sparse_proof_size(bytes.len(), expected_len));
+        }
+
+        let siblings = bytes[bitmap.len()..]
+            .chunks(hash_size)
+            .map(|chunk| {
+                T::Hash::try_from(chunk.to_vec()).map_err(|_| Error::vec_to_hash_conversion_error())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self::new(bitmap, siblings))
+    }
+
+    /// Serializes the proof to the bitmap followed by the siblings, from the bottom to the top.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.bitmap.to_vec();
+        for sibling in &self.siblings {
+            bytes.append(&mut (*sibling).into());
+        }
+        bytes
+    }
+
+    /// Returns the bitmap of the siblings that are not empty subtrees, in the same bit order
+    /// as a [`SparseKey`].
+    pub fn bitmap(&self) -> &[u8; 32] {
+        &self.bitmap
+    }
+
+    /// Returns the siblings that are not empty subtrees, from the bottom to the top.
+    pub fn siblings(&self) -> &[T::Hash] {
+        &self.siblings
+    }
+
+    /// Verifies that the leaf at `key` is set to `value` in the tree with the given root.
+    pub fn verify_membership(&self, root: T::Hash, key: &SparseKey, value: &T::Hash) -> bool {
+        match self.root(key, Some(value)) {
+            Ok(extracted_root) => extracted_root == root,
+            Err(_) => false,
+        }
+    }
+
+    /// Verifies that the leaf at `key` is empty in the tree with the given root.
+    pub fn verify_non_membership(&self, root: T::Hash, key: &SparseKey) -> bool {
+        match self.root(key, None) {
+            Ok(extracted_root) => extracted_root == root,
+            Err(_) => false,
+        }
+    }
+
+    /// Calculates the root of a tree where the leaf at `key` is set to `value`, or empty if
+    /// `value` is `None`. Used inside the verify methods, but can be used on its own.
+    ///
+    /// The roots of the empty subtrees are computed once per call (256 hashes), and walking up
+    /// takes one hash per level until the path leaves the empty subtrees, so a call costs at most
+    /// 512 hashes.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the number of siblings doesn't match the bitmap.
+    pub fn root(&self, key: &SparseKey, value: Option<&T::Hash>) -> Result<T::Hash, Error> {
+        let siblings_count = count_siblings(&self.bitmap);
+        if siblings_count != self.siblings.len() {
+            return Err(Error::siblings_count_mismatch(
+                siblings_count,
+                self.siblings.len(),
+            ));
+        }
+
+        let empty_hashes = empty_hashes::<T>();
+        let mut node = match value {
+            Some(value) => leaf_hash::<T>(key, value),
+            None => empty_hashes[SPARSE_TREE_DEPTH],
+        };
+        // Whether the node is still the root of an empty subtree, whose parent is known
+        let mut is_empty = value.is_none();
+        let mut siblings = self.siblings.iter();
+
+        for depth in (0..SPARSE_TREE_DEPTH).rev() {
+            if !key_bit(&self.bitmap, depth) && is_empty {
+                node = empty_hashes[depth];
+                continue;
+            }
+            is_empty = false;
+
+            let sibling = match key_bit(&self.bitmap, depth) {
+                true => *siblings
+                    .next()
+                    .ok_or_else(Error::not_enough_hashes_to_calculate_root)?,
+                false => empty_hashes[depth + 1],
+            };
+            node = match key_bit(key, depth) {
+                true => T::concat_and_hash(&sibling, Some(&node)),
+                false => T::concat_and_hash(&node, Some(&sibling)),
+            };
+        }
+
+        Ok(node)
+    }
+
+    /// Calculates the root and serializes it into a hex string.
+    pub fn root_hex(&self, key: &SparseKey, value: Option<&T::Hash>) -> Result<String, Error> {
+        let root = self.root(key, value)?;
+        Ok(utils::collections::to_hex_string(&root))
+    }
+}
+
+fn count_siblings(bitmap: &[u8; 32]) -> usize {
+    bitmap.iter().map(|byte| byte.count_ones() as usize).sum()
+}
@@ -0,0 +1,245 @@
+use crate::prelude::*;
+use crate::{utils, Hasher, SparseMerkleProof};
+use alloc::collections::BTreeMap;
+
+/// Number of layers below the root of a [`SparseMerkleTree`], one for each bit of a key
+pub const SPARSE_TREE_DEPTH: usize = 256;
+
+/// Key of a leaf in a [`SparseMerkleTree`]. Its bits, starting from the most significant bit of
+/// the first byte, are the path from the root to the leaf - 0 goes left and 1 goes right.
+pub type SparseKey = [u8; 32];
+
+/// [`SparseMerkleTree`] is a Merkle tree with a leaf for each of the 2^256 keys, where all
+/// leaves that were not set are empty. Unlike [`MerkleTree`], it can prove that a key is *not*
+/// in the tree, for example that an account is absent from a revocation list.
+///
+/// Only the leaves that were set and the nodes above them are stored, and empty subtrees are
+/// replaced by precomputed hashes. The node hashes are kept up to date, so inserting or removing
+/// a leaf rehashes only the [`SPARSE_TREE_DEPTH`] nodes on its path. A set leaf is hashed as
+/// `H(key || value)` and an empty leaf as `H("")`, so a proof always commits to the key it was
+/// made for.
+///
+/// ## Examples
+///
+/// ```
+/// # use rs_merkle::{SparseMerkleTree, SparseMerkleProof, algorithms::Sha256, Hasher};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut tree = SparseMerkleTree::<Sha256>::new();
+/// let alice = Sha256::hash("alice".as_bytes());
+/// let bob = Sha256::hash("bob".as_bytes());
+///
+/// tree.insert(alice, Sha256::hash("revoked".as_bytes()));
+/// let root = tree.root();
+///
+/// let proof = SparseMerkleProof::<Sha256>::from_bytes(&tree.proof(&bob).to_bytes())?;
+/// assert!(proof.verify_non_membership(root, &bob));
+///
+/// let proof = tree.proof(&alice);
+/// assert!(proof.verify_membership(root, &alice, &Sha256::hash("revoked".as_bytes())));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`MerkleTree`]: crate::MerkleTree
+#[derive(Clone)]
+pub struct SparseMerkleTree<T: Hasher> {
+    leaves: BTreeMap<SparseKey, T::Hash>,
+    // Hashes of the nodes that are not empty subtrees, indexed by their depth and path
+    nodes: BTreeMap<(usize, SparseKey), T::Hash>,
+    // Roots of empty subtrees, indexed by their depth
+    empty_hashes: [T::Hash; SPARSE_TREE_DEPTH + 1],
+}
+
+impl<T: Hasher> Default for SparseMerkleTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Hasher> SparseMerkleTree<T> {
+    /// Creates a new, empty instance of a sparse Merkle tree.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use rs_merkle::{SparseMerkleTree, algorithms::Sha256};
+    ///
+    /// let tree = SparseMerkleTree::<Sha256>::new();
+    /// assert!(tree.is_empty());
+    /// ```
+    pub fn new() -> Self {
+        Self {
+            leaves: BTreeMap::new(),
+            nodes: BTreeMap::new(),
+            empty_hashes: empty_hashes::<T>(),
+        }
+    }
+
+    /// Builds the tree from `(key, value)` pairs. If a key is given more than once, the last
+    /// value is kept.
+    pub fn from_leaves(leaves: &[(SparseKey, T::Hash)]) -> Self {
+        let mut tree = Self::new();
+
+        for (key, value) in leaves {
+            tree.insert(*key, *value);
+        }
+
+        tree
+    }
+
+    /// Sets the value of the leaf at `key`, replacing the previous value if there was one.
+    pub fn insert(&mut self, key: SparseKey, value: T::Hash) -> &mut Self {
+        self.leaves.insert(key, value);
+        self.nodes
+            .insert((SPARSE_TREE_DEPTH, key), leaf_hash::<T>(&key, &value));
+        self.update_path(&key);
+        self
+    }
+
+    /// Empties the leaf at `key`, returning its previous value if it was set.
+    pub fn remove(&mut self, key: &SparseKey) -> Option<T::Hash> {
+        let value = self.leaves.remove(key)?;
+        self.nodes.remove(&(SPARSE_TREE_DEPTH, *key));
+        self.update_path(key);
+        Some(value)
+    }
+
+    /// Returns the value of the leaf at `key`, or `None` if the leaf is empty.
+    pub fn get(&self, key: &SparseKey) -> Option<&T::Hash> {
+        self.leaves.get(key)
+    }
+
+    /// Returns `true` if the leaf at `key` is set.
+    pub fn contains(&self, key: &SparseKey) -> bool {
+        self.leaves.contains_key(key)
+    }
+
+    /// Returns the number of leaves that are set.
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// Returns `true` if no leaf is set.
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Returns the root of the tree. The root of an empty tree is the root of 2^256 empty leaves.
+    pub fn root(&self) -> T::Hash {
+        self.node(0, &[0u8; 32])
+    }
+
+    /// Similar to [`SparseMerkleTree::root`], but returns a hex encoded string instead of
+    /// [`Hasher::Hash`].
+    pub fn root_hex(&self) -> String {
+        utils::collections::to_hex_string(&self.root())
+    }
+
+    /// Returns the proof of the leaf at `key`. If the leaf is set, the proof shows its value is in
+    /// the tree; otherwise it shows that the leaf is empty. Only the siblings that are not empty
+    /// subtrees are included in the proof.
+    pub fn proof(&self, key: &SparseKey) -> SparseMerkleProof<T> {
+        let mut bitmap = [0u8; 32];
+        let mut siblings = Vec::new();
+
+        for depth in 0..SPARSE_TREE_DEPTH {
+            if !self.nodes.contains_key(&(depth, key_prefix(key, depth))) {
+                // All the remaining siblings are empty subtrees
+                break;
+            }
+
+            let sibling = sibling_prefix(key, depth + 1);
+            if let Some(hash) = self.nodes.get(&(depth + 1, sibling)) {
+                bitmap[depth / 8] |= 1 << (7 - depth % 8);
+                siblings.push(*hash);
+            }
+        }
+
+        // Proof hashes go from the bottom to the top, as in `MerkleProof`
+        siblings.reverse();
+        SparseMerkleProof::new(bitmap, siblings)
+    }
+
+    /// Returns the hash of the node at `depth` with the given path, which is the root of an empty
+    /// subtree if no leaf below it is set
+    fn node(&self, depth: usize, path: &SparseKey) -> T::Hash {
+        self.nodes
+            .get(&(depth, *path))
+            .copied()
+            .unwrap_or(self.empty_hashes[depth])
+    }
+
+    /// Rehashes the nodes from the leaf at `key` up to the root, after the leaf has changed.
+    /// Nodes that have become empty subtrees are removed
+    fn update_path(&mut self, key: &SparseKey) {
+        for depth in (0..SPARSE_TREE_DEPTH).rev() {
+            let path = key_prefix(key, depth);
+            let child = key_prefix(key, depth + 1);
+            let sibling = sibling_prefix(key, depth + 1);
+
+            if !self.nodes.contains_key(&(depth + 1, child))
+                && !self.nodes.contains_key(&(depth + 1, sibling))
+            {
+                self.nodes.remove(&(depth, path));
+                continue;
+            }
+
+            let (left, right) = if key_bit(key, depth) {
+                (sibling, child)
+            } else {
+                (child, sibling)
+            };
+            let hash = T::concat_and_hash(
+                &self.node(depth + 1, &left),
+                Some(&self.node(depth + 1, &right)),
+            );
+            self.nodes.insert((depth, path), hash);
+        }
+    }
+}
+
+/// Returns the path from the root to the node at `depth` on the way to the key, i.e. the first
+/// `depth` bits of the key with the rest set to 0
+fn key_prefix(key: &SparseKey, depth: usize) -> SparseKey {
+    let mut path = [0u8; 32];
+    path[..depth / 8].copy_from_slice(&key[..depth / 8]);
+    if depth < SPARSE_TREE_DEPTH {
+        path[depth / 8] = key[depth / 8] & !(0xff >> (depth % 8));
+    }
+    path
+}
+
+/// Returns the path to the sibling of the node at `depth` (> 0) on the way to the key
+fn sibling_prefix(key: &SparseKey, depth: usize) -> SparseKey {
+    let mut path = key_prefix(key, depth);
+    path[(depth - 1) / 8] ^= 1 << (7 - (depth - 1) % 8);
+    path
+}
+
+/// Returns the bit of the key that picks the child of a node at `depth`, `true` being right
+pub(crate) fn key_bit(key: &SparseKey, depth: usize) -> bool {
+    (key[depth / 8] >> (7 - depth % 8)) & 1 == 1
+}
+
+/// Hash of a leaf that is set
+pub(crate) fn leaf_hash<T: Hasher>(key: &SparseKey, value: &T::Hash) -> T::Hash {
+    let mut data = key.to_vec();
+    data.append(&mut (*value).into());
+    T::hash(&data)
+}
+
+/// Hash of a leaf that is empty
+fn empty_leaf_hash<T: Hasher>() -> T::Hash {
+    T::hash(&[])
+}
+
+/// Roots of empty subtrees indexed by their depth, from the root of the empty tree at depth 0 to
+/// the empty leaf at depth [`SPARSE_TREE_DEPTH`]. Computed in one pass of 256 hashes
+pub(crate) fn empty_hashes<T: Hasher>() -> [T::Hash; SPARSE_TREE_DEPTH + 1] {
+    let mut empty_hashes = [empty_leaf_hash::<T>(); SPARSE_TREE_DEPTH + 1];
+    for depth in (0..SPARSE_TREE_DEPTH).rev() {
+        empty_hashes[depth] =
+            T::concat_and_hash(&empty_hashes[depth + 1], Some(&empty_hashes[depth + 1]));
+    }
+    empty_hashes
+}
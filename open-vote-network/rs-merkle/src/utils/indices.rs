@@ -46,6 +46,44 @@ pub fn tree_depth(leaves_count: usize) -> usize {
     height
 }
 
+/// Returns the number of nodes in each layer of a tree with the given number of leaves, starting
+/// from the leaves and ending with the root
+pub fn layer_widths(leaves_count: usize) -> Vec<usize> {
+    if leaves_count == 0 {
+        return Vec::new();
+    }
+
+    let mut layer_widths = vec![leaves_count];
+    for _ in 0..tree_depth(leaves_count) {
+        let width = div_ceil(*layer_widths.last().unwrap(), 2);
+        layer_widths.push(width);
+    }
+
+    layer_widths
+}
+
+/// Returns the peaks of a Merkle mountain range with the given number of leaves, from left to
+/// right, as `(index of the first leaf, height)` of the perfect subtree under each peak
+pub fn mountain_peaks(leaves_count: usize) -> Vec<(usize, usize)> {
+    let mut peaks = Vec::new();
+    let mut start = 0;
+
+    for height in (0..usize::BITS as usize).rev() {
+        if leaves_count & (1 << height) != 0 {
+            peaks.push((start, height));
+            start += 1 << height;
+        }
+    }
+
+    peaks
+}
+
+/// Returns the number of leaves in the left subtree of a tree with at least 2 leaves, which is the
+/// largest power of two smaller than the number of leaves
+pub fn subtree_split(leaves_count: usize) -> usize {
+    1 << (usize::BITS - 1 - (leaves_count - 1).leading_zeros())
+}
+
 pub fn uneven_layers(tree_leaves_count: usize) -> BTreeMap<usize, usize> {
     let mut leaves_count = tree_leaves_count;
     let depth = tree_depth(tree_leaves_count);
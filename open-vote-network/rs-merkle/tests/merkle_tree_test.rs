@@ -34,7 +34,7 @@ pub mod tree_depth {
 
 pub mod proof {
     use crate::common;
-    use rs_merkle::{algorithms::Sha256, MerkleTree};
+    use rs_merkle::{algorithms::Sha256, Hasher, MerkleTree};
 
     #[test]
     pub fn should_return_a_correct_proof() {
@@ -52,6 +52,27 @@ pub mod proof {
 
         assert_eq!(proof_hashes, expected_proof_hashes)
     }
+
+    #[test]
+    pub fn should_return_the_same_proofs_in_a_batch() {
+        for leaves_len in 0..20 {
+            let leaves: Vec<[u8; 32]> = (0..leaves_len)
+                .map(|i: usize| Sha256::hash(&i.to_le_bytes()))
+                .collect();
+            let merkle_tree = MerkleTree::<Sha256>::from_leaves(&leaves);
+            let indices: Vec<usize> = (0..leaves_len).rev().collect();
+
+            let proofs = merkle_tree.proofs(&indices);
+
+            assert_eq!(proofs.len(), leaves_len);
+            for (index, proof) in indices.iter().zip(proofs) {
+                assert_eq!(
+                    proof.proof_hashes(),
+                    merkle_tree.proof(&[*index]).proof_hashes()
+                );
+            }
+        }
+    }
 }
 
 pub mod commit {
@@ -187,7 +208,7 @@ pub mod commit {
 
 pub mod rollback {
     use crate::common;
-    use rs_merkle::{algorithms::Sha256, Hasher, MerkleTree};
+    use rs_merkle::{algorithms::Sha256, Error, Hasher, MerkleTree};
 
     #[test]
     pub fn should_rollback_previous_commit() {
@@ -264,4 +285,1057 @@ pub mod rollback {
             Some("1f7379539707bcaea00564168d1d4d626b09b73f8a2a365234c62d763f854da2".to_string())
         );
     }
+
+    #[test]
+    pub fn should_rollback_to_any_previous_commit() -> Result<(), Error> {
+        let test_data = common::setup();
+        let mut merkle_tree = MerkleTree::<Sha256>::new();
+        let mut expected_leaves = Vec::new();
+
+        // Each commit grows, changes or shrinks the tree
+        merkle_tree
+            .append(test_data.leaf_hashes.clone().as_mut())
+            .commit();
+        expected_leaves.push(merkle_tree.leaves());
+        merkle_tree.insert(Sha256::hash("g".as_bytes())).commit();
+        expected_leaves.push(merkle_tree.leaves());
+        merkle_tree
+            .update(2, Sha256::hash("x".as_bytes()))?
+            .commit();
+        expected_leaves.push(merkle_tree.leaves());
+        merkle_tree.remove(0)?.remove(3)?.commit();
+        expected_leaves.push(merkle_tree.leaves());
+        merkle_tree
+            .remove(0)?
+            .remove(0)?
+            .remove(0)?
+            .remove(0)?
+            .remove(0)?
+            .commit();
+        expected_leaves.push(merkle_tree.leaves());
+
+        let roots = merkle_tree.commit_roots();
+        assert_eq!(roots.len(), 5);
+        assert_eq!(
+            roots[0],
+            MerkleTree::<Sha256>::from_leaves(&test_data.leaf_hashes).root()
+        );
+        assert_eq!(roots[4], None);
+
+        for commit_index in (0..4).rev() {
+            merkle_tree.rollback_to(commit_index);
+
+            let leaves = expected_leaves[commit_index].clone().unwrap_or_default();
+            let expected_tree = MerkleTree::<Sha256>::from_leaves(&leaves);
+            assert_eq!(merkle_tree.root(), roots[commit_index]);
+            assert_eq!(merkle_tree.root(), expected_tree.root());
+            assert_eq!(merkle_tree.depth(), expected_tree.depth());
+            assert_eq!(merkle_tree.leaves(), Some(leaves));
+            assert_eq!(merkle_tree.commit_roots(), roots[..=commit_index].to_vec());
+        }
+
+        // Rolling back to a commit that doesn't exist does nothing
+        merkle_tree.rollback_to(3);
+        assert_eq!(merkle_tree.commit_roots(), roots[..1].to_vec());
+
+        merkle_tree.rollback();
+        assert_eq!(merkle_tree.root(), None);
+        assert_eq!(merkle_tree.commit_roots(), Vec::new());
+
+        Ok(())
+    }
+}
+
+pub mod update {
+    use crate::common;
+    use rs_merkle::{algorithms::Sha256, Error, ErrorKind, Hasher, MerkleTree};
+
+    #[test]
+    pub fn should_give_the_same_root_as_rebuilding_the_tree() -> Result<(), Error> {
+        let test_data = common::setup();
+        let mut leaves = test_data.leaf_hashes.clone();
+        let mut merkle_tree = MerkleTree::<Sha256>::from_leaves(&leaves);
+
+        for (index, value) in ["x", "y", "z"].iter().enumerate() {
+            let leaf = Sha256::hash(value.as_bytes());
+            leaves[index * 2] = leaf;
+            merkle_tree.update(index * 2, leaf)?;
+        }
+
+        // Updating an uncommitted leaf replaces it as well
+        let leaf = Sha256::hash("g".as_bytes());
+        leaves.push(leaf);
+        merkle_tree.insert(Sha256::hash("w".as_bytes()));
+        merkle_tree.update(leaves.len() - 1, leaf)?;
+
+        let expected_tree = MerkleTree::<Sha256>::from_leaves(&leaves);
+        assert_eq!(merkle_tree.uncommitted_root(), expected_tree.root());
+        assert_eq!(merkle_tree.root_hex(), Some(test_data.expected_root_hex));
+
+        merkle_tree.commit();
+        assert_eq!(merkle_tree.root(), expected_tree.root());
+        assert_eq!(merkle_tree.leaves(), Some(leaves.clone()));
+
+        // Proofs of the updated tree are still valid
+        let root = merkle_tree.root().ok_or(Error::not_enough_helper_nodes())?;
+        for index in 0..leaves.len() {
+            let proof = merkle_tree.proof(&[index]);
+            assert!(proof.verify(root, &[index], &[leaves[index]], leaves.len()));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn should_return_an_error_when_the_index_is_out_of_bounds() {
+        let test_data = common::setup();
+        let mut merkle_tree = MerkleTree::<Sha256>::from_leaves(&test_data.leaf_hashes);
+
+        let result = merkle_tree.update(6, Sha256::hash("x".as_bytes()));
+
+        assert_eq!(
+            result.err().map(|error| error.kind()),
+            Some(ErrorKind::LeafIndexOutOfBounds)
+        );
+    }
+}
+
+pub mod remove {
+    use crate::common;
+    use rs_merkle::{algorithms::Sha256, Error, ErrorKind, Hasher, MerkleTree};
+
+    #[test]
+    pub fn should_give_the_same_root_as_rebuilding_the_tree() -> Result<(), Error> {
+        let test_data = common::setup();
+        let mut leaves = test_data.leaf_hashes.clone();
+        let mut merkle_tree = MerkleTree::<Sha256>::from_leaves(&leaves);
+
+        // Removing leaves one by one, shrinking the tree all the way down to a single leaf
+        for index in [1, 4, 0, 2, 0] {
+            leaves.swap_remove(index);
+            merkle_tree.remove(index)?.commit();
+
+            let expected_tree = MerkleTree::<Sha256>::from_leaves(&leaves);
+            assert_eq!(merkle_tree.root(), expected_tree.root());
+            assert_eq!(merkle_tree.depth(), expected_tree.depth());
+            assert_eq!(merkle_tree.leaves(), Some(leaves.clone()));
+
+            // Proofs of the smaller tree are still valid
+            let root = merkle_tree.root().ok_or(Error::not_enough_helper_nodes())?;
+            for index in 0..leaves.len() {
+                let proof = merkle_tree.proof(&[index]);
+                assert!(proof.verify(root, &[index], &[leaves[index]], leaves.len()));
+            }
+        }
+
+        // Removing the last leaf leaves an empty tree
+        merkle_tree.remove(0)?.commit();
+        assert_eq!(merkle_tree.root(), None);
+        assert_eq!(merkle_tree.leaves_len(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn should_combine_with_other_uncommitted_changes() -> Result<(), Error> {
+        let test_data = common::setup();
+        let mut leaves = test_data.leaf_hashes.clone();
+        let mut merkle_tree = MerkleTree::<Sha256>::from_leaves(&leaves);
+
+        let g = Sha256::hash("g".as_bytes());
+        let x = Sha256::hash("x".as_bytes());
+
+        // Removing a committed leaf moves the uncommitted last leaf into its place
+        merkle_tree.insert(g).remove(2)?;
+        leaves.push(g);
+        leaves.swap_remove(2);
+        // Removing committed leaves from the end drops their uncommitted updates
+        merkle_tree.update(5, x)?.remove(5)?.remove(4)?;
+        leaves.truncate(4);
+
+        let expected_tree = MerkleTree::<Sha256>::from_leaves(&leaves);
+        assert_eq!(merkle_tree.uncommitted_root(), expected_tree.root());
+
+        merkle_tree.commit();
+        assert_eq!(merkle_tree.root(), expected_tree.root());
+        assert_eq!(merkle_tree.leaves(), Some(leaves));
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn should_rollback_a_removal() -> Result<(), Error> {
+        let test_data = common::setup();
+        let mut merkle_tree = MerkleTree::<Sha256>::from_leaves(&test_data.leaf_hashes);
+
+        merkle_tree.remove(0)?.remove(0)?.commit();
+        merkle_tree
+            .update(0, Sha256::hash("x".as_bytes()))?
+            .commit();
+        assert_eq!(merkle_tree.leaves_len(), 4);
+
+        merkle_tree.rollback();
+        merkle_tree.rollback();
+        assert_eq!(merkle_tree.root_hex(), Some(test_data.expected_root_hex));
+        assert_eq!(merkle_tree.leaves(), Some(test_data.leaf_hashes));
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn should_return_an_error_when_the_index_is_out_of_bounds() {
+        let mut merkle_tree = MerkleTree::<Sha256>::new();
+
+        let result = merkle_tree.remove(0);
+
+        assert_eq!(
+            result.err().map(|error| error.kind()),
+            Some(ErrorKind::LeafIndexOutOfBounds)
+        );
+    }
+}
+
+pub mod rfc6962 {
+    use rs_merkle::{
+        algorithms::{Rfc6962, Sha256},
+        Hasher, MerkleProof, MerkleTree,
+    };
+
+    // Leaves and roots of the first n leaves, as used by the Certificate Transparency reference
+    // implementation
+    fn leaves() -> Vec<[u8; 32]> {
+        let leaf_values: [&[u8]; 8] = [
+            &[],
+            &[0x00],
+            &[0x10],
+            &[0x20, 0x21],
+            &[0x30, 0x31],
+            &[0x40, 0x41, 0x42, 0x43],
+            &[0x50, 0x51, 0x52, 0x53, 0x54, 0x55, 0x56, 0x57],
+            &[
+                0x60, 0x61, 0x62, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0x6a, 0x6b, 0x6c, 0x6d,
+                0x6e, 0x6f,
+            ],
+        ];
+
+        leaf_values
+            .iter()
+            .map(|x| Rfc6962::<Sha256>::hash(x))
+            .collect()
+    }
+
+    const EXPECTED_ROOTS_HEX: [&str; 8] = [
+        "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d",
+        "fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125",
+        "aeb6bcfe274b70a14fb067a5e5578264db0fa9b51af5e0ba159158f329e06e77",
+        "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+        "4e3bbb1f7b478dcfe71fb631631519a3bca12c9aefca1612bfce4c13a86264d4",
+        "76e67dadbcdf1e10e1b74ddc608abd2f98dfb16fbce75277b5232a127f2087ef",
+        "ddb89be403809e325750d3d263cd78929c2942b7942a34b77e122c9594a74c8c",
+        "5dc9da79a70659a9ad559cb701ded9a2ab9d823aad2f4960cfe370eff4604328",
+    ];
+
+    #[test]
+    pub fn should_match_the_reference_roots() {
+        let leaves = leaves();
+
+        for (leaves_len, expected_root_hex) in (1..=leaves.len()).zip(EXPECTED_ROOTS_HEX.iter()) {
+            let merkle_tree = MerkleTree::<Rfc6962<Sha256>>::from_leaves(&leaves[..leaves_len]);
+
+            assert_eq!(merkle_tree.root_hex(), Some(expected_root_hex.to_string()));
+        }
+    }
+
+    #[test]
+    pub fn should_match_the_reference_roots_when_appending_leaves() {
+        let mut merkle_tree = MerkleTree::<Rfc6962<Sha256>>::new();
+
+        for (leaf, expected_root_hex) in leaves().iter().zip(EXPECTED_ROOTS_HEX.iter()) {
+            merkle_tree.insert(*leaf).commit();
+
+            assert_eq!(merkle_tree.root_hex(), Some(expected_root_hex.to_string()));
+        }
+    }
+
+    #[test]
+    pub fn should_verify_proofs_against_the_reference_roots() {
+        let leaves = leaves();
+        let merkle_tree = MerkleTree::<Rfc6962<Sha256>>::from_leaves(&leaves[..7]);
+        let indices_to_prove = vec![2, 6];
+        let leaves_to_prove = vec![leaves[2], leaves[6]];
+
+        let proof_bytes = merkle_tree.proof(&indices_to_prove).to_bytes();
+        let proof = MerkleProof::<Rfc6962<Sha256>>::from_bytes(&proof_bytes).unwrap();
+
+        assert_eq!(
+            proof
+                .root_hex(&indices_to_prove, &leaves_to_prove, 7)
+                .unwrap(),
+            EXPECTED_ROOTS_HEX[6]
+        );
+        assert!(proof.verify(
+            merkle_tree.root().unwrap(),
+            &indices_to_prove,
+            &leaves_to_prove,
+            7
+        ));
+    }
+
+    #[test]
+    pub fn should_reject_an_internal_node_posing_as_a_leaf() {
+        let leaf_values = ["a", "b", "c", "d"];
+
+        // Without domain separation, the concatenation of the two first leaf hashes is a valid
+        // leaf of a tree with half the leaves and the same root
+        let leaves: Vec<[u8; 32]> = leaf_values
+            .iter()
+            .map(|x| Sha256::hash(x.as_bytes()))
+            .collect();
+        let merkle_tree = MerkleTree::<Sha256>::from_leaves(&leaves);
+        let forged_value = [leaves[0], leaves[1]].concat();
+        let forged_leaf = Sha256::hash(&forged_value);
+        let proof =
+            MerkleProof::<Sha256>::new(vec![Sha256::concat_and_hash(&leaves[2], Some(&leaves[3]))]);
+
+        assert!(proof.verify(merkle_tree.root().unwrap(), &[0], &[forged_leaf], 2));
+
+        let leaves: Vec<[u8; 32]> = leaf_values
+            .iter()
+            .map(|x| Rfc6962::<Sha256>::hash(x.as_bytes()))
+            .collect();
+        let merkle_tree = MerkleTree::<Rfc6962<Sha256>>::from_leaves(&leaves);
+        let forged_value = [leaves[0], leaves[1]].concat();
+        let forged_leaf = Rfc6962::<Sha256>::hash(&forged_value);
+        let proof = MerkleProof::<Rfc6962<Sha256>>::new(vec![Rfc6962::<Sha256>::concat_and_hash(
+            &leaves[2],
+            Some(&leaves[3]),
+        )]);
+
+        assert!(!proof.verify(merkle_tree.root().unwrap(), &[0], &[forged_leaf], 2));
+    }
+}
+
+pub mod sparse_merkle_tree {
+    use rs_merkle::{
+        algorithms::Sha256, ErrorKind, Hasher, SparseKey, SparseMerkleProof, SparseMerkleTree,
+    };
+
+    fn setup() -> (SparseMerkleTree<Sha256>, Vec<(SparseKey, [u8; 32])>) {
+        let mut neighbour = Sha256::hash("a".as_bytes());
+        // Only differs from the key of "a" in the last bit, so it shares the whole path
+        neighbour[31] ^= 1;
+
+        let leaves: Vec<(SparseKey, [u8; 32])> = ["a", "b", "c", "d"]
+            .iter()
+            .map(|x| Sha256::hash(x.as_bytes()))
+            .chain(vec![neighbour])
+            .map(|key| (key, Sha256::hash(&key)))
+            .collect();
+
+        (SparseMerkleTree::<Sha256>::from_leaves(&leaves), leaves)
+    }
+
+    #[test]
+    pub fn should_verify_membership_proofs() {
+        let (tree, leaves) = setup();
+        let root = tree.root();
+
+        for (key, value) in &leaves {
+            let proof = tree.proof(key);
+
+            assert!(proof.verify_membership(root, key, value));
+            assert!(!proof.verify_membership(root, key, &Sha256::hash("x".as_bytes())));
+            assert!(!proof.verify_non_membership(root, key));
+        }
+    }
+
+    #[test]
+    pub fn should_verify_non_membership_proofs() {
+        let (tree, _) = setup();
+        let root = tree.root();
+
+        for absent in ["e", "f", "g"].iter() {
+            let key = Sha256::hash(absent.as_bytes());
+            let proof = tree.proof(&key);
+
+            assert!(proof.verify_non_membership(root, &key));
+            assert!(!proof.verify_membership(root, &key, &Sha256::hash(&key)));
+        }
+    }
+
+    #[test]
+    pub fn should_return_the_same_root_regardless_of_insertion_order() {
+        let (tree, mut leaves) = setup();
+
+        leaves.reverse();
+        let reversed_tree = SparseMerkleTree::<Sha256>::from_leaves(&leaves);
+
+        assert_eq!(tree.root_hex(), reversed_tree.root_hex());
+    }
+
+    #[test]
+    pub fn should_return_the_empty_root_after_removing_all_leaves() {
+        let (mut tree, leaves) = setup();
+        let empty_root = SparseMerkleTree::<Sha256>::new().root();
+
+        assert_ne!(tree.root(), empty_root);
+        for (key, value) in &leaves {
+            assert_eq!(tree.remove(key), Some(*value));
+        }
+
+        assert!(tree.is_empty());
+        assert_eq!(tree.root(), empty_root);
+        assert!(tree
+            .proof(&leaves[0].0)
+            .verify_non_membership(empty_root, &leaves[0].0));
+    }
+
+    #[test]
+    pub fn should_update_the_root_and_proofs_on_insert_and_remove() {
+        let (mut tree, leaves) = setup();
+        let (key, value) = leaves[0];
+
+        // Removing a leaf gives the same tree as never inserting it
+        let without_key = SparseMerkleTree::<Sha256>::from_leaves(&leaves[1..]);
+        tree.remove(&key);
+        assert_eq!(tree.root(), without_key.root());
+        assert!(tree.proof(&key).verify_non_membership(tree.root(), &key));
+
+        // Replacing the value of a leaf gives the same tree as inserting the new value
+        let new_value = Sha256::hash("x".as_bytes());
+        let mut with_new_value = leaves.clone();
+        with_new_value[0].1 = new_value;
+        tree.insert(key, value).insert(key, new_value);
+        assert_eq!(
+            tree.root(),
+            SparseMerkleTree::<Sha256>::from_leaves(&with_new_value).root()
+        );
+        assert!(tree
+            .proof(&key)
+            .verify_membership(tree.root(), &key, &new_value));
+    }
+
+    #[test]
+    pub fn should_serialize_a_compact_proof() -> Result<(), Box<dyn std::error::Error>> {
+        let (tree, leaves) = setup();
+        let (key, value) = leaves[0];
+
+        let proof = tree.proof(&key);
+        let proof_bytes = proof.to_bytes();
+        let parsed = SparseMerkleProof::<Sha256>::from_bytes(&proof_bytes)?;
+
+        // The key of "a" and its neighbour share the whole path, so the sibling at the bottom is
+        // a leaf, and only a few other siblings are not empty
+        assert!(proof.siblings().len() < 10);
+        assert_eq!(proof.siblings()[0], {
+            let mut neighbour_leaf = leaves[4].0.to_vec();
+            neighbour_leaf.extend_from_slice(&leaves[4].1);
+            Sha256::hash(&neighbour_leaf)
+        });
+        assert_eq!(proof_bytes.len(), 32 + proof.siblings().len() * 32);
+        assert_eq!(parsed.bitmap(), proof.bitmap());
+        assert_eq!(parsed.siblings(), proof.siblings());
+        assert!(parsed.verify_membership(tree.root(), &key, &value));
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn should_return_an_error_when_the_proof_is_malformed() {
+        let (tree, leaves) = setup();
+        let proof_bytes = tree.proof(&leaves[0].0).to_bytes();
+
+        let result = SparseMerkleProof::<Sha256>::from_bytes(&proof_bytes[..proof_bytes.len() - 1]);
+        assert_eq!(
+            result.err().map(|error| error.kind()),
+            Some(ErrorKind::SerializedProofSizeIsIncorrect)
+        );
+
+        let result = SparseMerkleProof::<Sha256>::from_bytes(&proof_bytes[..16]);
+        assert_eq!(
+            result.err().map(|error| error.kind()),
+            Some(ErrorKind::SerializedProofSizeIsIncorrect)
+        );
+
+        let proof = SparseMerkleProof::<Sha256>::new([0xff; 32], vec![]);
+        assert_eq!(
+            proof
+                .root(&leaves[0].0, None)
+                .err()
+                .map(|error| error.kind()),
+            Some(ErrorKind::SiblingsCountMismatch)
+        );
+    }
+}
+
+pub mod merkle_mountain_range {
+    use rs_merkle::{
+        algorithms::Sha256, proof_serializers::ReverseHashesOrder, ErrorKind, Hasher,
+        MerkleMountainRange, MerkleMountainRangeProof, MerkleTree,
+    };
+
+    fn leaves(count: usize) -> Vec<[u8; 32]> {
+        (0..count)
+            .map(|i| Sha256::hash(i.to_string().as_bytes()))
+            .collect()
+    }
+
+    #[test]
+    pub fn should_have_the_same_root_as_a_merkle_tree() {
+        let leaves = leaves(20);
+        let mut mmr = MerkleMountainRange::<Sha256>::new();
+
+        assert_eq!(mmr.root(), None);
+        for (i, leaf) in leaves.iter().enumerate() {
+            mmr.push(*leaf);
+            let merkle_tree = MerkleTree::<Sha256>::from_leaves(&leaves[..=i]);
+
+            assert_eq!(mmr.root(), merkle_tree.root());
+            assert_eq!(mmr.peaks().len(), (i + 1).count_ones() as usize);
+        }
+    }
+
+    #[test]
+    pub fn should_verify_inclusion_proofs() -> Result<(), Box<dyn std::error::Error>> {
+        let leaves = leaves(13);
+
+        for leaves_len in 1..=leaves.len() {
+            let mmr = MerkleMountainRange::<Sha256>::from_leaves(&leaves[..leaves_len]);
+            let root = mmr.root().ok_or("couldn't get the root")?;
+
+            for leaf_index in 0..leaves_len {
+                let proof_bytes = mmr.proof(leaf_index)?.serialize::<ReverseHashesOrder>();
+                let proof = MerkleMountainRangeProof::<Sha256>::deserialize::<ReverseHashesOrder>(
+                    &proof_bytes,
+                )?;
+
+                assert!(proof.verify(root, leaf_index, leaves[leaf_index], leaves_len));
+                if leaves_len > 1 {
+                    let other_index = (leaf_index + 1) % leaves_len;
+                    assert!(!proof.verify(root, leaf_index, leaves[other_index], leaves_len));
+                    assert!(!proof.verify(root, other_index, leaves[leaf_index], leaves_len));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn should_verify_consistency_proofs() -> Result<(), Box<dyn std::error::Error>> {
+        let leaves = leaves(13);
+        let mut mmr = MerkleMountainRange::<Sha256>::new();
+
+        for new_len in 1..=leaves.len() {
+            mmr.push(leaves[new_len - 1]);
+            let new_root = mmr.root().ok_or("couldn't get the root")?;
+
+            for old_len in 1..=new_len {
+                let old_root = mmr.root_at(old_len).ok_or("couldn't get the old root")?;
+                let proof = mmr.consistency_proof(old_len)?;
+                let proof = MerkleMountainRangeProof::<Sha256>::from_bytes(&proof.to_bytes())?;
+
+                assert!(proof.verify_consistency(old_root, new_root, old_len, new_len));
+                if old_len < new_len {
+                    assert!(!proof.verify_consistency(new_root, new_root, old_len, new_len));
+                    assert!(!proof.verify_consistency(old_root, old_root, old_len, new_len));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn should_return_an_error_for_invalid_proofs() {
+        let leaves = leaves(5);
+        let mmr = MerkleMountainRange::<Sha256>::from_leaves(&leaves);
+
+        assert_eq!(
+            mmr.proof(5).err().map(|error| error.kind()),
+            Some(ErrorKind::LeafIndexOutOfBounds)
+        );
+        assert_eq!(
+            mmr.consistency_proof(0).err().map(|error| error.kind()),
+            Some(ErrorKind::InvalidTreeSizes)
+        );
+        assert_eq!(
+            mmr.consistency_proof(6).err().map(|error| error.kind()),
+            Some(ErrorKind::InvalidTreeSizes)
+        );
+
+        let mut proof_hashes = mmr.proof(0).unwrap().proof_hashes().to_vec();
+        proof_hashes.push(leaves[0]);
+        let proof = MerkleMountainRangeProof::<Sha256>::new(proof_hashes);
+        assert_eq!(
+            proof.root(0, leaves[0], 5).err().map(|error| error.kind()),
+            Some(ErrorKind::UnusedProofHashes)
+        );
+
+        let proof = MerkleMountainRangeProof::<Sha256>::new(vec![]);
+        assert_eq!(
+            proof.root(0, leaves[0], 5).err().map(|error| error.kind()),
+            Some(ErrorKind::NotEnoughHashesToCalculateRoot)
+        );
+    }
+}
+
+pub mod consistency {
+    use rs_merkle::{
+        algorithms::{Rfc6962, Sha256},
+        proof_serializers::ReverseHashesOrder,
+        ErrorKind, Hasher, MerkleProof, MerkleTree,
+    };
+
+    #[test]
+    pub fn should_verify_consistency_between_all_sizes() -> Result<(), Box<dyn std::error::Error>> {
+        let leaves: Vec<[u8; 32]> = (0..13)
+            .map(|i: usize| Sha256::hash(i.to_string().as_bytes()))
+            .collect();
+        let merkle_tree = MerkleTree::<Sha256>::from_leaves(&leaves);
+        let roots: Vec<[u8; 32]> = (1..=leaves.len())
+            .map(|len| {
+                MerkleTree::<Sha256>::from_leaves(&leaves[..len])
+                    .root()
+                    .unwrap()
+            })
+            .collect();
+
+        for new_len in 1..=leaves.len() {
+            for old_len in 1..=new_len {
+                let (old_root, new_root) = (roots[old_len - 1], roots[new_len - 1]);
+                let proof_bytes = merkle_tree
+                    .consistency_proof(old_len, new_len)?
+                    .serialize::<ReverseHashesOrder>();
+                let proof = MerkleProof::<Sha256>::deserialize::<ReverseHashesOrder>(&proof_bytes)?;
+
+                assert!(proof.verify_consistency(old_root, new_root, old_len, new_len));
+                if old_len < new_len {
+                    assert!(!proof.verify_consistency(new_root, new_root, old_len, new_len));
+                    assert!(!proof.verify_consistency(old_root, old_root, old_len, new_len));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn should_match_the_reference_proofs() -> Result<(), Box<dyn std::error::Error>> {
+        // Leaves and proofs used by the Certificate Transparency reference implementation
+        let leaf_values: [&[u8]; 8] = [
+            &[],
+            &[0x00],
+            &[0x10],
+            &[0x20, 0x21],
+            &[0x30, 0x31],
+            &[0x40, 0x41, 0x42, 0x43],
+            &[0x50, 0x51, 0x52, 0x53, 0x54, 0x55, 0x56, 0x57],
+            &[
+                0x60, 0x61, 0x62, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0x6a, 0x6b, 0x6c, 0x6d,
+                0x6e, 0x6f,
+            ],
+        ];
+        let leaves: Vec<[u8; 32]> = leaf_values
+            .iter()
+            .map(|x| Rfc6962::<Sha256>::hash(x))
+            .collect();
+        let merkle_tree = MerkleTree::<Rfc6962<Sha256>>::from_leaves(&leaves);
+        let expected_proofs: [(usize, usize, Vec<&str>); 4] = [
+            (1, 1, vec![]),
+            (
+                1,
+                8,
+                vec![
+                    "96a296d224f285c67bee93c30f8a309157f0daa35dc5b87e410b78630a09cfc7",
+                    "5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+                    "6b47aaf29ee3c2af9af889bc1fb9254dabd31177f16232dd6aab035ca39bf6e4",
+                ],
+            ),
+            (
+                6,
+                8,
+                vec![
+                    "0ebc5d3437fbe2db158b9f126a1d118e308181031d0a949f8dededebc558ef6a",
+                    "ca854ea128ed050b41b35ffc1b87b8eb2bde461e9e3b5596ece6b9d5975a0ae0",
+                    "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+                ],
+            ),
+            (
+                2,
+                5,
+                vec![
+                    "5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+                    "bc1a0643b12e4d2d7c77918f44e0f4f79a838b6cf9ec5b5c283e1f4d88599e6b",
+                ],
+            ),
+        ];
+
+        for (old_len, new_len, expected_proof_hashes) in expected_proofs.iter() {
+            let proof = merkle_tree.consistency_proof(*old_len, *new_len)?;
+            let old_root = MerkleTree::<Rfc6962<Sha256>>::from_leaves(&leaves[..*old_len])
+                .root()
+                .ok_or("couldn't get the merkle root")?;
+            let new_root = MerkleTree::<Rfc6962<Sha256>>::from_leaves(&leaves[..*new_len])
+                .root()
+                .ok_or("couldn't get the merkle root")?;
+
+            assert_eq!(&proof.proof_hashes_hex(), expected_proof_hashes);
+            assert!(proof.verify_consistency(old_root, new_root, *old_len, *new_len));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn should_return_an_error_when_the_sizes_are_invalid() {
+        let leaves: Vec<[u8; 32]> = ["a", "b", "c"]
+            .iter()
+            .map(|x| Sha256::hash(x.as_bytes()))
+            .collect();
+        let merkle_tree = MerkleTree::<Sha256>::from_leaves(&leaves);
+
+        for (old_len, new_len) in [(0, 2), (3, 2), (2, 4)].iter() {
+            assert_eq!(
+                merkle_tree
+                    .consistency_proof(*old_len, *new_len)
+                    .err()
+                    .map(|error| error.kind()),
+                Some(ErrorKind::InvalidTreeSizes)
+            );
+        }
+    }
+}
+
+#[cfg(feature = "concordium")]
+pub mod concordium {
+    use crate::common;
+    use concordium_contracts_common::{
+        from_bytes,
+        schema::{Fields, SchemaType, SizeLength, Type},
+        to_bytes,
+    };
+    use rs_merkle::{algorithms::Sha256, MerkleProof, MerkleTree};
+
+    #[test]
+    pub fn should_serialize_a_proof_as_a_contract_parameter() {
+        let test_data = common::setup();
+        let merkle_tree = MerkleTree::<Sha256>::from_leaves(&test_data.leaf_hashes);
+        let indices_to_prove = vec![3, 4];
+        let leaves_to_prove = &test_data.leaf_hashes[3..5];
+
+        let proof = merkle_tree.proof(&indices_to_prove);
+        let parameter = to_bytes(&proof);
+
+        // The number of hashes as a u32, followed by the hashes in direct order
+        assert_eq!(parameter[..4], 3u32.to_le_bytes());
+        assert_eq!(parameter[4..], proof.to_bytes()[..]);
+
+        let parsed: MerkleProof<Sha256> = from_bytes(&parameter).unwrap();
+        assert!(parsed.verify(
+            merkle_tree.root().unwrap(),
+            &indices_to_prove,
+            leaves_to_prove,
+            test_data.leaf_hashes.len()
+        ));
+        assert!(from_bytes::<MerkleProof<Sha256>>(&parameter[..parameter.len() - 1]).is_err());
+    }
+
+    #[test]
+    pub fn should_describe_a_proof_in_the_schema() {
+        assert_eq!(
+            MerkleProof::<Sha256>::get_type(),
+            Type::Struct(Fields::Named(vec![(
+                "proof_hashes".to_string(),
+                Type::List(
+                    SizeLength::U32,
+                    Box::new(Type::Array(32, Box::new(Type::U8)))
+                ),
+            )]))
+        );
+    }
+}
+
+#[cfg(feature = "parallel")]
+pub mod parallel {
+    use rs_merkle::{algorithms::Sha256, Hasher, MerkleTree};
+
+    fn leaves(leaves_len: usize) -> Vec<[u8; 32]> {
+        (0..leaves_len)
+            .map(|i| Sha256::hash(&i.to_le_bytes()))
+            .collect()
+    }
+
+    #[test]
+    pub fn should_build_the_same_tree_as_from_leaves() {
+        for leaves_len in 0..70 {
+            let leaves = leaves(leaves_len);
+            let expected_tree = MerkleTree::<Sha256>::from_leaves(&leaves);
+
+            let merkle_tree = MerkleTree::<Sha256>::par_from_leaves(&leaves);
+
+            assert_eq!(merkle_tree.root(), expected_tree.root());
+            assert_eq!(merkle_tree.leaves(), expected_tree.leaves());
+            assert_eq!(merkle_tree.commit_roots(), expected_tree.commit_roots());
+            if leaves_len > 0 {
+                assert_eq!(merkle_tree.depth(), expected_tree.depth());
+            }
+        }
+    }
+
+    #[test]
+    pub fn should_keep_the_history_of_a_tree_built_in_parallel() {
+        let leaves = leaves(13);
+        let mut merkle_tree = MerkleTree::<Sha256>::par_from_leaves(&leaves[..10]);
+        let mut expected_tree = MerkleTree::<Sha256>::from_leaves(&leaves[..10]);
+
+        for tree in [&mut merkle_tree, &mut expected_tree].iter_mut() {
+            tree.append(&mut leaves[10..].to_vec()).commit();
+        }
+        assert_eq!(merkle_tree.root(), expected_tree.root());
+
+        merkle_tree.rollback();
+        assert_eq!(
+            merkle_tree.root(),
+            MerkleTree::<Sha256>::from_leaves(&leaves[..10]).root()
+        );
+        merkle_tree.rollback();
+        assert_eq!(merkle_tree.root(), None);
+    }
+
+    #[test]
+    pub fn should_return_the_same_proofs_as_the_sequential_batch() {
+        let leaves = leaves(3000);
+        let merkle_tree = MerkleTree::<Sha256>::par_from_leaves(&leaves);
+        let root = merkle_tree.root().unwrap();
+        let indices: Vec<usize> = (0..leaves.len()).step_by(7).chain(vec![2999, 0]).collect();
+
+        let proofs = merkle_tree.par_proofs(&indices);
+
+        assert_eq!(proofs.len(), indices.len());
+        for ((index, proof), expected_proof) in indices
+            .iter()
+            .zip(&proofs)
+            .zip(merkle_tree.proofs(&indices))
+        {
+            assert_eq!(proof.proof_hashes(), expected_proof.proof_hashes());
+            assert!(proof.verify(root, &[*index], &leaves[*index..*index + 1], leaves.len()));
+        }
+    }
+}
+
+#[cfg(any(feature = "keccak", feature = "sha3", feature = "blake2b"))]
+pub mod hashers {
+    use crate::common;
+    use rs_merkle::{utils, Hasher, MerkleMountainRange, MerkleTree};
+
+    /// Checks the hashes of "" and "abc", then the root and the proof of the leaves 3 and 4 of
+    /// the tree of the test leaves, against values computed independently with the same hash
+    fn check_hasher<H: Hasher<Hash = [u8; 32]>>(
+        empty_hash_hex: &str,
+        abc_hash_hex: &str,
+        expected_root_hex: &str,
+        expected_proof_hashes: [&str; 3],
+    ) {
+        assert_eq!(
+            utils::collections::to_hex_string(&H::hash(b"")),
+            empty_hash_hex
+        );
+        assert_eq!(
+            utils::collections::to_hex_string(&H::hash(b"abc")),
+            abc_hash_hex
+        );
+
+        let leaves: Vec<[u8; 32]> = common::setup()
+            .leaf_values
+            .iter()
+            .map(|x| H::hash(x.as_bytes()))
+            .collect();
+        let merkle_tree = MerkleTree::<H>::from_leaves(&leaves);
+        let root = merkle_tree.root().unwrap();
+        assert_eq!(merkle_tree.root_hex(), Some(expected_root_hex.to_string()));
+        assert_eq!(
+            MerkleMountainRange::<H>::from_leaves(&leaves).root(),
+            Some(root)
+        );
+
+        let indices_to_prove = vec![3, 4];
+        let proof = merkle_tree.proof(&indices_to_prove);
+        assert_eq!(proof.proof_hashes_hex(), expected_proof_hashes);
+        assert!(proof.verify(root, &indices_to_prove, &leaves[3..5], leaves.len()));
+        assert!(!proof.verify(root, &indices_to_prove, &leaves[2..4], leaves.len()));
+    }
+
+    #[cfg(feature = "keccak")]
+    #[test]
+    pub fn should_match_keccak256_vectors() {
+        check_hasher::<rs_merkle::algorithms::Keccak256>(
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+            "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45",
+            "9012f1e18a87790d2e01faace75aaaca38e53df437cdce2c0552464dda4af49c",
+            [
+                "0b42b6393c1f53060fe3ddbfcd7aadcca894465a5a438f69c87d790b2299b9b2",
+                "d1e8aeb79500496ef3dc2e57ba746a8315d048b7a664a2bf948db4fa91960483",
+                "805b21d846b189efaeb0377d6bb0d201b3872a363e607c25088f025b0c6ae1f8",
+            ],
+        );
+    }
+
+    #[cfg(feature = "sha3")]
+    #[test]
+    pub fn should_match_sha3_256_vectors() {
+        check_hasher::<rs_merkle::algorithms::Sha3_256>(
+            "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a",
+            "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532",
+            "ac3da38e993e128b8a4a637542ff42ef6589efba30c56abb96cab319733750cf",
+            [
+                "263ab762270d3b73d3e2cddf9acc893bb6bd41110347e5d5e4bd1d3c128ea90a",
+                "a0b37b8bfae8e71330bd8e278e4a45ca916d00475dd8b85e9352533454c9fec8",
+                "29df505440ebe180c00857e92b0694c56a33762b08944472492b0cbf6ec607e3",
+            ],
+        );
+    }
+
+    #[cfg(feature = "blake2b")]
+    #[test]
+    pub fn should_match_blake2b_256_vectors() {
+        check_hasher::<rs_merkle::algorithms::Blake2b256>(
+            "0e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8",
+            "bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319",
+            "1b0e542a750f8cbdc5fe4a1b75999a0e9a2caa15a88798dc24ee123e742c2ce1",
+            [
+                "ed5402299a6208014e0f5f25ae6ca3badddc95db67dce164cb8aa086bd48978a",
+                "e0befc611c4bd14b4d2f0d6c78a0de8d98c747217912f27ac7f67b06476bde3d",
+                "43145816c4f1efa1c8bda6dc342028e63cec088c591dfebac0ef70b4825b3c71",
+            ],
+        );
+    }
+}
+
+#[cfg(feature = "disk")]
+pub mod merkle_tree_file {
+    use crate::common;
+    use rs_merkle::{
+        algorithms::{Rfc6962, Sha256},
+        ErrorKind, Hasher, MerkleTree, MerkleTreeFile,
+    };
+    use std::{fs, path::PathBuf};
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("rs_merkle_{}_{}.tree", name, std::process::id()))
+    }
+
+    #[test]
+    pub fn should_have_the_same_root_and_proofs_as_a_merkle_tree(
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let path = temp_path("same_as_merkle_tree");
+
+        for leaves_len in 0..40 {
+            let leaves = (0..leaves_len).map(|i: usize| Sha256::hash(&i.to_le_bytes()));
+            let merkle_tree =
+                MerkleTree::<Sha256>::from_leaves(&leaves.clone().collect::<Vec<_>>());
+
+            MerkleTreeFile::<Sha256>::create(&path, leaves)?;
+            let tree_file = MerkleTreeFile::<Sha256>::open(&path)?;
+
+            assert_eq!(tree_file.leaves_len(), leaves_len);
+            assert_eq!(tree_file.root(), merkle_tree.root());
+            assert_eq!(tree_file.root_hex(), merkle_tree.root_hex());
+            for index in 0..leaves_len {
+                assert_eq!(
+                    tree_file.leaf(index),
+                    merkle_tree.leaves().map(|l| l[index])
+                );
+                assert_eq!(
+                    tree_file.proof(&[index])?.proof_hashes(),
+                    merkle_tree.proof(&[index]).proof_hashes()
+                );
+            }
+            assert_eq!(tree_file.leaf(leaves_len), None);
+            assert_eq!(
+                tree_file
+                    .proof(&[leaves_len])
+                    .err()
+                    .map(|error| error.kind()),
+                Some(ErrorKind::LeafIndexOutOfBounds)
+            );
+        }
+
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    pub fn should_make_a_multi_proof() -> Result<(), Box<dyn std::error::Error>> {
+        let test_data = common::setup();
+        let path = temp_path("multi_proof");
+
+        let tree_file =
+            MerkleTreeFile::<Sha256>::create(&path, test_data.leaf_hashes.iter().copied())?;
+        let proof = tree_file.proof(&[3, 4])?;
+
+        assert_eq!(tree_file.root_hex(), Some(test_data.expected_root_hex));
+        assert_eq!(
+            proof.proof_hashes_hex(),
+            [
+                "2e7d2c03a9507ae265ecf5b5356885a53393a2029d241394997265a1a25aefc6",
+                "252f10c83610ebca1a059c0bae8255eba2f95be4d1d7bcfa89d7248a82d9f111",
+                "e5a01fee14e0ed5c48714f22180f25ad8365b53f9779f79dc4a3d7e93963f94a",
+            ]
+        );
+
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    pub fn should_not_open_a_file_of_another_hasher() -> Result<(), Box<dyn std::error::Error>> {
+        let test_data = common::setup();
+        let path = temp_path("another_hasher");
+
+        MerkleTreeFile::<Sha256>::create(&path, test_data.leaf_hashes.iter().copied())?;
+
+        assert_eq!(
+            MerkleTreeFile::<Rfc6962<Sha256>>::open(&path)
+                .err()
+                .map(|error| error.kind()),
+            Some(ErrorKind::HasherMismatch)
+        );
+
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    pub fn should_not_open_a_file_that_is_not_a_tree_file() -> Result<(), Box<dyn std::error::Error>>
+    {
+        let test_data = common::setup();
+        let path = temp_path("not_a_tree_file");
+        let open_error = |path: &PathBuf| {
+            MerkleTreeFile::<Sha256>::open(path)
+                .err()
+                .map(|error| error.kind())
+        };
+
+        MerkleTreeFile::<Sha256>::create(&path, test_data.leaf_hashes.iter().copied())?;
+        let bytes = fs::read(&path)?;
+
+        fs::write(&path, &bytes[..bytes.len() - 1])?;
+        assert_eq!(open_error(&path), Some(ErrorKind::UnsupportedFileFormat));
+
+        fs::write(&path, &bytes[..10])?;
+        assert_eq!(open_error(&path), Some(ErrorKind::UnsupportedFileFormat));
+
+        let mut other_version = bytes.clone();
+        other_version[4] = 2;
+        fs::write(&path, &other_version)?;
+        assert_eq!(open_error(&path), Some(ErrorKind::UnsupportedFileFormat));
+
+        fs::write(&path, b"not a tree")?;
+        assert_eq!(open_error(&path), Some(ErrorKind::UnsupportedFileFormat));
+
+        fs::remove_file(&path)?;
+        assert_eq!(open_error(&path), Some(ErrorKind::Io));
+        Ok(())
+    }
 }
@@ -2,10 +2,20 @@
 
 use concordium_std::*;
 use group::GroupEncoding;
+use k256::elliptic_curve::ops::Reduce;
 use k256::elliptic_curve::{PublicKey, ScalarCore, SecretKey};
-use k256::{ProjectivePoint, Scalar, Secp256k1};
+use k256::{ProjectivePoint, Scalar, Secp256k1, U256};
 use sha2::{Digest, Sha256};
 
+/// Error from converting bytes into a Scalar or a ProjectivePoint
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ConversionError {
+    // Not a valid non-zero scalar in the prime field
+    InvalidScalar,
+    // Not a valid point on the curve
+    InvalidPoint,
+}
+
 #[derive(Serialize, SchemaType, Default, PartialEq, Clone)]
 pub struct OneInTwoZKP {
     r1: Vec<u8>,
@@ -49,34 +59,92 @@ impl OneInTwoZKP {
     }
 
     /// Extract the Scalars of the proof: (r1, r2, d1, d2)
-    pub fn extract_scalars(&self) -> (Scalar, Scalar, Scalar, Scalar) {
-        (
-            convert_vec_to_scalar(&self.r1),
-            convert_vec_to_scalar(&self.r2),
-            convert_vec_to_scalar(&self.d1),
-            convert_vec_to_scalar(&self.d2),
-        )
+    pub fn extract_scalars(&self) -> Result<(Scalar, Scalar, Scalar, Scalar), ConversionError> {
+        Ok((
+            convert_vec_to_scalar(&self.r1)?,
+            convert_vec_to_scalar(&self.r2)?,
+            convert_vec_to_scalar(&self.d1)?,
+            convert_vec_to_scalar(&self.d2)?,
+        ))
     }
 
     /// Extract the Points of the proof: (x, y, a1, b1, a2, b2)
     pub fn extract_points(
         &self,
-    ) -> (
-        ProjectivePoint,
-        ProjectivePoint,
-        ProjectivePoint,
-        ProjectivePoint,
-        ProjectivePoint,
-        ProjectivePoint,
-    ) {
+    ) -> Result<
         (
-            convert_vec_to_point(&self.x),
-            convert_vec_to_point(&self.y),
-            convert_vec_to_point(&self.a1),
-            convert_vec_to_point(&self.b1),
-            convert_vec_to_point(&self.a2),
-            convert_vec_to_point(&self.b2),
-        )
+            ProjectivePoint,
+            ProjectivePoint,
+            ProjectivePoint,
+            ProjectivePoint,
+            ProjectivePoint,
+            ProjectivePoint,
+        ),
+        ConversionError,
+    > {
+        Ok((
+            convert_vec_to_point(&self.x)?,
+            convert_vec_to_point(&self.y)?,
+            convert_vec_to_point(&self.a1)?,
+            convert_vec_to_point(&self.b1)?,
+            convert_vec_to_point(&self.a2)?,
+            convert_vec_to_point(&self.b2)?,
+        ))
+    }
+}
+
+#[derive(Serialize, SchemaType, Default, PartialEq, Clone)]
+pub struct OneInKZKP {
+    r: Vec<Vec<u8>>,
+    d: Vec<Vec<u8>>,
+    a: Vec<Vec<u8>>,
+    b: Vec<Vec<u8>>,
+}
+
+impl OneInKZKP {
+    /// Create a new OneInKZKP with one (r, d, a, b) entry per vote option
+    pub fn new(
+        r: Vec<Scalar>,
+        d: Vec<Scalar>,
+        a: Vec<ProjectivePoint>,
+        b: Vec<ProjectivePoint>,
+    ) -> Self {
+        Self {
+            r: r.iter().map(|r| r.to_bytes().to_vec()).collect(),
+            d: d.iter().map(|d| d.to_bytes().to_vec()).collect(),
+            a: a.iter().map(|a| a.to_bytes().to_vec()).collect(),
+            b: b.iter().map(|b| b.to_bytes().to_vec()).collect(),
+        }
+    }
+
+    /// Extract the Scalars of the proof: (r, d)
+    pub fn extract_scalars(&self) -> Result<(Vec<Scalar>, Vec<Scalar>), ConversionError> {
+        Ok((
+            self.r
+                .iter()
+                .map(convert_vec_to_scalar)
+                .collect::<Result<_, _>>()?,
+            self.d
+                .iter()
+                .map(convert_vec_to_scalar)
+                .collect::<Result<_, _>>()?,
+        ))
+    }
+
+    /// Extract the Points of the proof: (a, b)
+    pub fn extract_points(
+        &self,
+    ) -> Result<(Vec<ProjectivePoint>, Vec<ProjectivePoint>), ConversionError> {
+        Ok((
+            self.a
+                .iter()
+                .map(convert_vec_to_point)
+                .collect::<Result<_, _>>()?,
+            self.b
+                .iter()
+                .map(convert_vec_to_point)
+                .collect::<Result<_, _>>()?,
+        ))
     }
 }
 
@@ -96,11 +164,40 @@ impl SchnorrProof {
     }
 
     /// Extract the primitives of the proof: (g_w, r)
-    pub fn extract_primitives(&self) -> (ProjectivePoint, Scalar) {
-        (
-            convert_vec_to_point(&self.g_w),
-            convert_vec_to_scalar(&self.r),
-        )
+    pub fn extract_primitives(&self) -> Result<(ProjectivePoint, Scalar), ConversionError> {
+        Ok((
+            convert_vec_to_point(&self.g_w)?,
+            convert_vec_to_scalar(&self.r)?,
+        ))
+    }
+}
+
+#[derive(Serialize, SchemaType, PartialEq, Default, Clone)]
+pub struct ChaumPedersenProof {
+    pub g_w: Vec<u8>,
+    pub h_w: Vec<u8>,
+    pub r: Vec<u8>,
+}
+
+impl ChaumPedersenProof {
+    /// Create a new ChaumPedersenProof
+    pub fn new(g_w: ProjectivePoint, h_w: ProjectivePoint, r: Scalar) -> Self {
+        Self {
+            g_w: g_w.to_bytes().to_vec(),
+            h_w: h_w.to_bytes().to_vec(),
+            r: r.to_bytes().to_vec(),
+        }
+    }
+
+    /// Extract the primitives of the proof: (g_w, h_w, r)
+    pub fn extract_primitives(
+        &self,
+    ) -> Result<(ProjectivePoint, ProjectivePoint, Scalar), ConversionError> {
+        Ok((
+            convert_vec_to_point(&self.g_w)?,
+            convert_vec_to_point(&self.h_w)?,
+            convert_vec_to_scalar(&self.r)?,
+        ))
     }
 }
 
@@ -112,28 +209,82 @@ pub struct MerkleProof {
 }
 
 /// Utility function to convert Vec -> Scalar
-pub fn convert_vec_to_scalar(vec: &Vec<u8>) -> Scalar {
-    let scalar_option = SecretKey::<Secp256k1>::from_be_bytes(vec).ok();
+pub fn convert_vec_to_scalar(vec: &Vec<u8>) -> Result<Scalar, ConversionError> {
+    let scalar =
+        SecretKey::<Secp256k1>::from_be_bytes(vec).map_err(|_| ConversionError::InvalidScalar)?;
 
-    let scalar = unwrap_abort(scalar_option);
-
-    return From::<&'_ ScalarCore<Secp256k1>>::from(SecretKey::as_scalar_core(&scalar));
+    Ok(From::<&'_ ScalarCore<Secp256k1>>::from(
+        SecretKey::as_scalar_core(&scalar),
+    ))
 }
 
 /// Utility function to convert Vec -> ProjectivePoint
-pub fn convert_vec_to_point(vec: &Vec<u8>) -> ProjectivePoint {
-    let point_option = PublicKey::<Secp256k1>::from_sec1_bytes(vec).ok();
+pub fn convert_vec_to_point(vec: &Vec<u8>) -> Result<ProjectivePoint, ConversionError> {
+    let point =
+        PublicKey::<Secp256k1>::from_sec1_bytes(vec).map_err(|_| ConversionError::InvalidPoint)?;
 
-    let point = unwrap_abort(point_option);
+    Ok(PublicKey::to_projective(&point))
+}
 
-    return PublicKey::to_projective(&point);
+/// Utility function to encode a vote option i of a voter with weight w as the exponent w*base^i,
+/// such that option i is voted as g^(w*base^i)
+pub fn encode_vote_option(option: u32, base: u64, weight: u64) -> Scalar {
+    Scalar::from(base.pow(option)) * Scalar::from(weight)
 }
 
-/// Utility function to go from Vec -> Hash -> Scalar
+/// Utility function to compute the merkle leaf of a voter, which commits to both their account and weight
+pub fn merkle_leaf(account: &AccountAddress, weight: u64) -> [u8; 32] {
+    Sha256::digest(&to_bytes(&(*account, weight))).into()
+}
+
+/// Utility function to go from Vec -> Hash -> Scalar (reduced modulo the order of the curve, so it cannot fail)
 pub fn hash_to_scalar(bytes_to_hash: Vec<u8>) -> Scalar {
     let hash_value = Sha256::digest(bytes_to_hash);
 
-    return convert_vec_to_scalar(&hash_value.to_vec());
+    return <Scalar as Reduce<U256>>::from_be_bytes_reduced(hash_value);
+}
+
+/// Domain-separation tags, one for each type of Fiat-Shamir challenge
+pub const SCHNORR_DOMAIN: &[u8] = b"OpenVoteNetwork/Schnorr";
+pub const ONE_IN_TWO_DOMAIN: &[u8] = b"OpenVoteNetwork/OneInTwo";
+pub const ONE_IN_K_DOMAIN: &[u8] = b"OpenVoteNetwork/OneInK";
+pub const CHAUM_PEDERSEN_DOMAIN: &[u8] = b"OpenVoteNetwork/ChaumPedersen";
+
+/// What a Fiat-Shamir challenge is bound to besides the curve points, such that a proof
+/// cannot be replayed in another election instance, by another voter or in another phase
+#[derive(Clone, PartialEq, Debug)]
+pub struct ChallengeContext {
+    pub contract: ContractAddress,
+    pub sender: AccountAddress,
+    pub phase: Vec<u8>, // serialized voting phase
+}
+
+impl ChallengeContext {
+    pub fn new(contract: ContractAddress, sender: AccountAddress, phase: &impl Serial) -> Self {
+        ChallengeContext {
+            contract,
+            sender,
+            phase: to_bytes(phase),
+        }
+    }
+}
+
+/// Utility function to create a challenge H(domain, contract, sender, phase, points) -> Scalar,
+/// where the encoding of each point is hashed in the given order
+pub fn hash_to_challenge(
+    domain: &[u8],
+    context: &ChallengeContext,
+    points_to_hash: &[ProjectivePoint],
+) -> Scalar {
+    let mut bytes_to_hash = domain.to_vec();
+    bytes_to_hash.extend(to_bytes(&context.contract));
+    bytes_to_hash.extend(to_bytes(&context.sender));
+    bytes_to_hash.extend(&context.phase);
+    for point in points_to_hash {
+        bytes_to_hash.extend(point.to_bytes().to_vec());
+    }
+
+    hash_to_scalar(bytes_to_hash)
 }
 
 /// Utility to better unwrap a value in WASM
@@ -4,19 +4,32 @@
 
 use concordium_std::*;
 use group::GroupEncoding;
-use k256::ProjectivePoint;
+use k256::{ProjectivePoint, Scalar};
 use rs_merkle::algorithms::Sha256 as merkle_sha256;
-use rs_merkle::*;
 use sha2::{Digest, Sha256};
-use util::{hash_to_scalar, unwrap_abort, MerkleProof, OneInTwoZKP, SchnorrProof};
+use util::{
+    encode_vote_option, hash_to_challenge, merkle_leaf, ChallengeContext, ChaumPedersenProof,
+    MerkleProof, OneInKZKP, OneInTwoZKP, SchnorrProof, CHAUM_PEDERSEN_DOMAIN, ONE_IN_K_DOMAIN,
+    ONE_IN_TWO_DOMAIN, SCHNORR_DOMAIN,
+};
 
-/// Check Schnorr ZKP: g^w = g^r * g^xz
-pub fn verify_schnorr_zkp(g_x: ProjectivePoint, schnorr: util::SchnorrProof) -> bool {
-    let (g_w, r) = SchnorrProof::extract_primitives(&schnorr);
+/// Check Schnorr ZKP: g^w = g^r * g^xz. A proof that does not decode is rejected
+pub fn verify_schnorr_zkp(
+    g_x: ProjectivePoint,
+    schnorr: util::SchnorrProof,
+    context: &ChallengeContext,
+) -> bool {
+    let (g_w, r) = match SchnorrProof::extract_primitives(&schnorr) {
+        Ok(primitives) => primitives,
+        Err(_) => return false,
+    };
 
-    // Create hash z = H(g, g^w, g^x)
-    let value_to_hash = ProjectivePoint::GENERATOR + g_w + g_x;
-    let z = hash_to_scalar(value_to_hash.to_bytes().to_vec());
+    // Create hash z = H(domain, context, g, g^w, g^x)
+    let z = hash_to_challenge(
+        SCHNORR_DOMAIN,
+        context,
+        &[ProjectivePoint::GENERATOR, g_w, g_x],
+    );
 
     let g_r = ProjectivePoint::GENERATOR * r;
     let g_x_z = g_x * z;
@@ -28,15 +41,53 @@ pub fn verify_schnorr_zkp(g_x: ProjectivePoint, schnorr: util::SchnorrProof) ->
     false
 }
 
+/// Check Chaum-Pedersen ZKP that g^x and h^x share the exponent x: g^w = g^r * g^xz and h^w = h^r * h^xz
+pub fn verify_chaum_pedersen_zkp(
+    g_x: ProjectivePoint,
+    h: ProjectivePoint,
+    h_x: ProjectivePoint,
+    proof: util::ChaumPedersenProof,
+    context: &ChallengeContext,
+) -> bool {
+    let (g_w, h_w, r) = match ChaumPedersenProof::extract_primitives(&proof) {
+        Ok(primitives) => primitives,
+        Err(_) => return false,
+    };
+
+    // Create hash z = H(domain, context, g, h, g^x, h^x, g^w, h^w)
+    let z = hash_to_challenge(
+        CHAUM_PEDERSEN_DOMAIN,
+        context,
+        &[ProjectivePoint::GENERATOR, h, g_x, h_x, g_w, h_w],
+    );
+
+    if (ProjectivePoint::GENERATOR * r) + (g_x * z) != g_w {
+        return false;
+    }
+    if (h * r) + (h_x * z) != h_w {
+        return false;
+    }
+    true
+}
+
 /// Check one-in-two ZKP: check v = 1 or v = 0 without knowing which
-pub fn verify_one_in_two_zkp(zkp: util::OneInTwoZKP, g_y: ProjectivePoint) -> bool {
-    let (r1, r2, d1, d2) = OneInTwoZKP::extract_scalars(&zkp);
+pub fn verify_one_in_two_zkp(
+    zkp: util::OneInTwoZKP,
+    g_y: ProjectivePoint,
+    context: &ChallengeContext,
+) -> bool {
+    let (r1, r2, d1, d2) = match OneInTwoZKP::extract_scalars(&zkp) {
+        Ok(scalars) => scalars,
+        Err(_) => return false,
+    };
 
-    let (x, y, a1, b1, a2, b2) = OneInTwoZKP::extract_points(&zkp);
+    let (x, y, a1, b1, a2, b2) = match OneInTwoZKP::extract_points(&zkp) {
+        Ok(points) => points,
+        Err(_) => return false,
+    };
 
-    // c = H(g^x, y, a1, b1, a2, b2)
-    let value_to_hash = x.clone() + y.clone() + a1.clone() + b1.clone() + a2.clone() + b2.clone();
-    let c = hash_to_scalar(value_to_hash.to_bytes().to_vec());
+    // c = H(domain, context, g^x, y, a1, b1, a2, b2)
+    let c = hash_to_challenge(ONE_IN_TWO_DOMAIN, context, &[x, y, a1, b1, a2, b2]);
 
     if c != d1.clone() + d2.clone() {
         return false;
@@ -56,42 +107,148 @@ pub fn verify_one_in_two_zkp(zkp: util::OneInTwoZKP, g_y: ProjectivePoint) -> bo
     true
 }
 
+/// Check one-in-k ZKP: check that the vote is g^xy*g^(w*base^i) for one of the k options i without knowing which.
+/// For two options this is the range proof that the weighted vote is for v = 0 or v = 1.
+/// The proof is checked against the voter's stored voting key g^x and the submitted vote y
+pub fn verify_one_in_k_zkp(
+    zkp: util::OneInKZKP,
+    x: ProjectivePoint,
+    y: ProjectivePoint,
+    g_y: ProjectivePoint,
+    number_of_options: u32,
+    base: u64,
+    weight: u64,
+    context: &ChallengeContext,
+) -> bool {
+    let (r, d) = match OneInKZKP::extract_scalars(&zkp) {
+        Ok(scalars) => scalars,
+        Err(_) => return false,
+    };
+
+    let (a, b) = match OneInKZKP::extract_points(&zkp) {
+        Ok(points) => points,
+        Err(_) => return false,
+    };
+
+    // There must be exactly one (r, d, a, b) entry per option
+    let k = number_of_options as usize;
+    if r.len() != k || d.len() != k || a.len() != k || b.len() != k {
+        return false;
+    }
+
+    // c = H(domain, context, g^x, y, a1, b1, ..., ak, bk)
+    let mut points_to_hash = vec![x, y];
+    for i in 0..k {
+        points_to_hash.push(a[i]);
+        points_to_hash.push(b[i]);
+    }
+    let c = hash_to_challenge(ONE_IN_K_DOMAIN, context, &points_to_hash);
+
+    let d_sum = d.iter().fold(Scalar::ZERO, |sum, d_i| sum + d_i);
+    if c != d_sum {
+        return false;
+    }
+
+    for i in 0..k {
+        let g_v = ProjectivePoint::GENERATOR * encode_vote_option(i as u32, base, weight);
+
+        if a[i] != (ProjectivePoint::GENERATOR * r[i].clone()) + (x.clone() * d[i].clone()) {
+            return false;
+        }
+        if b[i] != (g_y.clone() * r[i].clone()) + ((y.clone() - g_v) * d[i].clone()) {
+            return false;
+        }
+    }
+    true
+}
+
 /// Check commitment matches actual vote
 pub fn check_commitment(vote: ProjectivePoint, commitment: Vec<u8>) -> bool {
     Sha256::digest(&vote.to_bytes().to_vec()).to_vec() == commitment
 }
 
-/// Brute force and tally yes votes on-chain
-pub fn brute_force_tally(votes: Vec<ProjectivePoint>) -> i32 {
-    // Set first vote as initial tally
-    let mut tally = unwrap_abort(votes.get(0)).clone();
+/// Largest tally exponent that result searches for on-chain. Baby-step giant-step takes about 2*sqrt(MAX_TALLY)
+/// point additions, so setup rejects configs where the tally can be larger, as result could never finish
+pub const MAX_TALLY: u64 = 1 << 24;
+
+/// Brute force and tally the weighted votes for each option on-chain.
+/// Option i is voted as g^(w*base^i), so the tally g^(c_1*base^0 + ... + c_k*base^(k-1)) is found with a bounded
+/// discrete log and decoded into the total weight c_i voted for each option. Returns None if no valid tally is found
+pub fn brute_force_tally(
+    votes: Vec<ProjectivePoint>,
+    number_of_options: u32,
+    base: u64,
+    total_weight: u64,
+) -> Option<Vec<u64>> {
+    // Add all the votes (curve points) to tally, e.g \prod g^xy*g^v (calculated differently due to additive curve)
+    let tally = votes
+        .iter()
+        .fold(ProjectivePoint::IDENTITY, |tally, vote| tally + vote);
 
-    for i in 1..votes.len() {
-        // Add all the rest of the votes (curve points) to tally, e.g \prod g^xy*g^v (calculated differently due to additive curve)
-        tally = tally + unwrap_abort(votes.get(i));
+    // The tally is at most when every voter chooses the last option
+    let max_exponent = base
+        .checked_pow(number_of_options.checked_sub(1)?)?
+        .checked_mul(total_weight)?;
+
+    let mut exponent = baby_step_giant_step(tally, max_exponent)?;
+
+    // Decode the exponent in base `base` to get the weighted votes for each option
+    let mut option_votes = Vec::new();
+    for _ in 0..number_of_options {
+        option_votes.push(exponent % base);
+        exponent /= base;
+    }
+
+    // Every voter must have voted with their full weight for exactly one option
+    if option_votes.iter().sum::<u64>() != total_weight {
+        return None;
     }
+    Some(option_votes)
+}
 
+/// Find e with g^e = point and 0 <= e <= max_exponent using baby-step giant-step.
+/// Returns None if no such e exists
+fn baby_step_giant_step(point: ProjectivePoint, max_exponent: u64) -> Option<u64> {
+    // Step size m, such that m * m > max_exponent
+    let mut step: u64 = 1;
+    while step * step <= max_exponent {
+        step += 1;
+    }
+
+    // Baby steps: table of g^j -> j for 0 <= j < m
+    let mut baby_steps = collections::BTreeMap::new();
     let mut current_g = ProjectivePoint::IDENTITY;
-    let mut yes_votes = 0;
-    let pg = ProjectivePoint::GENERATOR;
+    for j in 0..step {
+        baby_steps.insert(current_g.to_bytes().to_vec(), j);
+        current_g += ProjectivePoint::GENERATOR;
+    }
 
-    // Go through all votes and brute force number of yes votes
-    while current_g != tally {
-        yes_votes += 1;
-        current_g += &pg;
+    // Giant steps: look up point * g^(-im) in the table for 0 <= i < m
+    let giant_step = -current_g;
+    let mut gamma = point;
+    for i in 0..step {
+        if let Some(j) = baby_steps.get(&gamma.to_bytes().to_vec()) {
+            let exponent = i * step + j;
+            if exponent <= max_exponent {
+                return Some(exponent);
+            }
+            return None;
+        }
+        gamma += giant_step;
     }
-    yes_votes
+    None
 }
 
-/// Checks merkle proof-of-membership and that the hash of the sender matches the leaf that is proved 
+/// Checks merkle proof-of-membership and that the hash of the sender and their weight matches the leaf that is proved.
+/// Returns an error if the proof bytes cannot be parsed
 pub fn verify_merkle_proof(
     merkle_root: &[u8; 32],
     merkle_leaf_count: i32,
     merkle_proof: &MerkleProof,
     sender: &AccountAddress,
-) -> bool {
-    let proof =
-        unwrap_abort(rs_merkle::MerkleProof::<merkle_sha256>::from_bytes(&merkle_proof.proof).ok());
+    weight: u64,
+) -> Result<bool, rs_merkle::Error> {
+    let proof = rs_merkle::MerkleProof::<merkle_sha256>::from_bytes(&merkle_proof.proof)?;
 
     if proof.verify(
         *merkle_root,
@@ -99,9 +256,7 @@ pub fn verify_merkle_proof(
         &[merkle_proof.leaf],
         merkle_leaf_count as usize,
     ) {
-        let account_hash = merkle_sha256::hash(&to_bytes(sender));
-
-        return account_hash == merkle_proof.leaf;
+        return Ok(merkle_leaf(sender, weight) == merkle_proof.leaf);
     }
-    false
+    Ok(false)
 }
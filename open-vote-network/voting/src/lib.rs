@@ -5,9 +5,11 @@
 //! as defined here: http://homepages.cs.ncl.ac.uk/feng.hao/files/OpenVote_IET.pdf
 
 use concordium_std::*;
-use k256::elliptic_curve::PublicKey;
-use k256::Secp256k1;
-use util::{convert_vec_to_point, OneInTwoZKP, SchnorrProof};
+use k256::ProjectivePoint;
+use util::{
+    convert_vec_to_point, ChallengeContext, ChaumPedersenProof, ConversionError, OneInKZKP,
+    SchnorrProof,
+};
 
 pub mod crypto;
 pub mod tests;
@@ -16,15 +18,19 @@ pub mod property;
 
 // Contract structs
 
-#[derive(Serialize, SchemaType)]
+#[derive(Serialize, SchemaType, Clone)]
 pub struct VoteConfig {
     merkle_root: [u8; 32],
     merkle_leaf_count: i32,
+    max_total_weight: u64, // upper bound on the sum of the weights of all voters
     voting_question: String,
+    voting_options: Vec<String>,
     deposit: Amount,
+    beneficiary: AccountAddress, // account that can sweep the forfeited deposits, if the vote aborts
     registration_timeout: types::RegistrationTimeout,
     commit_timeout: types::CommitTimeout,
     vote_timeout: types::VoteTimeout,
+    recovery_timeout: types::RecoveryTimeout,
 }
 
 #[derive(Serialize, SchemaType)]
@@ -32,6 +38,7 @@ pub struct RegisterMessage {
     pub voting_key: Vec<u8>,          // g^x
     pub voting_key_zkp: SchnorrProof, // zkp for x
     pub merkle_proof: util::MerkleProof,
+    pub weight: u64, // weight w committed to in the merkle leaf of the voter
 }
 
 #[derive(Serialize, SchemaType)]
@@ -42,8 +49,34 @@ pub struct CommitMessage {
 
 #[derive(Serialize, SchemaType)]
 pub struct VoteMessage {
-    pub vote: Vec<u8>,         // g^y*g^xv, v = {0, 1}
-    pub vote_zkp: OneInTwoZKP, // one-in-two zkp for v
+    pub vote: Vec<u8>,       // g^y*g^xv, v = w*base^i for option i
+    pub vote_zkp: OneInKZKP, // one-in-k zkp for v
+}
+
+#[derive(Serialize, SchemaType)]
+pub struct RecoveryMessage {
+    pub recovery_key: Vec<u8>, // ĝ^x, ĝ computed from the voting keys of voters who dropped out
+    pub recovery_zkp: ChaumPedersenProof, // zkp for x
+}
+
+#[derive(Serialize, SchemaType, Clone, PartialEq, Debug)]
+pub struct Settlement {
+    pub refunds: Vec<(AccountAddress, Amount)>, // deposits refunded to voters who did their part
+    pub forfeits: Vec<(AccountAddress, Amount)>, // deposits forfeited by voters who stalled or dropped out
+    pub caller_reward: Option<(AccountAddress, Amount)>, // reward to the change_phase caller who aborted the vote
+    pub remaining: Amount, // forfeited deposits left in the contract for the beneficiary to sweep
+}
+
+// View structs
+
+#[derive(Serialize, SchemaType)]
+pub struct VotingKeysView {
+    pub voting_keys: Vec<(AccountAddress, Vec<u8>)>, // g^x of each registered voter, in the order of the voters
+}
+
+#[derive(Serialize, SchemaType)]
+pub struct VotersView {
+    pub voters: Vec<(AccountAddress, types::VoterStatus)>, // status of each voter, in the order of the voters
 }
 
 // Contract state
@@ -52,18 +85,42 @@ pub struct VoteMessage {
 pub struct VotingState<S> {
     config: VoteConfig,
     voting_phase: types::VotingPhase,
-    voting_result: (i32, i32),
+    voting_result: Vec<u64>,
     voters: StateMap<AccountAddress, Voter, S>,
+    settlement: Option<Settlement>, // None = vote has not ended
 }
 
 #[derive(Serialize, SchemaType, Clone, PartialEq, Default)]
 struct Voter {
+    weight: u64,
     voting_key: Vec<u8>,
     voting_key_zkp: SchnorrProof,
     reconstructed_key: Vec<u8>,
     commitment: Vec<u8>,
     vote: Vec<u8>,
-    vote_zkp: OneInTwoZKP,
+    vote_zkp: OneInKZKP,
+    recovery_key: Vec<u8>,
+    recovery_zkp: ChaumPedersenProof,
+}
+
+impl VoteConfig {
+    /// Number of options k a voter can choose between
+    fn number_of_options(&self) -> u32 {
+        self.voting_options.len() as u32
+    }
+
+    /// Base that option i is encoded with as g^(w*base^i). It must be larger than the total weight of the voters,
+    /// such that the weighted tally of each option can be decoded from the sum of all votes. None if it overflows
+    fn vote_base(&self) -> Option<u64> {
+        self.max_total_weight.checked_add(1)
+    }
+
+    /// Largest possible tally, reached when the full weight is voted for the last option, or None if it overflows
+    fn max_tally(&self) -> Option<u64> {
+        self.vote_base()?
+            .checked_pow(self.number_of_options().checked_sub(1)?)?
+            .checked_mul(self.max_total_weight)
+    }
 }
 
 // Contract functions
@@ -77,22 +134,51 @@ fn setup<S: HasStateApi>(
     let vote_config: VoteConfig = ctx.parameter_cursor().get()?;
 
     // Ensure config is valid
-    ensure!( // check that there is time for registration
+    // Check that there is time for registration
+    ensure!(
         vote_config.registration_timeout > ctx.metadata().slot_time(),
         types::SetupError::InvalidRegistrationTimeout
     );
-    ensure!( // check that there is time for commiting votes
+    // Check that there is time for commiting votes
+    ensure!(
         vote_config.commit_timeout > vote_config.registration_timeout,
         types::SetupError::InvalidPrecommitTimeout
     );
-    ensure!( // check that there is time for voting
+    // Check that there is time for voting
+    ensure!(
         vote_config.vote_timeout > vote_config.commit_timeout,
         types::SetupError::InvalidVoteTimeout
     );
-    ensure!( // check that there is at least 0 deposited
+    // Check that there is time for recovering from voters dropping out
+    ensure!(
+        vote_config.recovery_timeout > vote_config.vote_timeout,
+        types::SetupError::InvalidRecoveryTimeout
+    );
+    // Check that there is at least 0 deposited
+    ensure!(
         vote_config.deposit >= Amount::zero(),
         types::SetupError::NegativeDeposit
     );
+    // Check that every voter can have a weight of at least 1
+    ensure!(
+        vote_config.max_total_weight >= vote_config.merkle_leaf_count as u64,
+        types::SetupError::InvalidMaxTotalWeight
+    );
+    // Check that the base the options are encoded with does not overflow
+    ensure!(
+        vote_config.vote_base().is_some(),
+        types::SetupError::InvalidMaxTotalWeight
+    );
+    // Check that there are at least 2 options
+    ensure!(
+        vote_config.number_of_options() > 1,
+        types::SetupError::InvalidNumberOfOptions
+    );
+    // Check that the largest possible tally is small enough for result to find it on-chain
+    ensure!(
+        matches!(vote_config.max_tally(), Some(max_tally) if max_tally <= crypto::MAX_TALLY),
+        types::SetupError::TallyTooLarge
+    );
 
     // Allow only >2 voters
     //ensure!(
@@ -104,8 +190,9 @@ fn setup<S: HasStateApi>(
     let state = VotingState {
         config: vote_config,
         voting_phase: types::VotingPhase::Registration,
-        voting_result: (-1, -1), // -1 = no result yet
+        voting_result: Vec::new(), // empty = no result yet
         voters: state_builder.new_map(),
+        settlement: None,
     };
 
     // Return success with initial voting state
@@ -139,15 +226,35 @@ fn register<S: HasStateApi>(
         types::RegisterError::NotRegistrationPhase
     );
 
-    // Check voter is authorized through verifying merkle proof-of-membership
+    // Check voter is authorized with their weight through verifying merkle proof-of-membership
+    let authorized = match crypto::verify_merkle_proof(
+        &host.state().config.merkle_root,
+        host.state().config.merkle_leaf_count,
+        &register_message.merkle_proof,
+        &sender_address,
+        register_message.weight,
+    ) {
+        Ok(authorized) => authorized,
+        Err(_) => bail!(types::RegisterError::InvalidMerkleProof),
+    };
+    ensure!(authorized, types::RegisterError::UnauthorizedVoter);
+
+    // Check that the weight can be tallied, i.e. is positive and the total weight stays within the maximum
     ensure!(
-        crypto::verify_merkle_proof(
-            &host.state().config.merkle_root,
-            host.state().config.merkle_leaf_count,
-            &register_message.merkle_proof,
-            &sender_address
+        register_message.weight > 0,
+        types::RegisterError::InvalidWeight
+    );
+    let registered_weight = host
+        .state()
+        .voters
+        .iter()
+        .fold(0, |sum, (_, v)| sum + v.weight);
+    ensure!(
+        matches!(
+            registered_weight.checked_add(register_message.weight),
+            Some(total) if total <= host.state().config.max_total_weight
         ),
-        types::RegisterError::UnauthorizedVoter
+        types::RegisterError::MaxTotalWeightExceeded
     );
 
     // Check correct amount is paid
@@ -177,19 +284,25 @@ fn register<S: HasStateApi>(
         let mut voter = util::unwrap_abort(host.state_mut().voters.get_mut(&sender_address));
 
         // Check voting key (g^x) is valid point on curve, by attempting to convert
-        match PublicKey::<Secp256k1>::from_sec1_bytes(&register_message.voting_key) {
+        let voting_key = match convert_vec_to_point(&register_message.voting_key) {
             Ok(p) => p,
             Err(_) => bail!(types::RegisterError::InvalidVotingKey),
         };
 
-        // Check validity of ZKP
+        // Check validity of ZKP, bound to this contract, the sender and the registration phase
         let zkp: SchnorrProof = register_message.voting_key_zkp.clone();
+        let context = ChallengeContext::new(
+            ctx.self_address(),
+            sender_address,
+            &types::VotingPhase::Registration,
+        );
         ensure!(
-            crypto::verify_schnorr_zkp(convert_vec_to_point(&register_message.voting_key), zkp),
+            crypto::verify_schnorr_zkp(voting_key, zkp, &context),
             types::RegisterError::InvalidZKP
         );
 
-        // Add register message to correct voter (i.e. voting key and zkp)
+        // Add register message to correct voter (i.e. weight, voting key and zkp)
+        voter.weight = register_message.weight;
         voter.voting_key = register_message.voting_key;
         voter.voting_key_zkp = register_message.voting_key_zkp;
     }
@@ -230,6 +343,11 @@ fn commit<S: HasStateApi>(
         commitment_message.reconstructed_key != Vec::<u8>::new(),
         types::CommitError::InvalidCommitMessage
     );
+    // Check reconstructed key (g^y) is valid point on curve, by attempting to convert
+    ensure!(
+        convert_vec_to_point(&commitment_message.reconstructed_key).is_ok(),
+        types::CommitError::InvalidReconstructedKey
+    );
     ensure!(
         host.state().voting_phase == types::VotingPhase::Commit,
         types::CommitError::NotCommitPhase
@@ -266,7 +384,7 @@ fn commit<S: HasStateApi>(
     Ok(())
 }
 
-/// VOTE PHASE: function voters call to send their encrypted vote along with a one-in-two ZKP
+/// VOTE PHASE: function voters call to send their encrypted vote along with a one-in-k ZKP
 #[receive(contract = "voting", name = "vote", parameter = "VoteMessage", mutable)]
 fn vote<S: HasStateApi>(
     ctx: &impl HasReceiveContext,
@@ -293,27 +411,50 @@ fn vote<S: HasStateApi>(
         types::VoteError::PhaseEnded
     );
 
+    let number_of_options = host.state().config.number_of_options();
+    // Setup rejects configs where the base overflows
+    let base = util::unwrap_abort(host.state().config.vote_base());
+    let context = ChallengeContext::new(
+        ctx.self_address(),
+        sender_address,
+        &types::VotingPhase::Vote,
+    );
+
     // Get voter
     match host.state_mut().voters.get_mut(&sender_address) {
         Some(mut v) => {
             // Ensure that voters cannot change their vote (cannot call vote function multiple times)
             ensure!(v.vote == Vec::<u8>::new(), types::VoteError::AlreadyVoted);
 
-            // Verify one-in-two ZKP
+            let reconstructed_key = match convert_vec_to_point(&v.reconstructed_key) {
+                Ok(p) => p,
+                Err(_) => bail!(types::VoteError::InvalidReconstructedKey),
+            };
+            let vote = match convert_vec_to_point(&vote_message.vote) {
+                Ok(p) => p,
+                Err(_) => bail!(types::VoteError::InvalidVote),
+            };
+            // Register only stores voting keys that decode
+            let voting_key = util::unwrap_abort(convert_vec_to_point(&v.voting_key).ok());
+
+            // Verify one-in-k ZKP for the submitted vote under the voter's own voting key
             ensure!(
-                crypto::verify_one_in_two_zkp(
+                crypto::verify_one_in_k_zkp(
                     vote_message.vote_zkp.clone(),
-                    convert_vec_to_point(&v.reconstructed_key)
+                    voting_key,
+                    vote,
+                    reconstructed_key,
+                    number_of_options,
+                    base,
+                    v.weight,
+                    &context
                 ),
                 types::VoteError::InvalidZKP
             );
 
             // Check commitment matches vote
             ensure!(
-                crypto::check_commitment(
-                    convert_vec_to_point(&vote_message.vote),
-                    v.commitment.clone()
-                ),
+                crypto::check_commitment(vote, v.commitment.clone()),
                 types::VoteError::VoteCommitmentMismatch
             );
 
@@ -324,28 +465,118 @@ fn vote<S: HasStateApi>(
         None => bail!(types::VoteError::VoterNotFound),
     };
 
-    // Check all voters have voted and automatically move to next phase if so
+    // Check all voters have voted and automatically move to next phase if so, refunding their deposits
     if host
         .state()
         .voters
         .iter()
         .all(|(_, v)| v.vote != Vec::<u8>::new())
     {
+        settle_deposits(None, host)?;
         host.state_mut().voting_phase = types::VotingPhase::Result;
     }
 
-    // Refund deposit to sender address (they have voted and their job is done)
-    host.invoke_transfer(&sender_address, host.state().config.deposit)?;
+    Ok(())
+}
+
+/// RECOVERY PHASE: function voters who voted call to cancel out the voting keys of the voters who dropped out,
+/// by sending ĝ^x along with a ZKP that it uses the same x as their voting key
+#[receive(
+    contract = "voting",
+    name = "recover",
+    parameter = "RecoveryMessage",
+    mutable
+)]
+fn recover<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VotingState<S>, StateApiType = S>,
+) -> Result<(), types::RecoveryError> {
+    let recovery_message: RecoveryMessage = ctx.parameter_cursor().get()?;
+
+    // Get sender address and bail if its another smart contract
+    let sender_address = match ctx.sender() {
+        Address::Contract(_) => bail!(types::RecoveryError::ContractSender),
+        Address::Account(account_address) => account_address,
+    };
+
+    ensure!(
+        host.state().voting_phase == types::VotingPhase::Recovery,
+        types::RecoveryError::NotRecoveryPhase
+    );
+    ensure!(
+        ctx.metadata().slot_time() <= host.state().config.recovery_timeout,
+        types::RecoveryError::PhaseEnded
+    );
+
+    // Only voters who voted take part in the recovery
+    let voter = match host.state().voters.get(&sender_address) {
+        Some(v) if v.vote != Vec::<u8>::new() => v.clone(),
+        _ => bail!(types::RecoveryError::UnauthorizedVoter),
+    };
+    ensure!(
+        voter.recovery_key == Vec::<u8>::new(),
+        types::RecoveryError::AlreadyRecovered
+    );
+
+    // Check recovery key (ĝ^x) is valid point on curve, by attempting to convert
+    let recovery_key = match convert_vec_to_point(&recovery_message.recovery_key) {
+        Ok(p) => p,
+        Err(_) => bail!(types::RecoveryError::InvalidRecoveryKey),
+    };
+
+    let voting_key = match convert_vec_to_point(&voter.voting_key) {
+        Ok(p) => p,
+        Err(_) => bail!(types::RecoveryError::InvalidVotingKey),
+    };
+    let recovery_base = match compute_recovery_base(host.state(), &sender_address) {
+        Ok(p) => p,
+        Err(_) => bail!(types::RecoveryError::InvalidVotingKey),
+    };
+
+    // Check validity of ZKP, bound to this contract, the sender and the recovery phase
+    let context = ChallengeContext::new(
+        ctx.self_address(),
+        sender_address,
+        &types::VotingPhase::Recovery,
+    );
+    ensure!(
+        crypto::verify_chaum_pedersen_zkp(
+            voting_key,
+            recovery_base,
+            recovery_key,
+            recovery_message.recovery_zkp.clone(),
+            &context
+        ),
+        types::RecoveryError::InvalidZKP
+    );
+
+    // Save recovery key and zkp in voter state
+    if let Some(mut v) = host.state_mut().voters.get_mut(&sender_address) {
+        v.recovery_key = recovery_message.recovery_key;
+        v.recovery_zkp = recovery_message.recovery_zkp;
+    }
+
+    // Check if all voters who voted have sent their recovery key and automatically move to next phase if so,
+    // refunding their deposits and forfeiting the deposits of the voters who dropped out
+    if host
+        .state()
+        .voters
+        .iter()
+        .all(|(_, v)| v.vote == Vec::<u8>::new() || v.recovery_key != Vec::<u8>::new())
+    {
+        settle_deposits(None, host)?;
+        host.state_mut().voting_phase = types::VotingPhase::Result;
+    }
 
     Ok(())
 }
 
-/// RESULT PHASE: function anyone can call to compute tally if vote is over
+/// RESULT PHASE: function anyone can call to compute the weighted tally of each option if vote is over
 #[receive(contract = "voting", name = "result", mutable)]
 fn result<S: HasStateApi>(
     _ctx: &impl HasReceiveContext,
     host: &mut impl HasHost<VotingState<S>, StateApiType = S>,
-) -> Result<(i32, i32), types::ResultError> {
+) -> Result<Vec<u64>, types::ResultError> {
     let mut state = host.state_mut();
 
     ensure!(
@@ -353,25 +584,47 @@ fn result<S: HasStateApi>(
         types::ResultError::NotResultPhase
     );
 
-    // Create list of all votes
-    let mut votes = Vec::new();
-    votes.extend(
-        state
-            .voters
-            .iter()
-            .map(|(_, v)| convert_vec_to_point(&v.vote)),
-    );
-
-    // Brute force the tally (number of yes votes)
-    let yes_votes = crypto::brute_force_tally(votes.clone());
+    // Create list of all votes, where the recovery key cancels out the voting keys of voters who dropped out
+    let votes = match state
+        .voters
+        .iter()
+        .filter(|(_, v)| v.vote != Vec::<u8>::new())
+        .map(|(_, v)| {
+            if v.recovery_key != Vec::<u8>::new() {
+                Ok(convert_vec_to_point(&v.vote)? - convert_vec_to_point(&v.recovery_key)?)
+            } else {
+                convert_vec_to_point(&v.vote)
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(votes) => votes,
+        Err(_) => bail!(types::ResultError::InvalidVote),
+    };
 
-    // Calc no votes
-    let no_votes = votes.len() as i32 - yes_votes;
+    // Total weight of the voters who voted, which the tally sums to
+    let total_weight = state
+        .voters
+        .iter()
+        .filter(|(_, v)| v.vote != Vec::<u8>::new())
+        .fold(0, |sum, (_, v)| sum + v.weight);
+
+    // Brute force the tally (total weight voted for each option)
+    let option_votes = match crypto::brute_force_tally(
+        votes,
+        state.config.number_of_options(),
+        // Setup rejects configs where the base overflows
+        util::unwrap_abort(state.config.vote_base()),
+        total_weight,
+    ) {
+        Some(option_votes) => option_votes,
+        None => bail!(types::ResultError::TallyNotFound),
+    };
 
     // Set voting result in public state
-    state.voting_result = (yes_votes, no_votes);
+    state.voting_result = option_votes.clone();
 
-    Ok((yes_votes, no_votes))
+    Ok(option_votes)
 }
 
 /// CHANGE PHASE: function anyone can call to change voting phase if conditions are met
@@ -403,20 +656,32 @@ fn change_phase<S: HasStateApi>(
             }
             // Change to abort if <3 voters have registered and time is over
             else if now > host.state().config.registration_timeout {
-                refund_deposits(sender_address, host)?;
+                settle_deposits(Some(sender_address), host)?;
                 host.state_mut().voting_phase = types::VotingPhase::Abort
             }
         }
         types::VotingPhase::Commit => {
-            // Change to vote phase, if all voters have committed
-            if host.state().voters.iter().all(|(_, v)| {
-                v.reconstructed_key != Vec::<u8>::new() && v.commitment != Vec::<u8>::new()
-            }) {
+            let committed_voters = host
+                .state()
+                .voters
+                .iter()
+                .filter(|(_, v)| {
+                    v.reconstructed_key != Vec::<u8>::new() && v.commitment != Vec::<u8>::new()
+                })
+                .count();
+            let registered_voters = host.state().voters.iter().count();
+
+            // Change to vote phase, if all voters have committed, or if commit time is over and enough voters have
+            // committed to recover from the rest dropping out
+            if committed_voters == registered_voters
+                || (now > host.state().config.commit_timeout
+                    && can_recover(committed_voters, registered_voters))
+            {
                 host.state_mut().voting_phase = types::VotingPhase::Vote
             }
             // Change to abort if all have not committed and commit time is over
             else if now > host.state().config.commit_timeout {
-                refund_deposits(sender_address, host)?;
+                settle_deposits(Some(sender_address), host)?;
                 host.state_mut().voting_phase = types::VotingPhase::Abort
             }
         }
@@ -428,11 +693,42 @@ fn change_phase<S: HasStateApi>(
                 .iter()
                 .all(|(_, v)| v.vote != Vec::<u8>::new())
             {
+                settle_deposits(None, host)?;
                 host.state_mut().voting_phase = types::VotingPhase::Result
             }
+            // Change to recovery phase if vote time is over and enough voters have voted to recover from the rest dropping out
+            else if now > host.state().config.vote_timeout
+                && can_recover(
+                    host.state()
+                        .voters
+                        .iter()
+                        .filter(|(_, v)| v.vote != Vec::<u8>::new())
+                        .count(),
+                    host.state().voters.iter().count(),
+                )
+            {
+                host.state_mut().voting_phase = types::VotingPhase::Recovery
+            }
             // Change to abort if vote time is over and not all have voted
             else if now > host.state().config.vote_timeout {
-                refund_deposits(sender_address, host)?;
+                settle_deposits(Some(sender_address), host)?;
+                host.state_mut().voting_phase = types::VotingPhase::Abort
+            }
+        }
+        types::VotingPhase::Recovery => {
+            // Change to result phase, if all voters who voted have sent their recovery key
+            if host
+                .state()
+                .voters
+                .iter()
+                .all(|(_, v)| v.vote == Vec::<u8>::new() || v.recovery_key != Vec::<u8>::new())
+            {
+                settle_deposits(None, host)?;
+                host.state_mut().voting_phase = types::VotingPhase::Result
+            }
+            // Change to abort if recovery time is over and not all have sent their recovery key
+            else if now > host.state().config.recovery_timeout {
+                settle_deposits(Some(sender_address), host)?;
                 host.state_mut().voting_phase = types::VotingPhase::Abort
             }
         }
@@ -441,102 +737,239 @@ fn change_phase<S: HasStateApi>(
     Ok(())
 }
 
-/// Function to refund deposits, in case of the vote aborting. It penalizes stalling/malicious voters, refunds honest and rewards the change_phase caller who found out that we needed to abort
-fn refund_deposits<S: HasStateApi>(
-    sender: AccountAddress,
+/// VIEW: function anyone can call to get the current voting phase
+#[receive(
+    contract = "voting",
+    name = "view_phase",
+    return_value = "types::VotingPhase"
+)]
+fn view_phase<S: HasStateApi>(
+    _ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VotingState<S>, StateApiType = S>,
+) -> ReceiveResult<types::VotingPhase> {
+    Ok(host.state().voting_phase)
+}
+
+/// VIEW: function anyone can call to get the voting config
+#[receive(contract = "voting", name = "view_config", return_value = "VoteConfig")]
+fn view_config<S: HasStateApi>(
+    _ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VotingState<S>, StateApiType = S>,
+) -> ReceiveResult<VoteConfig> {
+    Ok(host.state().config.clone())
+}
+
+/// VIEW: function voters call to get the voting keys (g^x) of all registered voters, such that they can
+/// compute their reconstructed key (g^y). The keys are in the order of the voters (by account address),
+/// which is the order the reconstructed keys must be computed in
+#[receive(
+    contract = "voting",
+    name = "view_voting_keys",
+    return_value = "VotingKeysView"
+)]
+fn view_voting_keys<S: HasStateApi>(
+    _ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VotingState<S>, StateApiType = S>,
+) -> ReceiveResult<VotingKeysView> {
+    let voting_keys = host
+        .state()
+        .voters
+        .iter()
+        .filter(|(_, v)| v.voting_key != Vec::<u8>::new())
+        .map(|(addr, v)| (*addr, v.voting_key.clone()))
+        .collect();
+
+    Ok(VotingKeysView { voting_keys })
+}
+
+/// VIEW: function anyone can call to get how far each voter has come in the vote, in the order of the voters
+#[receive(contract = "voting", name = "view_voters", return_value = "VotersView")]
+fn view_voters<S: HasStateApi>(
+    _ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VotingState<S>, StateApiType = S>,
+) -> ReceiveResult<VotersView> {
+    let voters = host
+        .state()
+        .voters
+        .iter()
+        .map(|(addr, v)| {
+            let status = if v.recovery_key != Vec::<u8>::new() {
+                types::VoterStatus::Recovered
+            } else if v.vote != Vec::<u8>::new() {
+                types::VoterStatus::Voted
+            } else if v.commitment != Vec::<u8>::new() {
+                types::VoterStatus::Committed
+            } else {
+                types::VoterStatus::Registered
+            };
+            (*addr, status)
+        })
+        .collect();
+
+    Ok(VotersView { voters })
+}
+
+/// VIEW: function anyone can call to get the final result, i.e. the total weight voted for each option.
+/// The result is empty until it has been computed by calling result
+#[receive(contract = "voting", name = "view_result", return_value = "Vec<u64>")]
+fn view_result<S: HasStateApi>(
+    _ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VotingState<S>, StateApiType = S>,
+) -> ReceiveResult<Vec<u64>> {
+    Ok(host.state().voting_result.clone())
+}
+
+/// VIEW: function anyone can call to get how the deposits were settled. It is None until the vote has ended
+#[receive(
+    contract = "voting",
+    name = "view_settlement",
+    return_value = "Option<Settlement>"
+)]
+fn view_settlement<S: HasStateApi>(
+    _ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VotingState<S>, StateApiType = S>,
+) -> ReceiveResult<Option<Settlement>> {
+    Ok(host.state().settlement.clone())
+}
+
+/// RESULT/ABORT PHASE: function the beneficiary calls to collect the forfeited deposits remaining after the settlement
+#[receive(contract = "voting", name = "sweep", mutable)]
+fn sweep<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
     host: &mut impl HasHost<VotingState<S>, StateApiType = S>,
-) -> Result<(), TransferError> {
-    // Number of voters registered for the vote
-    let number_of_voters = host.state().voters.iter().count() as u64;
+) -> Result<(), types::SweepError> {
+    // Get sender address and bail if its another smart contract
+    let sender_address = match ctx.sender() {
+        Address::Contract(_) => bail!(types::SweepError::ContractSender),
+        Address::Account(account_address) => account_address,
+    };
 
-    // Get account list of the voters who stalled the vote
-    let stalling_accounts: Vec<AccountAddress> = match host.state().voting_phase {
-        types::VotingPhase::Registration => {
-            let mut stalling_accounts = Vec::<AccountAddress>::new();
-            for (addr, voter) in host.state().voters.iter() {
-                if voter.voting_key == Vec::<u8>::new() {
-                    stalling_accounts.push(*addr);
-                }
-            }
-            stalling_accounts
-        }
-        types::VotingPhase::Commit => {
-            let mut stalling_accounts = Vec::<AccountAddress>::new();
-            for (addr, voter) in host.state().voters.iter() {
-                if voter.reconstructed_key == Vec::<u8>::new() {
-                    stalling_accounts.push(*addr);
-                }
-            }
-            stalling_accounts
+    ensure!(
+        sender_address == host.state().config.beneficiary,
+        types::SweepError::UnauthorizedBeneficiary
+    );
+
+    let remaining = match &host.state().settlement {
+        Some(settlement) => settlement.remaining,
+        None => bail!(types::SweepError::NotSettled),
+    };
+    ensure!(
+        remaining > Amount::zero(),
+        types::SweepError::NothingToSweep
+    );
+
+    host.invoke_transfer(&sender_address, remaining)?;
+
+    if let Some(settlement) = host.state_mut().settlement.as_mut() {
+        settlement.remaining = Amount::zero();
+    }
+
+    Ok(())
+}
+
+/// Check if enough voters participate to finish the vote through a recovery round, i.e. atleast 3 and a majority
+fn can_recover(participating_voters: usize, registered_voters: usize) -> bool {
+    participating_voters > 2 && 2 * participating_voters > registered_voters
+}
+
+/// Compute the recovery base ĝ of a voter from the voting keys of the voters who dropped out (did not vote),
+/// adding the keys before the voter and subtracting the keys after.
+/// Voters are ordered as in the voters map, which must be the order the reconstructed keys were computed in
+fn compute_recovery_base<S: HasStateApi>(
+    state: &VotingState<S>,
+    voter: &AccountAddress,
+) -> Result<ProjectivePoint, ConversionError> {
+    let mut recovery_base = ProjectivePoint::IDENTITY;
+    let mut before_voter = true;
+
+    for (addr, v) in state.voters.iter() {
+        if *addr == *voter {
+            before_voter = false;
+            continue;
         }
-        types::VotingPhase::Vote => {
-            let mut stalling_accounts = Vec::<AccountAddress>::new();
-            for (addr, voter) in host.state().voters.iter() {
-                if voter.vote == Vec::<u8>::new() {
-                    stalling_accounts.push(*addr);
-                }
+        if v.vote == Vec::<u8>::new() {
+            if before_voter {
+                recovery_base += convert_vec_to_point(&v.voting_key)?;
+            } else {
+                recovery_base -= convert_vec_to_point(&v.voting_key)?;
             }
-            stalling_accounts
         }
-        // Impossible case
-        _ => trap(),
+    }
+    Ok(recovery_base)
+}
+
+/// Compute how the deposits are settled when the vote ends, in the result phase or by aborting. Voters who did
+/// their part in the phase the vote ends from get their deposit refunded, while stalling voters and voters who
+/// dropped out forfeit theirs. In a recovery round, voters must have sent their recovery key to have done their part.
+/// The change_phase caller who found out that we needed to abort is rewarded one forfeited deposit, if they did not
+/// forfeit themselves. There is no caller to reward when the vote reaches the result
+fn compute_settlement<S: HasStateApi>(
+    state: &VotingState<S>,
+    caller: Option<AccountAddress>,
+) -> Settlement {
+    let deposit = state.config.deposit;
+    let mut settlement = Settlement {
+        refunds: Vec::new(),
+        forfeits: Vec::new(),
+        caller_reward: None,
+        remaining: Amount::zero(),
     };
 
-    // Get account list of the honest voters
-    let honest_accounts: Vec<AccountAddress> = match host.state().voting_phase {
-        types::VotingPhase::Registration => {
-            let mut honest_accounts = Vec::<AccountAddress>::new();
-            for (addr, voter) in host.state().voters.iter() {
-                if voter.voting_key != Vec::<u8>::new() {
-                    honest_accounts.push(*addr);
-                }
-            }
-            honest_accounts
-        }
-        types::VotingPhase::Commit => {
-            let mut honest_accounts = Vec::<AccountAddress>::new();
-            for (addr, voter) in host.state().voters.iter() {
-                if voter.reconstructed_key != Vec::<u8>::new() {
-                    honest_accounts.push(*addr);
-                }
+    for (addr, voter) in state.voters.iter() {
+        // Whether the voter did their part in the phase the vote ends from
+        let honest = match state.voting_phase {
+            types::VotingPhase::Registration => voter.voting_key != Vec::<u8>::new(),
+            types::VotingPhase::Commit => {
+                voter.reconstructed_key != Vec::<u8>::new() && voter.commitment != Vec::<u8>::new()
             }
-            honest_accounts
-        }
-        types::VotingPhase::Vote => {
-            let mut honest_accounts = Vec::<AccountAddress>::new();
-            for (addr, voter) in host.state().voters.iter() {
-                if voter.vote != Vec::<u8>::new() {
-                    honest_accounts.push(*addr);
-                }
+            types::VotingPhase::Vote => voter.vote != Vec::<u8>::new(),
+            types::VotingPhase::Recovery => {
+                voter.vote != Vec::<u8>::new() && voter.recovery_key != Vec::<u8>::new()
             }
-            honest_accounts
-        }
-        // Impossible case
-        _ => trap(),
-    };
+            // Impossible case
+            _ => trap(),
+        };
 
-    // Reward sender (caller of change_phase) if they are not a stalling voter
-    // and there were honest voters
-    if !stalling_accounts.contains(&sender) && number_of_voters - honest_accounts.len() as u64 > 0 {
-        host.invoke_transfer(&sender, host.state().config.deposit)?;
+        if honest {
+            settlement.refunds.push((*addr, deposit));
+        } else {
+            settlement.forfeits.push((*addr, deposit));
+        }
     }
 
-    // Go through all honest voters and refund their deposit
-    for account in honest_accounts {
-        host.invoke_transfer(&account, host.state().config.deposit)?;
+    let forfeited = deposit * settlement.forfeits.len() as u64;
+
+    // Reward caller if they are not a stalling voter and there were stalling voters
+    match caller {
+        Some(caller)
+            if !settlement.forfeits.is_empty()
+                && !settlement.forfeits.iter().any(|(addr, _)| *addr == caller) =>
+        {
+            settlement.caller_reward = Some((caller, deposit));
+            settlement.remaining = forfeited - deposit;
+        }
+        _ => settlement.remaining = forfeited,
     }
 
+    settlement
+}
 
+/// Function to settle deposits when the vote ends, before changing to the result or abort phase. It pays out the
+/// refunds and caller reward of the settlement and stores it, such that the remaining forfeited deposits can be
+/// swept by the beneficiary
+fn settle_deposits<S: HasStateApi>(
+    caller: Option<AccountAddress>,
+    host: &mut impl HasHost<VotingState<S>, StateApiType = S>,
+) -> Result<(), TransferError> {
+    let settlement = compute_settlement(host.state(), caller);
 
-    // Do this instead to fix the bug
-   /*  
-   if host.state().voting_phase != types::VotingPhase::Vote {
-        for addr in honest_accounts {
-            host.invoke_transfer(&addr, host.state().config.deposit)?;
-        }
+    for (addr, amount) in settlement.refunds.iter() {
+        host.invoke_transfer(addr, *amount)?;
+    }
+    if let Some((addr, amount)) = settlement.caller_reward {
+        host.invoke_transfer(&addr, amount)?;
     }
-    */
-
 
+    host.state_mut().settlement = Some(settlement);
     Ok(())
 }
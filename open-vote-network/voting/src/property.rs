@@ -57,7 +57,7 @@ mod tests {
         voted: bool,
     }
 
-    #[derive(Clone, Debug, Copy)]
+    #[derive(Clone, Debug, Copy, PartialEq)]
     pub enum Funcs {
         Register,
         Commit,
@@ -79,27 +79,58 @@ mod tests {
             let func = *func_vec.get(random_index).unwrap();
             func
         }
+
+        // Shrink towards the functions called earlier in a vote
+        fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+            let func = *self;
+            Box::new(Funcs::make_vec().into_iter().take_while(move |f| *f != func))
+        }
     }
 
-    pub struct Commands(Vec<Funcs>);
+    // A step of a trace: an account calls a function after time has advanced
+    #[derive(Clone, Debug, Copy)]
+    pub struct Step {
+        account: usize,
+        func: Funcs,
+        advance: u64,
+    }
 
-    impl Clone for Commands {
-        fn clone(&self) -> Self {
-            Commands(self.0.clone())
+    impl Arbitrary for Step {
+        fn arbitrary(g: &mut Gen) -> Self {
+            Step {
+                account: usize::arbitrary(g),
+                func: Funcs::arbitrary(g),
+                advance: u64::arbitrary(g) % 21, // advance time by 0-20 ms
+            }
+        }
+
+        // Shrink towards the first account calling the functions of earlier phases earlier
+        fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+            let step = *self;
+            Box::new(
+                self.account.shrink().map(move |account| Step { account, ..step })
+                    .chain(self.func.shrink().map(move |func| Step { func, ..step }))
+                    .chain(self.advance.shrink().map(move |advance| Step { advance, ..step })),
+            )
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct Commands(Vec<Step>);
+
     impl Arbitrary for Commands {
         fn arbitrary(g: &mut Gen) -> Self {
-            let commands = vec![];
-            for _ in 1..4 {
-                let func_vec = Funcs::make_vec();
-                let random_index = (u64::arbitrary(g) as usize) % func_vec.len();
-                let func: Funcs = *func_vec.get(random_index).unwrap();
-                commands.clone().push(func);
+            let mut commands = vec![];
+            for _ in 0..g.size() {
+                commands.push(Step::arbitrary(g));
             }
             return Commands(commands);
         }
+
+        // Shrink by removing steps and shrinking the remaining ones
+        fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+            Box::new(self.0.shrink().map(Commands))
+        }
     }
 
     #[quickcheck]
@@ -136,6 +167,10 @@ mod tests {
             reconstructed_keys.push(off_chain::compute_reconstructed_key(&keys, *g_x));
         }
 
+        // Every account has weight 1 and votes for the first option, encoded as g^(1*base^0)
+        let base = number_of_accounts as u64 + 1;
+        let g_v = ProjectivePoint::GENERATOR * util::encode_vote_option(0, base, 1);
+
         // Create map to keep track of behavior of random accounts
         let mut behavior_map = make_behavior_map(accounts.clone());
 
@@ -162,6 +197,7 @@ mod tests {
                 let mut ctx = TestReceiveContext::empty();
                 ctx.metadata_mut().set_slot_time(Timestamp::from_timestamp_millis(1));
                 ctx.set_sender(cur_addr_acc);
+                ctx.set_self_address(CONTRACT_ADDRESS);
                 host.set_self_balance(Amount::from_micro_ccd(u64::MAX));
                 let func_to_call = func_list.get(func_call_index as usize);
                 if func_to_call.is_none() { continue }
@@ -170,8 +206,9 @@ mod tests {
                         let (x, g_x) = voting_key_pairs.get(account_index).unwrap();
                         let register_message = to_bytes(&RegisterMessage {
                             voting_key: g_x.to_bytes().to_vec(),
-                            voting_key_zkp: off_chain::create_schnorr_zkp(*g_x, *x),
-                            merkle_proof: off_chain::create_merkle_proof(*cur_acc, &merkle_tree),
+                            voting_key_zkp: off_chain::create_schnorr_zkp(*g_x, *x, &challenge_context(*cur_acc, VotingPhase::Registration)),
+                            merkle_proof: off_chain::create_merkle_proof(*cur_acc, 1, &merkle_tree),
+                            weight: 1,
                         });
 
                         ctx.set_parameter(&register_message);
@@ -183,7 +220,7 @@ mod tests {
                     Funcs::Commit => {
                         let g_y = reconstructed_keys.get(account_index).unwrap();
                         let (x, _) = voting_key_pairs.get(account_index).unwrap();
-                        let commitment = off_chain::commit_to_vote(&x, &g_y, ProjectivePoint::GENERATOR);
+                        let commitment = off_chain::commit_to_vote(&x, &g_y, g_v);
 
                         //println!("asdflkasdf {:?}", ProjectivePoint::GENERATOR);
 
@@ -208,14 +245,14 @@ mod tests {
                         //let (x, g_x) = off_chain::create_votingkey_pair();
                         //let g_y = off_chain::compute_reconstructed_key(&vec![g_x.clone()], g_x.clone());
 
-                        let one_in_two_zkp_account1 =
-                            off_chain::create_one_in_two_zkp_no(*g_x, g_y.clone(), x.clone());
+                        let one_in_k_zkp_account1 =
+                            off_chain::create_one_in_k_zkp(*g_x, g_y.clone(), x.clone(), 0, 2, base, 1, &challenge_context(*cur_acc, VotingPhase::Vote));
 
                         let vote_message = &VoteMessage {
-                            vote: ((g_y.clone() * x.clone()) + ProjectivePoint::GENERATOR)
+                            vote: ((g_y.clone() * x.clone()) + g_v)
                                 .to_bytes()
                                 .to_vec(),
-                            vote_zkp: one_in_two_zkp_account1,
+                            vote_zkp: one_in_k_zkp_account1,
                         };
 
                         let vote_message_bytes = to_bytes(vote_message);
@@ -250,6 +287,7 @@ mod tests {
 
         let change_phase_caller = accounts.first().unwrap();
         let mut phase_caller_is_honest = false;
+        let mut recovery_timed_out = false;
         if cur_phase != VotingPhase::Result || cur_phase != VotingPhase::Abort {
             let mut state_builder = TestStateBuilder::new();
             let (mut ctx, _) =
@@ -266,11 +304,17 @@ mod tests {
             ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(301));
             let _ = change_phase(&ctx, &mut host);
             let _ = change_phase(&ctx, &mut host);
+            // No account sends a recovery key, so let a recovery round time out. Then no one did their part in the
+            // recovery phase, so every voter forfeits their deposit
+            recovery_timed_out = host.state().voting_phase == VotingPhase::Recovery;
+            ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(401));
+            let _ = change_phase(&ctx, &mut host);
             //println!("1 Phase: {:?}", cur_phase);
             //println!("2 Phase: {:?}", cur_phase);
             //println!("3 Phase: {:?}", cur_phase);
             //println!("Result: {:?}", result);
-            phase_caller_is_honest = behavior_map.clone().get(change_phase_caller).unwrap().clone().voted;
+            phase_caller_is_honest =
+                !recovery_timed_out && behavior_map.clone().get(change_phase_caller).unwrap().clone().voted;
         }
 
         //println!("Phase: {:?}", cur_phase);
@@ -292,7 +336,7 @@ mod tests {
             return temp;
         });
         let honest_accounts = accounts.clone().into_iter().filter(
-            |acc| behavior_map.clone().get_mut(acc).unwrap().voted
+            |acc| !recovery_timed_out && behavior_map.clone().get_mut(acc).unwrap().voted
         );
 
         // for acc in behavior_map.clone() {
@@ -308,8 +352,9 @@ mod tests {
 
 
         // Assertions about the net gain of "some_acc"
-        if phase_caller_is_honest {
-            // The contract will refund (=deposit) and reward change_phase_caller for calling change_phase (=deposit)
+        // If the vote reached the result, change_phase_caller is only refunded like the other honest accounts
+        if phase_caller_is_honest && cur_phase == VotingPhase::Abort {
+            // The contract will refund (=deposit) and reward change_phase_caller for aborting the vote (=deposit)
             // So we expect that the contract has transfered a total of 2*deposit to change_phase_caller
             assert_eq!(*transfer_map.get(change_phase_caller).unwrap(), 2*deposit);
             transfer_map.insert(*change_phase_caller, deposit); // Set the value for the next assert
@@ -321,8 +366,377 @@ mod tests {
     }
 
 
-}
 
+    // Make a receive context for the sender at the given time
+    fn make_ctx<'a>(sender: AccountAddress, time: u64) -> TestReceiveContext<'a> {
+        let mut ctx = TestReceiveContext::empty();
+        ctx.metadata_mut().set_slot_time(Timestamp::from_timestamp_millis(time));
+        ctx.set_sender(Address::Account(sender));
+        ctx.set_self_address(CONTRACT_ADDRESS);
+        ctx
+    }
+
+    // Every account goes as far in the vote as its progress says (0 = nothing, 1 = register, 2 = commit, 3 = vote),
+    // and no one recovers. Whatever phase the vote ends in, the deposits paid into the contract must equal
+    // what was transferred out plus the balance, and once the vote has ended the balance must be exactly what the
+    // settlement leaves for the beneficiary to sweep
+    #[quickcheck]
+    fn test_prop_balance_conservation(ran_deposit: u8, progress: Vec<u8>, ran_caller: u8) -> bool {
+        let deposit = Amount::from_micro_ccd(ran_deposit as u64 + 1);
+        let number_of_accounts = progress.len();
+        // discard tests outside of 3 <= x <= 8 accounts
+        if number_of_accounts < 3 { return true };
+        if number_of_accounts > 8 { return true };
+        let progress: Vec<u8> = progress.iter().map(|p| p % 4).collect();
+
+        let (accounts, vote_config, merkle_tree) =
+            setup_test_config(number_of_accounts as i32, deposit);
+        let caller = accounts[ran_caller as usize % number_of_accounts];
+
+        let base = number_of_accounts as u64 + 1;
+        let g_v = ProjectivePoint::GENERATOR * util::encode_vote_option(0, base, 1);
+
+        let init_parameter = to_bytes(&vote_config);
+        let init_ctx = setup_init_context(&init_parameter);
+        let mut state_builder = TestStateBuilder::new();
+        let voting_state = setup(&init_ctx, &mut state_builder).unwrap();
+        let (_, mut host) = setup_receive_context(None, caller, voting_state, state_builder);
+
+        // Registration: the test host does not add the deposit to the balance, so keep track of it here
+        let mut deposits = Amount::zero();
+        let mut voting_key_pairs = BTreeMap::new();
+        for (i, acc) in accounts.iter().enumerate().filter(|(i, _)| progress[*i] >= 1) {
+            let (x, g_x) = off_chain::create_votingkey_pair();
+            let register_message = to_bytes(&RegisterMessage {
+                voting_key: g_x.to_bytes().to_vec(),
+                voting_key_zkp: off_chain::create_schnorr_zkp(g_x, x, &challenge_context(*acc, VotingPhase::Registration)),
+                merkle_proof: off_chain::create_merkle_proof(*acc, 1, &merkle_tree),
+                weight: 1,
+            });
+            let mut ctx = make_ctx(*acc, 1);
+            ctx.set_parameter(&register_message);
+            if register(&ctx, &mut host, deposit).is_ok() {
+                deposits += deposit;
+                host.set_self_balance(host.self_balance() + deposit);
+                voting_key_pairs.insert(i, (x, g_x));
+            }
+        }
+        let _ = change_phase(&make_ctx(caller, 101), &mut host);
+
+        // Commit, with the reconstructed keys computed from the voting keys of the registered voters
+        let keys: Vec<ProjectivePoint> = voting_key_pairs.values().map(|(_, g_x)| *g_x).collect();
+        for (i, (x, g_x)) in voting_key_pairs.iter().filter(|(i, _)| progress[**i] >= 2) {
+            let g_y = off_chain::compute_reconstructed_key(&keys, *g_x);
+            let commit_message = to_bytes(&CommitMessage {
+                reconstructed_key: g_y.to_bytes().to_vec(),
+                commitment: off_chain::commit_to_vote(x, &g_y, g_v),
+            });
+            let mut ctx = make_ctx(accounts[*i], 101);
+            ctx.set_parameter(&commit_message);
+            let _ = commit(&ctx, &mut host);
+        }
+        let _ = change_phase(&make_ctx(caller, 201), &mut host);
+
+        // Vote
+        for (i, (x, g_x)) in voting_key_pairs.iter().filter(|(i, _)| progress[**i] >= 3) {
+            let acc = accounts[*i];
+            let g_y = off_chain::compute_reconstructed_key(&keys, *g_x);
+            let vote_message = to_bytes(&VoteMessage {
+                vote: ((g_y * x) + g_v).to_bytes().to_vec(),
+                vote_zkp: off_chain::create_one_in_k_zkp(*g_x, g_y, *x, 0, 2, base, 1, &challenge_context(acc, VotingPhase::Vote)),
+            });
+            let mut ctx = make_ctx(acc, 201);
+            ctx.set_parameter(&vote_message);
+            let _ = vote(&ctx, &mut host);
+        }
+
+        // No one recovers, so the recovery phase times out
+        let _ = change_phase(&make_ctx(caller, 301), &mut host);
+        let _ = change_phase(&make_ctx(caller, 401), &mut host);
+
+        let transferred: Amount = host.get_transfers().into_iter().map(|(_, amount)| amount).sum();
+        assert_eq!(deposits, transferred + host.self_balance(), "Deposits are not conserved");
+
+        match host.state().voting_phase {
+            VotingPhase::Result | VotingPhase::Abort => {
+                let settlement = host.state().settlement.clone().unwrap();
+                assert_eq!(host.self_balance(), settlement.remaining, "Balance should be what is left to sweep");
+                assert_eq!(
+                    deposit * settlement.forfeits.len() as u64,
+                    settlement.remaining + settlement.caller_reward.map_or(Amount::zero(), |(_, amount)| amount),
+                    "Forfeited deposits should go to the caller or the beneficiary"
+                );
+
+                if settlement.remaining > Amount::zero() {
+                    assert!(sweep(&make_ctx(BENEFICIARY, 401), &mut host).is_ok());
+                }
+                assert_eq!(host.self_balance(), Amount::zero(), "Nothing should be left after sweeping");
+            }
+            phase => panic!("Vote should have ended, but is in {:?}", phase),
+        }
 
+        true
+    }
+
+    // Abstract model of a registered voter
+    #[derive(Clone, Debug, Default)]
+    struct ModelVoter {
+        committed: bool,
+        voted: bool,
+    }
 
+    // Abstract model of the contract, with the timeouts of setup_test_config and every voter having weight 1.
+    // Voter i votes for option i % 2
+    #[derive(Clone, Debug)]
+    struct Model {
+        phase: VotingPhase,
+        number_of_accounts: usize,
+        deposit: u64,
+        voters: BTreeMap<usize, ModelVoter>, // registered voters by account index
+        balance: u64,
+        paid: BTreeMap<AccountAddress, u64>, // total transferred to each account
+        result: Vec<u64>,
+    }
+
+    impl Model {
+        fn new(number_of_accounts: usize, deposit: u64) -> Self {
+            Model {
+                phase: VotingPhase::Registration,
+                number_of_accounts,
+                deposit,
+                voters: BTreeMap::new(),
+                balance: 0,
+                paid: BTreeMap::new(),
+                result: Vec::new(),
+            }
+        }
+
+        fn pay(&mut self, account: AccountAddress, amount: u64) {
+            self.balance -= amount;
+            *self.paid.entry(account).or_insert(0) += amount;
+        }
 
+        fn can_recover(participating: usize, registered: usize) -> bool {
+            participating > 2 && 2 * participating > registered
+        }
+
+        // Each function returns whether the call should succeed, and updates the model if so
+
+        fn register(&mut self, i: usize, now: u64) -> bool {
+            if self.phase != VotingPhase::Registration || now > 100 || self.voters.contains_key(&i) {
+                return false;
+            }
+            self.voters.insert(i, ModelVoter::default());
+            self.balance += self.deposit;
+            if self.voters.len() == self.number_of_accounts {
+                self.phase = VotingPhase::Commit;
+            }
+            true
+        }
+
+        fn commit(&mut self, i: usize, now: u64) -> bool {
+            if self.phase != VotingPhase::Commit || now > 200 || !self.voters.contains_key(&i) {
+                return false;
+            }
+            self.voters.get_mut(&i).unwrap().committed = true;
+            if self.voters.values().all(|v| v.committed) {
+                self.phase = VotingPhase::Vote;
+            }
+            true
+        }
+
+        fn vote(&mut self, i: usize, accounts: &Vec<AccountAddress>, now: u64) -> bool {
+            match self.voters.get(&i) {
+                Some(v) if self.phase == VotingPhase::Vote && now <= 300 && v.committed && !v.voted => (),
+                _ => return false,
+            }
+            self.voters.get_mut(&i).unwrap().voted = true;
+            if self.voters.values().all(|v| v.voted) {
+                self.settle(None, accounts);
+                self.phase = VotingPhase::Result;
+            }
+            true
+        }
+
+        fn result(&mut self) -> bool {
+            if self.phase != VotingPhase::Result {
+                return false;
+            }
+            let mut result = vec![0, 0];
+            for (i, _) in self.voters.iter().filter(|(_, v)| v.voted) {
+                result[i % 2] += 1;
+            }
+            self.result = result;
+            true
+        }
+
+        fn change_phase(&mut self, caller: AccountAddress, accounts: &Vec<AccountAddress>, now: u64) -> bool {
+            let registered = self.voters.len();
+            let committed = self.voters.values().filter(|v| v.committed).count();
+            let voted = self.voters.values().filter(|v| v.voted).count();
+            match self.phase {
+                VotingPhase::Registration if now > 100 => {
+                    if registered > 2 {
+                        self.phase = VotingPhase::Commit;
+                    } else {
+                        self.settle(Some(caller), accounts);
+                        self.phase = VotingPhase::Abort;
+                    }
+                }
+                VotingPhase::Commit => {
+                    if committed == registered || (now > 200 && Model::can_recover(committed, registered)) {
+                        self.phase = VotingPhase::Vote;
+                    } else if now > 200 {
+                        self.settle(Some(caller), accounts);
+                        self.phase = VotingPhase::Abort;
+                    }
+                }
+                VotingPhase::Vote => {
+                    if voted == registered {
+                        self.settle(None, accounts);
+                        self.phase = VotingPhase::Result;
+                    } else if now > 300 && Model::can_recover(voted, registered) {
+                        self.phase = VotingPhase::Recovery;
+                    } else if now > 300 {
+                        self.settle(Some(caller), accounts);
+                        self.phase = VotingPhase::Abort;
+                    }
+                }
+                // No one sends their recovery key in the traces
+                VotingPhase::Recovery if now > 400 => {
+                    self.settle(Some(caller), accounts);
+                    self.phase = VotingPhase::Abort;
+                }
+                _ => (),
+            }
+            true
+        }
+
+        // When the vote ends, voters who did their part in the phase it ends from are refunded, and the caller
+        // is rewarded one deposit if anyone forfeited theirs and the caller did not
+        fn settle(&mut self, caller: Option<AccountAddress>, accounts: &Vec<AccountAddress>) {
+            let phase = self.phase;
+            let honest = |v: &ModelVoter| match phase {
+                VotingPhase::Registration => true,
+                VotingPhase::Commit => v.committed,
+                VotingPhase::Vote => v.voted,
+                // No one sends their recovery key in the traces
+                _ => false,
+            };
+            let refunded: Vec<usize> = self.voters.iter().filter(|(_, v)| honest(v)).map(|(i, _)| *i).collect();
+            let forfeited: Vec<AccountAddress> = self
+                .voters
+                .iter()
+                .filter(|(_, v)| !honest(v))
+                .map(|(i, _)| accounts[*i])
+                .collect();
+
+            for i in refunded {
+                self.pay(accounts[i], self.deposit);
+            }
+            match caller {
+                Some(caller) if !forfeited.is_empty() && !forfeited.contains(&caller) => {
+                    self.pay(caller, self.deposit)
+                }
+                _ => (),
+            }
+        }
+    }
+
+    // Model-based test: run a random trace of calls against both the model and the contract, and check after each
+    // step that they agree on whether the call succeeded, the phase, the result, the balance and the transfers.
+    // Failing traces are shrunk to the shortest trace that disagrees with the model
+    #[quickcheck]
+    fn test_prop_model(ran_accounts: u8, ran_deposit: u8, commands: Commands) -> bool {
+        let number_of_accounts = 3 + (ran_accounts as usize % 4); // between 3 and 6 accounts
+        let deposit = ran_deposit as u64 + 1;
+
+        let (accounts, vote_config, merkle_tree) =
+            setup_test_config(number_of_accounts as i32, Amount::from_micro_ccd(deposit));
+        let base = number_of_accounts as u64 + 1;
+
+        let voting_key_pairs: Vec<_> = (0..number_of_accounts).map(|_| off_chain::create_votingkey_pair()).collect();
+
+        let init_parameter = to_bytes(&vote_config);
+        let init_ctx = setup_init_context(&init_parameter);
+        let mut state_builder = TestStateBuilder::new();
+        let voting_state = setup(&init_ctx, &mut state_builder).unwrap();
+        let (_, mut host) = setup_receive_context(None, accounts[0], voting_state, state_builder);
+
+        let mut model = Model::new(number_of_accounts, deposit);
+        let mut now = 1;
+
+        for (step_index, step) in commands.0.iter().enumerate() {
+            now += step.advance;
+            let i = step.account % number_of_accounts;
+            let acc = accounts[i];
+            let (x, g_x) = voting_key_pairs[i];
+            let g_v = ProjectivePoint::GENERATOR * util::encode_vote_option(i as u32 % 2, base, 1);
+            // Reconstructed key from the voting keys of the registered voters, in the order of the voters.
+            // Unregistered accounts use their own voting key, which the contract rejects anyway
+            let g_y = if model.voters.contains_key(&i) {
+                let keys = model.voters.keys().map(|j| voting_key_pairs[*j].1).collect();
+                off_chain::compute_reconstructed_key(&keys, g_x)
+            } else {
+                g_x
+            };
+
+            let mut ctx = TestReceiveContext::empty();
+            ctx.metadata_mut().set_slot_time(Timestamp::from_timestamp_millis(now));
+            ctx.set_sender(Address::Account(acc));
+            ctx.set_self_address(CONTRACT_ADDRESS);
+
+            let (contract_ok, model_ok) = match step.func {
+                Funcs::Register => {
+                    let register_message = to_bytes(&RegisterMessage {
+                        voting_key: g_x.to_bytes().to_vec(),
+                        voting_key_zkp: off_chain::create_schnorr_zkp(g_x, x, &challenge_context(acc, VotingPhase::Registration)),
+                        merkle_proof: off_chain::create_merkle_proof(acc, 1, &merkle_tree),
+                        weight: 1,
+                    });
+                    ctx.set_parameter(&register_message);
+                    let contract_ok = register(&ctx, &mut host, Amount::from_micro_ccd(deposit)).is_ok();
+                    // The test host does not add the deposit to the balance
+                    if contract_ok {
+                        host.set_self_balance(host.self_balance() + Amount::from_micro_ccd(deposit));
+                    }
+                    (contract_ok, model.register(i, now))
+                }
+                Funcs::Commit => {
+                    let commit_message = to_bytes(&CommitMessage {
+                        reconstructed_key: g_y.to_bytes().to_vec(),
+                        commitment: off_chain::commit_to_vote(&x, &g_y, g_v),
+                    });
+                    ctx.set_parameter(&commit_message);
+                    (commit(&ctx, &mut host).is_ok(), model.commit(i, now))
+                }
+                Funcs::Vote => {
+                    let vote_message = to_bytes(&VoteMessage {
+                        vote: ((g_y * x) + g_v).to_bytes().to_vec(),
+                        vote_zkp: off_chain::create_one_in_k_zkp(g_x, g_y, x, i as u32 % 2, 2, base, 1, &challenge_context(acc, VotingPhase::Vote)),
+                    });
+                    ctx.set_parameter(&vote_message);
+                    (vote(&ctx, &mut host).is_ok(), model.vote(i, &accounts, now))
+                }
+                Funcs::Result => (result(&ctx, &mut host).is_ok(), model.result()),
+                Funcs::ChangePhase => (
+                    change_phase(&ctx, &mut host).is_ok(),
+                    model.change_phase(acc, &accounts, now),
+                ),
+            };
+
+            let transfers = host.get_transfers().into_iter().fold(BTreeMap::new(), |mut paid, (acc, amount)| {
+                *paid.entry(acc).or_insert(0) += amount.micro_ccd;
+                paid
+            });
+
+            assert_eq!(contract_ok, model_ok, "Step {} {:?} at time {}: success differs from the model", step_index, step, now);
+            assert_eq!(host.state().voting_phase, model.phase, "Step {} {:?}: phase differs from the model", step_index, step);
+            assert_eq!(host.state().voting_result, model.result, "Step {} {:?}: result differs from the model", step_index, step);
+            assert_eq!(host.self_balance().micro_ccd, model.balance, "Step {} {:?}: balance differs from the model", step_index, step);
+            assert_eq!(transfers, model.paid, "Step {} {:?}: transfers differ from the model", step_index, step);
+        }
+
+        true
+    }
+}
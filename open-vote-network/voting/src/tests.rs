@@ -48,8 +48,8 @@ mod tests {
 
         claim_eq!(
             state.voting_result,
-            (-1, -1),
-            "Voting result should be -1, since voting is not done"
+            Vec::<u64>::new(),
+            "Voting result should be empty, since voting is not done"
         );
 
         claim_eq!(
@@ -57,6 +57,39 @@ mod tests {
             0,
             "Registered voters map should be empty"
         );
+
+        // A vote needs at least 2 options
+        let (_, mut vote_config, _) = test_utils::setup_test_config(3, Amount::from_micro_ccd(0));
+        vote_config.voting_options = vec!["Yes".to_string()];
+        let vote_config_bytes = to_bytes(&vote_config);
+        let ctx = test_utils::setup_init_context(&vote_config_bytes);
+        claim_eq!(
+            setup(&ctx, &mut test_infrastructure::TestStateBuilder::new()).err(),
+            Some(types::SetupError::InvalidNumberOfOptions),
+            "Setup should fail with fewer than 2 options"
+        );
+
+        // The base the options are encoded with (max total weight + 1) must not overflow
+        let (_, mut vote_config, _) = test_utils::setup_test_config(3, Amount::from_micro_ccd(0));
+        vote_config.max_total_weight = u64::MAX;
+        let vote_config_bytes = to_bytes(&vote_config);
+        let ctx = test_utils::setup_init_context(&vote_config_bytes);
+        claim_eq!(
+            setup(&ctx, &mut test_infrastructure::TestStateBuilder::new()).err(),
+            Some(types::SetupError::InvalidMaxTotalWeight),
+            "Setup should fail when the base overflows"
+        );
+
+        // 40 voters choosing between 5 options can tally 40 * 41^4, which is too large to find on-chain
+        let (_, mut vote_config, _) = test_utils::setup_test_config(40, Amount::from_micro_ccd(0));
+        vote_config.voting_options = (0..5).map(|i| i.to_string()).collect();
+        let vote_config_bytes = to_bytes(&vote_config);
+        let ctx = test_utils::setup_init_context(&vote_config_bytes);
+        claim_eq!(
+            setup(&ctx, &mut test_infrastructure::TestStateBuilder::new()).err(),
+            Some(types::SetupError::TallyTooLarge),
+            "Setup should fail when result cannot find the largest possible tally"
+        );
     }
 
     #[concordium_test]
@@ -73,8 +106,13 @@ mod tests {
 
         let register_message = RegisterMessage {
             voting_key: g_x.to_bytes().to_vec(),
-            voting_key_zkp: off_chain::create_schnorr_zkp(g_x, x),
-            merkle_proof: off_chain::create_merkle_proof(accounts[0], &merkle_tree),
+            voting_key_zkp: off_chain::create_schnorr_zkp(
+                g_x,
+                x,
+                &test_utils::challenge_context(accounts[0], types::VotingPhase::Registration),
+            ),
+            merkle_proof: off_chain::create_merkle_proof(accounts[0], 1, &merkle_tree),
+            weight: 1,
         };
 
         let register_message_bytes = to_bytes(&register_message);
@@ -137,9 +175,14 @@ mod tests {
 
         let register_message2 = RegisterMessage {
             voting_key: g_x2.to_bytes().to_vec(),
-            voting_key_zkp: off_chain::create_schnorr_zkp(g_x2, x2),
+            voting_key_zkp: off_chain::create_schnorr_zkp(
+                g_x2,
+                x2,
+                &test_utils::challenge_context(voter2, types::VotingPhase::Registration),
+            ),
             // Unauthorized voter creates a malicious proof as another voter (account 0)
-            merkle_proof: off_chain::create_merkle_proof(accounts[0], &merkle_tree),
+            merkle_proof: off_chain::create_merkle_proof(accounts[0], 1, &merkle_tree),
+            weight: 1,
         };
 
         let register_message_bytes2 = to_bytes(&register_message2);
@@ -166,6 +209,200 @@ mod tests {
         );
     }
 
+    #[concordium_test]
+    fn test_register_malformed_message() {
+        let (accounts, vote_config, merkle_tree) =
+            test_utils::setup_test_config(2, Amount::from_micro_ccd(0));
+
+        let (x, g_x) = off_chain::create_votingkey_pair();
+        let context = test_utils::challenge_context(accounts[0], types::VotingPhase::Registration);
+
+        // Voting key, ZKP and merkle proof that cannot be decoded should be rejected, not trap
+        let malformed_messages = vec![
+            (
+                RegisterMessage {
+                    voting_key: vec![1, 2, 3],
+                    voting_key_zkp: off_chain::create_schnorr_zkp(g_x, x, &context),
+                    merkle_proof: off_chain::create_merkle_proof(accounts[0], 1, &merkle_tree),
+                    weight: 1,
+                },
+                types::RegisterError::InvalidVotingKey,
+            ),
+            (
+                RegisterMessage {
+                    voting_key: g_x.to_bytes().to_vec(),
+                    voting_key_zkp: util::SchnorrProof {
+                        g_w: vec![0; 33],
+                        r: vec![0; 32],
+                    },
+                    merkle_proof: off_chain::create_merkle_proof(accounts[0], 1, &merkle_tree),
+                    weight: 1,
+                },
+                types::RegisterError::InvalidZKP,
+            ),
+            (
+                RegisterMessage {
+                    voting_key: g_x.to_bytes().to_vec(),
+                    voting_key_zkp: off_chain::create_schnorr_zkp(g_x, x, &context),
+                    merkle_proof: util::MerkleProof {
+                        proof: vec![1, 2, 3],
+                        ..off_chain::create_merkle_proof(accounts[0], 1, &merkle_tree)
+                    },
+                    weight: 1,
+                },
+                types::RegisterError::InvalidMerkleProof,
+            ),
+        ];
+
+        for (register_message, expected_error) in malformed_messages {
+            let (state, state_builder) = test_utils::setup_state(
+                &accounts,
+                vote_config.clone(),
+                types::VotingPhase::Registration,
+            );
+
+            let register_message_bytes = to_bytes(&register_message);
+
+            let (ctx, mut host) = test_utils::setup_receive_context(
+                Some(&register_message_bytes),
+                accounts[0],
+                state,
+                state_builder,
+            );
+
+            let result = register(&ctx, &mut host, Amount::from_micro_ccd(0));
+
+            claim_eq!(
+                result,
+                Err(expected_error),
+                "Malformed message should be rejected"
+            );
+        }
+    }
+
+    #[concordium_test]
+    fn test_register_replayed_zkp() {
+        let (accounts, vote_config, merkle_tree) =
+            test_utils::setup_test_config(2, Amount::from_micro_ccd(0));
+
+        let (x, g_x) = off_chain::create_votingkey_pair();
+
+        // A ZKP is only valid for the contract instance, account and phase it was made for
+        let other_instance = util::ChallengeContext::new(
+            ContractAddress {
+                index: 1,
+                subindex: 0,
+            },
+            accounts[1],
+            &types::VotingPhase::Registration,
+        );
+        let replayed_contexts = vec![
+            other_instance,
+            test_utils::challenge_context(accounts[0], types::VotingPhase::Registration),
+            test_utils::challenge_context(accounts[1], types::VotingPhase::Commit),
+        ];
+
+        for context in replayed_contexts {
+            let (state, state_builder) = test_utils::setup_state(
+                &accounts,
+                vote_config.clone(),
+                types::VotingPhase::Registration,
+            );
+
+            let register_message_bytes = to_bytes(&RegisterMessage {
+                voting_key: g_x.to_bytes().to_vec(),
+                voting_key_zkp: off_chain::create_schnorr_zkp(g_x, x, &context),
+                merkle_proof: off_chain::create_merkle_proof(accounts[1], 1, &merkle_tree),
+                weight: 1,
+            });
+
+            let (ctx, mut host) = test_utils::setup_receive_context(
+                Some(&register_message_bytes),
+                accounts[1],
+                state,
+                state_builder,
+            );
+
+            let result = register(&ctx, &mut host, Amount::from_micro_ccd(0));
+
+            claim_eq!(
+                result,
+                Err(types::RegisterError::InvalidZKP),
+                "Replayed ZKP should be rejected"
+            );
+        }
+    }
+
+    #[concordium_test]
+    fn test_register_weight() {
+        let (accounts, mut vote_config, _) =
+            test_utils::setup_test_config(2, Amount::from_micro_ccd(0));
+
+        // Voter 1 has weight 3 and voter 2 has weight 2, but the total weight may only be 4
+        let voters = vec![(accounts[0], 3), (accounts[1], 2)];
+        let merkle_tree = off_chain::create_merkle_tree(&voters);
+        vote_config.merkle_root = merkle_tree.root().unwrap();
+        vote_config.max_total_weight = 4;
+
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Registration);
+        let (mut ctx, mut host) =
+            test_utils::setup_receive_context(None, accounts[0], state, state_builder);
+
+        let mut register_messages = Vec::new();
+        for (account, weight, claimed_weight) in [
+            (accounts[0], 3, 5),
+            (accounts[0], 3, 3),
+            (accounts[1], 2, 2),
+        ] {
+            let (x, g_x) = off_chain::create_votingkey_pair();
+            register_messages.push((
+                account,
+                to_bytes(&RegisterMessage {
+                    voting_key: g_x.to_bytes().to_vec(),
+                    voting_key_zkp: off_chain::create_schnorr_zkp(
+                        g_x,
+                        x,
+                        &test_utils::challenge_context(account, types::VotingPhase::Registration),
+                    ),
+                    merkle_proof: off_chain::create_merkle_proof(account, weight, &merkle_tree),
+                    weight: claimed_weight,
+                }),
+            ));
+        }
+
+        let expected_results = [
+            Err(types::RegisterError::UnauthorizedVoter),
+            Ok(()),
+            Err(types::RegisterError::MaxTotalWeightExceeded),
+        ];
+
+        for ((account, register_message_bytes), expected_result) in
+            register_messages.iter().zip(expected_results)
+        {
+            ctx.set_parameter(register_message_bytes);
+            ctx.set_sender(Address::Account(*account));
+
+            let result = register(&ctx, &mut host, Amount::from_micro_ccd(0));
+
+            claim_eq!(
+                result,
+                expected_result,
+                "Weight in the register message should match the merkle leaf and fit the max total weight"
+            );
+        }
+
+        let voter1 = match host.state().voters.get(&accounts[0]) {
+            Some(v) => v,
+            None => fail!("Voter 1 should exist"),
+        };
+        claim_eq!(
+            voter1.weight,
+            3,
+            "Voter 1 should be registered with weight 3"
+        );
+    }
+
     #[concordium_test]
     fn test_change_phase() {
         let (accounts, vote_config, _) =
@@ -177,6 +414,9 @@ mod tests {
         let (mut ctx, mut host) =
             test_utils::setup_receive_context(None, accounts[0], state, statte_builder);
 
+        // Deposit is 1 and there are 3 accounts thus balance is 3
+        host.set_self_balance(Amount::from_micro_ccd(3));
+
         // Simulate that the 3 voters have registered
         let (x1, g_x1) = off_chain::create_votingkey_pair();
         let (x2, g_x2) = off_chain::create_votingkey_pair();
@@ -344,6 +584,11 @@ mod tests {
             host.state().voting_phase,
             types::VotingPhase::Result,
             "Phase should have changed to result"
+        );
+        claim_eq!(
+            host.self_balance(),
+            Amount::zero(),
+            "All voters should be refunded when the vote reaches the result"
         )
     }
 
@@ -466,14 +711,25 @@ mod tests {
         let g_y2 = off_chain::compute_reconstructed_key(&keys, g_x2.clone());
         let g_y3 = off_chain::compute_reconstructed_key(&keys, g_x3.clone());
 
+        // Options are encoded as g^(base^i), where base is the number of voters + 1
+        let base = 4;
+        let g_yes = ProjectivePoint::GENERATOR * util::encode_vote_option(0, base, 1);
+        let g_no = ProjectivePoint::GENERATOR * util::encode_vote_option(1, base, 1);
+
         // Testing no vote
-        let one_in_two_zkp_account1 =
-            off_chain::create_one_in_two_zkp_no(g_x1, g_y1.clone(), x1.clone());
+        let one_in_k_zkp_account1 = off_chain::create_one_in_k_zkp(
+            g_x1,
+            g_y1.clone(),
+            x1.clone(),
+            1,
+            2,
+            base,
+            1,
+            &test_utils::challenge_context(accounts[0], types::VotingPhase::Vote),
+        );
         let vote_message1 = VoteMessage {
-            vote: ((g_y1.clone() * x1.clone()) + ProjectivePoint::IDENTITY)
-                .to_bytes()
-                .to_vec(),
-            vote_zkp: one_in_two_zkp_account1,
+            vote: ((g_y1.clone() * x1.clone()) + g_no).to_bytes().to_vec(),
+            vote_zkp: one_in_k_zkp_account1,
         };
         let vote_message_bytes = to_bytes(&vote_message1);
 
@@ -493,24 +749,30 @@ mod tests {
         host.state_mut().voters.insert(
             accounts[0],
             Voter {
+                weight: 1,
+                voting_key: g_x1.to_bytes().to_vec(),
                 reconstructed_key: g_y1.to_bytes().to_vec(),
-                commitment: off_chain::commit_to_vote(&x1, &g_y1, ProjectivePoint::IDENTITY),
+                commitment: off_chain::commit_to_vote(&x1, &g_y1, g_no),
                 ..Default::default()
             },
         );
         host.state_mut().voters.insert(
             accounts[1],
             Voter {
+                weight: 1,
+                voting_key: g_x2.to_bytes().to_vec(),
                 reconstructed_key: g_y2.to_bytes().to_vec(),
-                commitment: off_chain::commit_to_vote(&x2, &g_y2, ProjectivePoint::GENERATOR),
+                commitment: off_chain::commit_to_vote(&x2, &g_y2, g_yes),
                 ..Default::default()
             },
         );
         host.state_mut().voters.insert(
             accounts[2],
             Voter {
+                weight: 1,
+                voting_key: g_x3.to_bytes().to_vec(),
                 reconstructed_key: g_y3.to_bytes().to_vec(),
-                commitment: off_chain::commit_to_vote(&x3, &g_y3, ProjectivePoint::GENERATOR),
+                commitment: off_chain::commit_to_vote(&x3, &g_y3, g_yes),
                 ..Default::default()
             },
         );
@@ -532,18 +794,24 @@ mod tests {
 
         claim_eq!(
             host.self_balance(),
-            Amount::from_micro_ccd(2),
-            "Voter 1 should have been refunded"
+            Amount::from_micro_ccd(3),
+            "Voter 1 should not be refunded before the vote ends"
         );
 
         // Testing yes vote
-        let one_two_zkp_account2 =
-            off_chain::create_one_in_two_zkp_yes(g_x2, g_y2.clone(), x2.clone());
+        let one_in_k_zkp_account2 = off_chain::create_one_in_k_zkp(
+            g_x2,
+            g_y2.clone(),
+            x2.clone(),
+            0,
+            2,
+            base,
+            1,
+            &test_utils::challenge_context(accounts[1], types::VotingPhase::Vote),
+        );
         let vote_message2 = VoteMessage {
-            vote: ((g_y2 * x2) + ProjectivePoint::GENERATOR)
-                .to_bytes()
-                .to_vec(),
-            vote_zkp: one_two_zkp_account2,
+            vote: ((g_y2 * x2) + g_yes).to_bytes().to_vec(),
+            vote_zkp: one_in_k_zkp_account2,
         };
         let vote_message_bytes = to_bytes(&vote_message2);
         ctx.set_parameter(&vote_message_bytes);
@@ -556,11 +824,265 @@ mod tests {
             "Contract receive failed, but should not have"
         );
 
+        claim_eq!(
+            host.self_balance(),
+            Amount::from_micro_ccd(3),
+            "Voter 2 should not be refunded before the vote ends"
+        );
+
+        // Testing that voter 3's vote is only accepted with a ZKP bound to voter 3, this contract and the vote phase
+        let vote3 = ((g_y3 * x3) + g_yes).to_bytes().to_vec();
+        let vote_message_with_context = |context: &util::ChallengeContext| {
+            to_bytes(&VoteMessage {
+                vote: vote3.clone(),
+                vote_zkp: off_chain::create_one_in_k_zkp(g_x3, g_y3, x3, 0, 2, base, 1, context),
+            })
+        };
+        ctx.set_sender(Address::Account(accounts[2]));
+
+        // Replaying voter 3's valid ZKP on another contract instance
+        let valid_message_bytes = vote_message_with_context(&test_utils::challenge_context(
+            accounts[2],
+            types::VotingPhase::Vote,
+        ));
+
+        // Pairing voter 3's honest ZKP with another vote point
+        let swapped_message_bytes = to_bytes(&VoteMessage {
+            vote: ((g_y3 * x3) + g_no).to_bytes().to_vec(),
+            vote_zkp: off_chain::create_one_in_k_zkp(
+                g_x3,
+                g_y3,
+                x3,
+                0,
+                2,
+                base,
+                1,
+                &test_utils::challenge_context(accounts[2], types::VotingPhase::Vote),
+            ),
+        });
+        ctx.set_parameter(&swapped_message_bytes);
+        claim_eq!(
+            vote(&ctx, &mut host),
+            Err(types::VoteError::InvalidZKP),
+            "ZKP for another vote point should be rejected"
+        );
+
+        ctx.set_parameter(&valid_message_bytes);
+        ctx.set_self_address(ContractAddress {
+            index: 1,
+            subindex: 0,
+        });
+        claim_eq!(
+            vote(&ctx, &mut host),
+            Err(types::VoteError::InvalidZKP),
+            "Replayed vote on another contract instance should be rejected"
+        );
+        ctx.set_self_address(test_utils::CONTRACT_ADDRESS);
+
+        // Voter 3's ZKPs made for another sender or another phase
+        let replayed_messages: Vec<Vec<u8>> = vec![
+            test_utils::challenge_context(accounts[1], types::VotingPhase::Vote),
+            test_utils::challenge_context(accounts[2], types::VotingPhase::Commit),
+        ]
+        .iter()
+        .map(vote_message_with_context)
+        .collect();
+        for replayed_message_bytes in &replayed_messages {
+            ctx.set_parameter(replayed_message_bytes);
+            claim_eq!(
+                vote(&ctx, &mut host),
+                Err(types::VoteError::InvalidZKP),
+                "Replayed vote should be rejected"
+            );
+        }
+
+        // The same vote with the ZKP bound to voter 3, this contract and the vote phase is accepted
+        ctx.set_parameter(&valid_message_bytes);
+        claim!(
+            vote(&ctx, &mut host).is_ok(),
+            "Contract receive failed, but should not have"
+        );
+        claim_eq!(
+            host.state().voting_phase,
+            types::VotingPhase::Result,
+            "Vote should end once all voters have voted"
+        );
+    }
+
+    #[concordium_test]
+    fn test_recover() {
+        let (accounts, vote_config, _) =
+            test_utils::setup_test_config(4, Amount::from_micro_ccd(1));
+
+        // Create pk, sk pair of g^x and x for accounts
+        let (x1, g_x1) = off_chain::create_votingkey_pair();
+        let (x2, g_x2) = off_chain::create_votingkey_pair();
+        let (x3, g_x3) = off_chain::create_votingkey_pair();
+        let (_, g_x4) = off_chain::create_votingkey_pair();
+
+        let list_of_voting_keys = vec![g_x1.clone(), g_x2.clone(), g_x3.clone(), g_x4.clone()];
+
+        // Compute reconstructed key
+        let g_y1 = off_chain::compute_reconstructed_key(&list_of_voting_keys, g_x1.clone());
+        let g_y2 = off_chain::compute_reconstructed_key(&list_of_voting_keys, g_x2.clone());
+        let g_y3 = off_chain::compute_reconstructed_key(&list_of_voting_keys, g_x3.clone());
+        let g_y4 = off_chain::compute_reconstructed_key(&list_of_voting_keys, g_x4.clone());
+
+        // Options are encoded as g^(base^i), where base is the number of voters + 1
+        let base = 5;
+        let g_yes = ProjectivePoint::GENERATOR * util::encode_vote_option(0, base, 1);
+        let g_no = ProjectivePoint::GENERATOR * util::encode_vote_option(1, base, 1);
+
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Vote);
+
+        let (mut ctx, mut host) =
+            test_utils::setup_receive_context(None, accounts[0], state, state_builder);
+
+        // Deposit is 1 and there are 4 voters thus balance is 4
+        host.set_self_balance(Amount::from_micro_ccd(4));
+
+        // Simulate that the first 3 voters voted, while voter 4 dropped out after committing
+        for (account, x, g_x, g_y, g_v) in [
+            (accounts[0], x1, g_x1, g_y1, g_yes),
+            (accounts[1], x2, g_x2, g_y2, g_no),
+            (accounts[2], x3, g_x3, g_y3, g_yes),
+        ] {
+            host.state_mut().voters.insert(
+                account,
+                Voter {
+                    weight: 1,
+                    voting_key: g_x.to_bytes().to_vec(),
+                    reconstructed_key: g_y.to_bytes().to_vec(),
+                    commitment: off_chain::commit_to_vote(&x, &g_y, g_v),
+                    vote: ((g_y * x) + g_v).to_bytes().to_vec(),
+                    ..Default::default()
+                },
+            );
+        }
+        host.state_mut().voters.insert(
+            accounts[3],
+            Voter {
+                weight: 1,
+                voting_key: g_x4.to_bytes().to_vec(),
+                reconstructed_key: g_y4.to_bytes().to_vec(),
+                ..Default::default()
+            },
+        );
+
+        // Testing that the phase changes to recovery, since a majority voted before the vote timeout
+        ctx.metadata_mut()
+            .set_slot_time(Timestamp::from_timestamp_millis(301));
+
+        let result = change_phase(&ctx, &mut host);
+
+        claim!(
+            result.is_ok(),
+            "Contract receive failed, but should not have"
+        );
+        claim_eq!(
+            host.state().voting_phase,
+            types::VotingPhase::Recovery,
+            "Should change to recovery phase since a majority voted"
+        );
+
+        // Testing that the voter who dropped out cannot recover
+        let (recovery_key, recovery_zkp) = off_chain::create_recovery_key(
+            &list_of_voting_keys,
+            &vec![g_x4],
+            g_x1,
+            x1.clone(),
+            &test_utils::challenge_context(accounts[0], types::VotingPhase::Recovery),
+        );
+        let recovery_message_bytes = to_bytes(&RecoveryMessage {
+            recovery_key: recovery_key.to_bytes().to_vec(),
+            recovery_zkp,
+        });
+        ctx.set_parameter(&recovery_message_bytes);
+        ctx.set_sender(Address::Account(accounts[3]));
+
+        let result = recover(&ctx, &mut host);
+
+        claim_eq!(
+            result,
+            Err(types::RecoveryError::UnauthorizedVoter),
+            "Voter who dropped out should not be able to recover"
+        );
+
+        // Testing that the voters who voted can recover
+        let mut recovery_messages = Vec::new();
+        for (account, x, g_x) in [
+            (accounts[0], x1, g_x1),
+            (accounts[1], x2, g_x2),
+            (accounts[2], x3, g_x3),
+        ] {
+            let (recovery_key, recovery_zkp) = off_chain::create_recovery_key(
+                &list_of_voting_keys,
+                &vec![g_x4],
+                g_x,
+                x,
+                &test_utils::challenge_context(account, types::VotingPhase::Recovery),
+            );
+            recovery_messages.push((
+                account,
+                to_bytes(&RecoveryMessage {
+                    recovery_key: recovery_key.to_bytes().to_vec(),
+                    recovery_zkp,
+                }),
+            ));
+        }
+
+        for (account, recovery_message_bytes) in recovery_messages.iter() {
+            ctx.set_parameter(recovery_message_bytes);
+            ctx.set_sender(Address::Account(*account));
+
+            let result = recover(&ctx, &mut host);
+
+            claim!(
+                result.is_ok(),
+                "Contract receive failed, but should not have"
+            );
+        }
+
+        claim_eq!(
+            host.state().voting_phase,
+            types::VotingPhase::Result,
+            "Should change to result phase since all voters who voted have recovered"
+        );
+
+        // Testing that the voters who recovered are refunded, while the voter who dropped out forfeits their deposit
+        claim_eq!(
+            host.state().settlement,
+            Some(Settlement {
+                refunds: vec![
+                    (accounts[0], Amount::from_micro_ccd(1)),
+                    (accounts[1], Amount::from_micro_ccd(1)),
+                    (accounts[2], Amount::from_micro_ccd(1))
+                ],
+                forfeits: vec![(accounts[3], Amount::from_micro_ccd(1))],
+                caller_reward: None,
+                remaining: Amount::from_micro_ccd(1),
+            }),
+            "Deposits should be settled when the vote reaches the result"
+        );
         claim_eq!(
             host.self_balance(),
             Amount::from_micro_ccd(1),
-            "Voter 2 should have been refunded"
+            "Forfeited deposit should remain for the beneficiary"
         );
+
+        // Testing that the tally is found when the voting key of the voter who dropped out is cancelled out
+        let result = crate::result(&ctx, &mut host);
+
+        claim!(
+            result.is_ok(),
+            "Contract receive failed, but should not have"
+        );
+        claim_eq!(
+            vec![2, 1],
+            host.state().voting_result,
+            "Wrong voting result"
+        )
     }
 
     #[concordium_test]
@@ -582,6 +1104,11 @@ mod tests {
         let g_y3 = off_chain::compute_reconstructed_key(&list_of_voting_keys, g_x3.clone());
         let g_y4 = off_chain::compute_reconstructed_key(&list_of_voting_keys, g_x4.clone());
 
+        // Options are encoded as g^(base^i), where base is the number of voters + 1
+        let base = 5;
+        let g_yes = ProjectivePoint::GENERATOR * util::encode_vote_option(0, base, 1);
+        let g_no = ProjectivePoint::GENERATOR * util::encode_vote_option(1, base, 1);
+
         let (state, state_builder) =
             test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Result);
 
@@ -591,44 +1118,40 @@ mod tests {
         host.state_mut().voters.insert(
             accounts[0],
             Voter {
+                weight: 1,
                 reconstructed_key: g_y1.to_bytes().to_vec(),
-                commitment: off_chain::commit_to_vote(&x1, &g_y1, ProjectivePoint::IDENTITY),
-                vote: ((g_y1.clone() * x1.clone()) + ProjectivePoint::IDENTITY)
-                    .to_bytes()
-                    .to_vec(),
+                commitment: off_chain::commit_to_vote(&x1, &g_y1, g_no),
+                vote: ((g_y1.clone() * x1.clone()) + g_no).to_bytes().to_vec(),
                 ..Default::default()
             },
         );
         host.state_mut().voters.insert(
             accounts[1],
             Voter {
+                weight: 1,
                 reconstructed_key: g_y2.to_bytes().to_vec(),
-                commitment: off_chain::commit_to_vote(&x2, &g_y2, ProjectivePoint::IDENTITY),
-                vote: ((g_y2.clone() * x2.clone()) + ProjectivePoint::IDENTITY)
-                    .to_bytes()
-                    .to_vec(),
+                commitment: off_chain::commit_to_vote(&x2, &g_y2, g_no),
+                vote: ((g_y2.clone() * x2.clone()) + g_no).to_bytes().to_vec(),
                 ..Default::default()
             },
         );
         host.state_mut().voters.insert(
             accounts[2],
             Voter {
+                weight: 1,
                 reconstructed_key: g_y3.to_bytes().to_vec(),
-                commitment: off_chain::commit_to_vote(&x3, &g_y3, ProjectivePoint::GENERATOR),
-                vote: ((g_y3.clone() * x3.clone()) + ProjectivePoint::GENERATOR)
-                    .to_bytes()
-                    .to_vec(),
+                commitment: off_chain::commit_to_vote(&x3, &g_y3, g_yes),
+                vote: ((g_y3.clone() * x3.clone()) + g_yes).to_bytes().to_vec(),
                 ..Default::default()
             },
         );
         host.state_mut().voters.insert(
             accounts[3],
             Voter {
+                weight: 1,
                 reconstructed_key: g_y4.to_bytes().to_vec(),
-                commitment: off_chain::commit_to_vote(&x4, &g_y4, ProjectivePoint::GENERATOR),
-                vote: ((g_y4.clone() * x4.clone()) + ProjectivePoint::GENERATOR)
-                    .to_bytes()
-                    .to_vec(),
+                commitment: off_chain::commit_to_vote(&x4, &g_y4, g_yes),
+                vote: ((g_y4.clone() * x4.clone()) + g_yes).to_bytes().to_vec(),
                 ..Default::default()
             },
         );
@@ -640,92 +1163,168 @@ mod tests {
             "Contract receive failed, but should not have"
         );
 
-        claim_eq!((2, 2), host.state().voting_result, "Wrong voting result")
+        claim_eq!(
+            vec![2, 2],
+            host.state().voting_result,
+            "Wrong voting result"
+        )
     }
 
     #[concordium_test]
-    fn test_refund_deposits_all_honest() {
+    fn test_brute_force_tally() {
+        // 12 voters and 3 options, where g^xy cancels out when all votes are added
+        let base = 13;
+        let mut votes = Vec::new();
+        for (option, count) in [(0, 7), (1, 3), (2, 2)] {
+            let g_v = ProjectivePoint::GENERATOR * util::encode_vote_option(option, base, 1);
+            votes.extend((0..count).map(|_| g_v));
+        }
+
+        claim_eq!(
+            crypto::brute_force_tally(votes.clone(), 3, base, 12),
+            Some(vec![7, 3, 2]),
+            "Wrong number of votes for each option"
+        );
+
+        // Malformed tally beyond the largest possible tally should not be found
+        votes.push(ProjectivePoint::GENERATOR * util::encode_vote_option(3, base, 1));
+
+        claim_eq!(
+            crypto::brute_force_tally(votes, 3, base, 12),
+            None,
+            "Tally should not be found, since it exceeds the number of voters"
+        );
+
+        // Weighted votes with weights 5, 2 and 3 and a max total weight of 10
+        let base = 11;
+        let weighted_votes = vec![
+            ProjectivePoint::GENERATOR * util::encode_vote_option(0, base, 5),
+            ProjectivePoint::GENERATOR * util::encode_vote_option(1, base, 2),
+            ProjectivePoint::GENERATOR * util::encode_vote_option(0, base, 3),
+        ];
+
+        claim_eq!(
+            crypto::brute_force_tally(weighted_votes.clone(), 2, base, 10),
+            Some(vec![8, 2]),
+            "Wrong total weight for each option"
+        );
+        claim_eq!(
+            crypto::brute_force_tally(weighted_votes, 2, base, 9),
+            None,
+            "Tally should not be found, since it does not sum to the total weight"
+        );
+    }
+
+    #[concordium_test]
+    fn test_views() {
         let (accounts, vote_config, _) =
-            test_utils::setup_test_config(3, Amount::from_micro_ccd(1));
+            test_utils::setup_test_config(3, Amount::from_micro_ccd(0));
 
         let (state, state_builder) =
-            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Vote);
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Commit);
 
-        let (_ctx, mut host) =
+        let (ctx, mut host) =
             test_utils::setup_receive_context(None, accounts[0], state, state_builder);
-        // Simulate that the 3 voters have registered, commited and voted
 
-        // Create pk, sk pair of g^x and x for accounts
-        let (x1, g_x1) = off_chain::create_votingkey_pair();
+        // Simulate that the 3 voters have registered, and voter 2 has committed
+        let (_, g_x1) = off_chain::create_votingkey_pair();
         let (x2, g_x2) = off_chain::create_votingkey_pair();
-        let (x3, g_x3) = off_chain::create_votingkey_pair();
+        let (_, g_x3) = off_chain::create_votingkey_pair();
 
-        host.state_mut().voters.insert(
-            accounts[0],
-            Voter {
-                voting_key: g_x1.to_bytes().to_vec(),
-                ..Default::default()
-            },
-        );
+        let list_of_voting_keys = vec![g_x1.clone(), g_x2.clone(), g_x3.clone()];
+        let g_y2 = off_chain::compute_reconstructed_key(&list_of_voting_keys, g_x2.clone());
+
+        for (account, g_x) in [(accounts[0], g_x1), (accounts[2], g_x3)] {
+            host.state_mut().voters.insert(
+                account,
+                Voter {
+                    voting_key: g_x.to_bytes().to_vec(),
+                    ..Default::default()
+                },
+            );
+        }
         host.state_mut().voters.insert(
             accounts[1],
             Voter {
                 voting_key: g_x2.to_bytes().to_vec(),
+                reconstructed_key: g_y2.to_bytes().to_vec(),
+                commitment: off_chain::commit_to_vote(&x2, &g_y2, ProjectivePoint::GENERATOR),
                 ..Default::default()
             },
         );
-        host.state_mut().voters.insert(
-            accounts[2],
-            Voter {
-                voting_key: g_x3.to_bytes().to_vec(),
-                ..Default::default()
-            },
+
+        claim_eq!(
+            view_phase(&ctx, &host),
+            Ok(types::VotingPhase::Commit),
+            "Should be in commit phase"
         );
 
-        let list_of_voting_keys = vec![g_x1.clone(), g_x2.clone(), g_x3.clone()];
+        let voting_keys = match view_voting_keys(&ctx, &host) {
+            Ok(v) => v.voting_keys,
+            Err(e) => fail!("Viewing voting keys failed: {:?}", e),
+        };
+        claim_eq!(
+            voting_keys,
+            vec![
+                (accounts[0], g_x1.to_bytes().to_vec()),
+                (accounts[1], g_x2.to_bytes().to_vec()),
+                (accounts[2], g_x3.to_bytes().to_vec()),
+            ],
+            "Voting keys should be in the order of the voters"
+        );
+
+        let voters = match view_voters(&ctx, &host) {
+            Ok(v) => v.voters,
+            Err(e) => fail!("Viewing voters failed: {:?}", e),
+        };
+        claim_eq!(
+            voters,
+            vec![
+                (accounts[0], types::VoterStatus::Registered),
+                (accounts[1], types::VoterStatus::Committed),
+                (accounts[2], types::VoterStatus::Registered),
+            ],
+            "Wrong status of voters"
+        );
 
-        let g_y1 = off_chain::compute_reconstructed_key(&list_of_voting_keys, g_x1.clone());
-        let g_y2 = off_chain::compute_reconstructed_key(&list_of_voting_keys, g_x2.clone());
-        let g_y3 = off_chain::compute_reconstructed_key(&list_of_voting_keys, g_x3.clone());
+        claim_eq!(
+            view_result(&ctx, &host),
+            Ok(Vec::new()),
+            "Result should be empty, since voting is not done"
+        );
+    }
+
+    #[concordium_test]
+    fn test_refund_deposits_all_honest() {
+        let (accounts, vote_config, _) =
+            test_utils::setup_test_config(3, Amount::from_micro_ccd(1));
 
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Registration);
+
+        let (_ctx, mut host) =
+            test_utils::setup_receive_context(None, accounts[0], state, state_builder);
+
+        // Simulate that only 2 voters registered, so the vote aborts in the registration phase
         host.state_mut().voters.insert(
             accounts[0],
             Voter {
-                reconstructed_key: g_y1.to_bytes().to_vec(),
-                commitment: off_chain::commit_to_vote(&x1, &g_y1, ProjectivePoint::IDENTITY),
-                vote: ((g_y1.clone() * x1.clone()) + ProjectivePoint::IDENTITY)
-                    .to_bytes()
-                    .to_vec(),
+                voting_key: off_chain::create_votingkey_pair().1.to_bytes().to_vec(),
                 ..Default::default()
             },
         );
         host.state_mut().voters.insert(
             accounts[1],
             Voter {
-                reconstructed_key: g_y2.to_bytes().to_vec(),
-                commitment: off_chain::commit_to_vote(&x2, &g_y2, ProjectivePoint::IDENTITY),
-                vote: ((g_y2.clone() * x2.clone()) + ProjectivePoint::IDENTITY)
-                    .to_bytes()
-                    .to_vec(),
-                ..Default::default()
-            },
-        );
-        host.state_mut().voters.insert(
-            accounts[2],
-            Voter {
-                reconstructed_key: g_y3.to_bytes().to_vec(),
-                commitment: off_chain::commit_to_vote(&x3, &g_y3, ProjectivePoint::GENERATOR),
-                vote: ((g_y3.clone() * x3.clone()) + ProjectivePoint::GENERATOR)
-                    .to_bytes()
-                    .to_vec(),
+                voting_key: off_chain::create_votingkey_pair().1.to_bytes().to_vec(),
                 ..Default::default()
             },
         );
 
-        // Deposit is 1 and there are 3 accounts thus balance is 3
-        host.set_self_balance(Amount::from_micro_ccd(3));
+        // Deposit is 1 and there are 2 registered accounts thus balance is 2
+        host.set_self_balance(Amount::from_micro_ccd(2));
 
-        let result = refund_deposits(accounts[0], &mut host);
+        let result = settle_deposits(Some(accounts[0]), &mut host);
 
         claim!(
             result.is_ok(),
@@ -736,7 +1335,20 @@ mod tests {
             host.self_balance(),
             Amount::zero(),
             "All deposits should have been refunded"
-        )
+        );
+        claim_eq!(
+            host.state().settlement,
+            Some(Settlement {
+                refunds: vec![
+                    (accounts[0], Amount::from_micro_ccd(1)),
+                    (accounts[1], Amount::from_micro_ccd(1))
+                ],
+                forfeits: Vec::new(),
+                caller_reward: None,
+                remaining: Amount::zero(),
+            }),
+            "Settlement should only refund the registered voters"
+        );
     }
 
     #[concordium_test]
@@ -776,7 +1388,7 @@ mod tests {
         // Deposit is 1 and there are 3 accounts thus balance is 3
         host.set_self_balance(Amount::from_micro_ccd(3));
 
-        let result = refund_deposits(accounts[2], &mut host);
+        let result = settle_deposits(Some(accounts[2]), &mut host);
 
         claim!(
             result.is_ok(),
@@ -787,7 +1399,24 @@ mod tests {
             host.self_balance(),
             Amount::from_micro_ccd(3),
             "No deposits should be refunded"
-        )
+        );
+
+        let settlement = host.state().settlement.clone().unwrap();
+        claim_eq!(
+            settlement.forfeits.len(),
+            3,
+            "All deposits should be forfeited"
+        );
+        claim_eq!(
+            settlement.caller_reward,
+            None,
+            "Stalling caller should not be rewarded"
+        );
+        claim_eq!(
+            settlement.remaining,
+            Amount::from_micro_ccd(3),
+            "All forfeited deposits should remain for the beneficiary"
+        );
     }
 
     #[concordium_test]
@@ -852,7 +1481,7 @@ mod tests {
         // Deposit is 1 and there are 3 accounts thus balance is 3
         host.set_self_balance(Amount::from_micro_ccd(3));
 
-        let result = refund_deposits(accounts[1], &mut host);
+        let result = settle_deposits(Some(accounts[1]), &mut host);
 
         claim!(
             result.is_ok(),
@@ -862,22 +1491,165 @@ mod tests {
         claim_eq!(
             host.self_balance(),
             Amount::from_micro_ccd(0),
-            "Account[1] should get extra deposit for catching dishonest voter"
+            "Account[1] should get the forfeited deposit for catching dishonest voter"
+        );
+        claim_eq!(
+            host.state().settlement,
+            Some(Settlement {
+                refunds: vec![
+                    (accounts[0], Amount::from_micro_ccd(1)),
+                    (accounts[1], Amount::from_micro_ccd(1))
+                ],
+                forfeits: vec![(accounts[2], Amount::from_micro_ccd(1))],
+                caller_reward: Some((accounts[1], Amount::from_micro_ccd(1))),
+                remaining: Amount::zero(),
+            }),
+            "Voters who voted should be refunded"
         );
 
         //------------------------------------ Run again where dishonest is sender ---------------------
 
-        // Deposit is 1 and there are 3 accounts thus balance is 3
         host.set_self_balance(Amount::from_micro_ccd(3));
 
         // Dishonest voter is sender of refund request
-        let result = refund_deposits(accounts[2], &mut host);
+        let result = settle_deposits(Some(accounts[2]), &mut host);
 
         claim!(
             result.is_ok(),
             "Contract receive failed, but should not have"
         );
 
-        claim_eq!(host.self_balance(), Amount::from_micro_ccd(1), "Account[2] should not get deposit for catching dishonest voter, since they are dishonest")
+        claim_eq!(host.self_balance(), Amount::from_micro_ccd(1), "Account[2] should not get deposit for catching dishonest voter, since they are dishonest");
+        claim_eq!(
+            host.state().settlement.clone().unwrap().remaining,
+            Amount::from_micro_ccd(1),
+            "Forfeited deposit should remain for the beneficiary"
+        );
+    }
+
+    #[concordium_test]
+    fn test_refund_deposits_recovery() {
+        let (accounts, vote_config, _) =
+            test_utils::setup_test_config(4, Amount::from_micro_ccd(1));
+
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Recovery);
+
+        let (_ctx, mut host) =
+            test_utils::setup_receive_context(None, accounts[0], state, state_builder);
+
+        // Simulate that voter 4 dropped out, and that only voter 1 sent their recovery key before the recovery timeout
+        for account in &accounts[0..3] {
+            host.state_mut().voters.insert(
+                *account,
+                Voter {
+                    voting_key: off_chain::create_votingkey_pair().1.to_bytes().to_vec(),
+                    vote: off_chain::create_votingkey_pair().1.to_bytes().to_vec(),
+                    ..Default::default()
+                },
+            );
+        }
+        if let Some(mut voter) = host.state_mut().voters.get_mut(&accounts[0]) {
+            voter.recovery_key = off_chain::create_votingkey_pair().1.to_bytes().to_vec();
+        }
+
+        // Deposit is 1 and there are 4 accounts thus balance is 4
+        host.set_self_balance(Amount::from_micro_ccd(4));
+
+        // Voter 2 stalled the recovery round, so they cannot be rewarded for aborting it
+        let result = settle_deposits(Some(accounts[1]), &mut host);
+
+        claim!(
+            result.is_ok(),
+            "Contract receive failed, but should not have"
+        );
+
+        claim_eq!(
+            host.state().settlement,
+            Some(Settlement {
+                refunds: vec![(accounts[0], Amount::from_micro_ccd(1))],
+                forfeits: vec![
+                    (accounts[1], Amount::from_micro_ccd(1)),
+                    (accounts[2], Amount::from_micro_ccd(1)),
+                    (accounts[3], Amount::from_micro_ccd(1))
+                ],
+                caller_reward: None,
+                remaining: Amount::from_micro_ccd(3),
+            }),
+            "Only the voter who sent their recovery key should be refunded"
+        );
+        claim_eq!(
+            host.self_balance(),
+            Amount::from_micro_ccd(3),
+            "Forfeited deposits should remain for the beneficiary"
+        );
+    }
+
+    #[concordium_test]
+    fn test_sweep() {
+        let (accounts, vote_config, _) =
+            test_utils::setup_test_config(3, Amount::from_micro_ccd(1));
+
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Vote);
+
+        let (mut ctx, mut host) =
+            test_utils::setup_receive_context(None, test_utils::BENEFICIARY, state, state_builder);
+
+        // Deposit is 1 and none of the 3 voters voted, thus balance is 3
+        host.set_self_balance(Amount::from_micro_ccd(3));
+
+        // Cannot sweep before the vote has aborted
+        let result = sweep(&ctx, &mut host);
+        claim_eq!(
+            result,
+            Err(types::SweepError::NotSettled),
+            "Beneficiary should not be able to sweep before the deposits are settled"
+        );
+
+        let result = settle_deposits(Some(accounts[2]), &mut host);
+        claim!(
+            result.is_ok(),
+            "Contract receive failed, but should not have"
+        );
+        host.state_mut().voting_phase = types::VotingPhase::Abort;
+
+        // Only the beneficiary can sweep
+        ctx.set_sender(Address::Account(accounts[0]));
+        let result = sweep(&ctx, &mut host);
+        claim_eq!(
+            result,
+            Err(types::SweepError::UnauthorizedBeneficiary),
+            "Voter should not be able to sweep"
+        );
+
+        ctx.set_sender(Address::Account(test_utils::BENEFICIARY));
+        let result = sweep(&ctx, &mut host);
+        claim!(
+            result.is_ok(),
+            "Contract receive failed, but should not have"
+        );
+        claim_eq!(
+            host.self_balance(),
+            Amount::zero(),
+            "All forfeited deposits should have been swept"
+        );
+        claim!(
+            host.transfer_occurred(&test_utils::BENEFICIARY, Amount::from_micro_ccd(3)),
+            "Beneficiary should have received the forfeited deposits"
+        );
+        claim_eq!(
+            view_settlement(&ctx, &host).unwrap().unwrap().remaining,
+            Amount::zero(),
+            "Nothing should remain after sweeping"
+        );
+
+        // Cannot sweep twice
+        let result = sweep(&ctx, &mut host);
+        claim_eq!(
+            result,
+            Err(types::SweepError::NothingToSweep),
+            "Beneficiary should not be able to sweep twice"
+        );
     }
 }
@@ -3,6 +3,16 @@
 use crate::{types::VotingPhase, VoteConfig, VotingState};
 use concordium_std::*;
 use test_infrastructure::*;
+use util::ChallengeContext;
+
+/// Address of the contract instance in the tests
+pub const CONTRACT_ADDRESS: ContractAddress = ContractAddress {
+    index: 0,
+    subindex: 0,
+};
+
+/// Account that can sweep the forfeited deposits in the tests
+pub const BENEFICIARY: AccountAddress = AccountAddress([255; 32]);
 
 /// Creates a list of voter accounts and a config for testing
 #[concordium_cfg_test]
@@ -19,16 +29,22 @@ pub fn setup_test_config(
         voters.push(AccountAddress([i as u8; 32]))
     }
 
-    let merkle_tree = off_chain::create_merkle_tree(&voters);
+    // Every voter has a weight of 1
+    let merkle_tree =
+        off_chain::create_merkle_tree(&voters.iter().map(|voter| (*voter, 1)).collect());
 
     let vote_config = VoteConfig {
         merkle_root: merkle_tree.root().unwrap(),
         merkle_leaf_count: number_of_accounts,
+        max_total_weight: number_of_accounts as u64,
         voting_question: "Vote for x".to_string(),
+        voting_options: vec!["Yes".to_string(), "No".to_string()],
         deposit,
+        beneficiary: BENEFICIARY,
         registration_timeout: Timestamp::from_timestamp_millis(100),
         commit_timeout: Timestamp::from_timestamp_millis(200),
         vote_timeout: Timestamp::from_timestamp_millis(300),
+        recovery_timeout: Timestamp::from_timestamp_millis(400),
     };
 
     (voters, vote_config, merkle_tree)
@@ -63,8 +79,9 @@ pub fn setup_state(
     let state = VotingState {
         config: vote_config,
         voting_phase: phase,
-        voting_result: (-1, -1),
+        voting_result: Vec::new(),
         voters,
+        settlement: None,
     };
 
     (state, state_builder)
@@ -90,9 +107,15 @@ pub fn setup_receive_context(
     };
 
     ctx.set_sender(Address::Account(sender));
+    ctx.set_self_address(CONTRACT_ADDRESS);
     host.set_self_balance(Amount::from_micro_ccd(0));
     ctx.metadata_mut()
         .set_slot_time(Timestamp::from_timestamp_millis(1));
 
     (ctx, host)
 }
+
+/// Creates the challenge context of a proof sent by the sender to the test contract in the given phase
+pub fn challenge_context(sender: AccountAddress, phase: VotingPhase) -> ChallengeContext {
+    ChallengeContext::new(CONTRACT_ADDRESS, sender, &phase)
+}
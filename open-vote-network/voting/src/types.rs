@@ -6,6 +6,7 @@ use concordium_std::*;
 pub type RegistrationTimeout = Timestamp;
 pub type CommitTimeout = Timestamp;
 pub type VoteTimeout = Timestamp;
+pub type RecoveryTimeout = Timestamp;
 
 /// Enums
 
@@ -14,10 +15,19 @@ pub enum VotingPhase {
     Registration,
     Commit,
     Vote,
+    Recovery,
     Result,
     Abort,
 }
 
+#[derive(Serialize, PartialEq, SchemaType, Debug, Copy, Clone)]
+pub enum VoterStatus {
+    Registered,
+    Committed,
+    Voted,
+    Recovered,
+}
+
 #[derive(Debug, PartialEq, Eq, Reject)]
 pub enum SetupError {
     // Failed parsing the parameter
@@ -28,10 +38,17 @@ pub enum SetupError {
     InvalidPrecommitTimeout,
     InvalidCommitTimeout,
     InvalidVoteTimeout,
+    InvalidRecoveryTimeout,
     // Deposits should be >=0
     NegativeDeposit,
     // Must have atleast 3 voters
     InvalidNumberOfVoters,
+    // Must have atleast 2 options
+    InvalidNumberOfOptions,
+    // The largest possible tally must be small enough for result to find it on-chain
+    TallyTooLarge,
+    // Maximum total weight must allow every voter a weight of at least 1, and the base (max total weight + 1) must fit in a u64
+    InvalidMaxTotalWeight,
 }
 
 #[derive(Debug, PartialEq, Eq, Reject)]
@@ -61,6 +78,12 @@ pub enum RegisterError {
     InvalidZKP,
     // Invalid voting key (not valid ECC point)
     InvalidVotingKey,
+    // Merkle proof could not be parsed
+    InvalidMerkleProof,
+    // Weight must be positive
+    InvalidWeight,
+    // Total weight of the registered voters would exceed the maximum total weight
+    MaxTotalWeightExceeded,
 }
 
 #[derive(Debug, PartialEq, Eq, Reject)]
@@ -80,6 +103,8 @@ pub enum CommitError {
     VoterNotFound,
     // Something in CommitMessage is just an empty vector
     InvalidCommitMessage,
+    // Invalid reconstructed key (not valid ECC point)
+    InvalidReconstructedKey,
 }
 
 #[derive(Debug, PartialEq, Eq, Reject)]
@@ -106,6 +131,36 @@ pub enum VoteError {
     VoteCommitmentMismatch,
     // Voter already voted
     AlreadyVoted,
+    // Stored reconstructed key is not a valid ECC point (voter did not commit)
+    InvalidReconstructedKey,
+    // Invalid vote (not valid ECC point)
+    InvalidVote,
+}
+
+#[derive(Debug, PartialEq, Eq, Reject)]
+pub enum RecoveryError {
+    // Failed parsing the parameter
+    #[from(ParseError)]
+    ParseParams,
+    // Failed doing transfer
+    #[from(TransferError)]
+    DoTransfer,
+    // Only allow voters who voted
+    UnauthorizedVoter,
+    // Sender cannot be contract
+    ContractSender,
+    // Not in Recovery phase
+    NotRecoveryPhase,
+    // Recovery phase has ended
+    PhaseEnded,
+    // Voter already sent their recovery key
+    AlreadyRecovered,
+    // Invalid recovery key (not valid ECC point)
+    InvalidRecoveryKey,
+    // A stored voting key needed for the recovery is not a valid ECC point
+    InvalidVotingKey,
+    // ZKP not correct
+    InvalidZKP,
 }
 
 #[derive(Debug, PartialEq, Eq, Reject)]
@@ -115,6 +170,10 @@ pub enum ResultError {
     ParseParams,
     // Not in result phase
     NotResultPhase,
+    // Tally of the votes could not be found within the number of voters
+    TallyNotFound,
+    // A stored vote or recovery key is not a valid ECC point
+    InvalidVote,
 }
 
 #[derive(Debug, PartialEq, Eq, Reject)]
@@ -127,3 +186,21 @@ pub enum ChangeError {
     #[from(TransferError)]
     TransferRefund,
 }
+
+#[derive(Debug, PartialEq, Eq, Reject)]
+pub enum SweepError {
+    // Failed parsing the parameter
+    #[from(ParseError)]
+    ParseParams,
+    // Failed doing transfer
+    #[from(TransferError)]
+    DoTransfer,
+    // Sender cannot be contract
+    ContractSender,
+    // Only the beneficiary can sweep
+    UnauthorizedBeneficiary,
+    // Vote has not ended, so no deposits have been settled
+    NotSettled,
+    // Remaining forfeited deposits have already been swept (or there were none)
+    NothingToSweep,
+}